@@ -1,14 +1,25 @@
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::Frame;
-use std::time::{SystemTime, UNIX_EPOCH};
-use uuid::Uuid;
+use std::collections::HashMap;
+use std::time::Duration;
 
+use crate::auth::{self, Input};
+use crate::capture;
 use crate::components::{
-    AppLayout, Component, Content, ContentAction, Footer, Header, HeaderAction, ProjectTab,
-    Sidebar, SidebarAction,
+    AppLayout, Component, Content, ContentAction, EnvironmentAction, EnvironmentPanel, Footer,
+    Header, HeaderAction, ProjectTab, SettingsAction, SettingsPanel, Sidebar, SidebarAction,
+};
+use crate::http::{ClientOptions, HttpAction, RequestWorker};
+use crate::oauth;
+use crate::persistence::{
+    has_locked_secrets, is_encrypted, Folder, GlobalVariables, ProjectData, ProjectUpdate,
+    RequestData, ResponseData, Storage, StorageError, TlsSettings,
 };
-use crate::persistence::{ProjectData, Storage};
 use crate::theme::Theme;
+use crate::variables::{self, Scopes};
+
+const LOCKED_SECRETS: &str =
+    "Secrets are locked: set RURL_PASSPHRASE or secrets.key_file to the key they were saved with";
 
 pub enum AppAction {
     Noop,
@@ -28,6 +39,18 @@ enum Mode {
     TabMode,
     CreateProject,
     EditRequest,
+    History,
+    Name,
+    Environments,
+    Settings,
+}
+
+#[allow(clippy::large_enum_variant)]
+enum NameTarget {
+    NewRequest(Option<String>),
+    RenameRequest(RequestData),
+    NewFolder(Option<String>),
+    RenameFolder(Folder),
 }
 
 struct UiComponents {
@@ -35,15 +58,20 @@ struct UiComponents {
     header: Header,
     sidebar: Sidebar,
     content: Content,
+    environments: EnvironmentPanel,
+    settings: SettingsPanel,
     footer: Footer,
 }
 
 pub struct App {
-    storage: Storage,
+    storage: Box<dyn Storage>,
+    worker: RequestWorker,
     layout: AppLayout,
     header: Header,
     sidebar: Sidebar,
     content: Content,
+    environments: EnvironmentPanel,
+    settings: SettingsPanel,
     footer: Footer,
     should_render: bool,
     tick_count: u32,
@@ -52,21 +80,40 @@ pub struct App {
     mode: Mode,
     current_project: Option<ProjectData>,
     projects: Vec<ProjectData>,
+    /// Variables shared by every project, kept outside of them in `storage`.
+    global_variables: GlobalVariables,
+    /// TLS settings from the config file, under every project's.
+    global_tls: TlsSettings,
     project_name_buffer: String,
+    name_buffer: String,
+    /// What the text typed in `Mode::Name` will be used for.
+    name_target: Option<NameTarget>,
     theme: Theme,
 }
 
 impl App {
-    pub fn new(mut storage: Storage, theme: Theme) -> Self {
-        let (projects, current_project) = Self::init_projects(&mut storage);
-        let ui = Self::init_ui(&projects, &current_project);
+    pub fn new(
+        mut storage: Box<dyn Storage>,
+        theme: Theme,
+        global_tls: TlsSettings,
+    ) -> Result<Self, StorageError> {
+        let (projects, current_project) = Self::init_projects(storage.as_mut())?;
+        let global_variables = storage.load_global_variables()?;
+        let mut ui = Self::init_ui(&projects, &current_project, &global_variables);
+        let locked_globals = global_variables.variables.values().any(|v| is_encrypted(v));
+        if locked_globals || current_project.as_ref().is_some_and(has_locked_secrets) {
+            ui.footer.set_status(LOCKED_SECRETS.to_string());
+        }
 
-        App {
+        Ok(App {
             storage,
+            worker: RequestWorker::new(),
             layout: ui.layout,
             header: ui.header,
             sidebar: ui.sidebar,
             content: ui.content,
+            environments: ui.environments,
+            settings: ui.settings,
             footer: ui.footer,
             should_render: true,
             tick_count: 0,
@@ -75,22 +122,32 @@ impl App {
             mode: Mode::Normal,
             current_project,
             projects,
+            global_variables,
+            global_tls,
             project_name_buffer: String::new(),
+            name_buffer: String::new(),
+            name_target: None,
             theme,
-        }
+        })
     }
 
-    fn init_projects(storage: &mut Storage) -> (Vec<ProjectData>, Option<ProjectData>) {
-        let projects = storage.list_projects();
-        let first_project = projects.first();
-        let current_project = first_project
-            .map(|p| storage.load_project(&p.id).unwrap_or(None))
-            .flatten();
+    fn init_projects(
+        storage: &mut dyn Storage,
+    ) -> Result<(Vec<ProjectData>, Option<ProjectData>), StorageError> {
+        let projects = storage.list_projects()?;
+        let current_project = match projects.first() {
+            Some(project) => storage.load_project(&project.id)?,
+            None => None,
+        };
 
-        (projects, current_project)
+        Ok((projects, current_project))
     }
 
-    fn init_ui(projects: &Vec<ProjectData>, current_project: &Option<ProjectData>) -> UiComponents {
+    fn init_ui(
+        projects: &Vec<ProjectData>,
+        current_project: &Option<ProjectData>,
+        global_variables: &GlobalVariables,
+    ) -> UiComponents {
         let project_tabs: Vec<ProjectTab> = projects
             .iter()
             .map(|p| ProjectTab {
@@ -98,15 +155,30 @@ impl App {
             })
             .collect();
         let layout = AppLayout::new();
-        let header = Header::new(project_tabs);
+        let mut header = Header::new(project_tabs);
         let mut sidebar = Sidebar::new();
+        let mut environments = EnvironmentPanel::new();
+        let mut settings = SettingsPanel::new();
+        let mut content = Content::new();
 
         // Set initial requests if we have a current project
         if let Some(project) = current_project {
-            sidebar.set_requests(project.requests.clone());
+            sidebar.set_items(project.folders.clone(), project.requests.clone());
+            environments.set_environments(
+                project.environments.clone(),
+                project.active_environment.clone(),
+            );
+            environments.set_shared_variables(
+                global_variables.clone(),
+                project.variables.clone(),
+                project.secrets.clone(),
+            );
+            header.set_environment(project.active_environment.clone());
+            settings.set_settings(project.settings.clone(), project.environments.clone());
+            content.set_settings(project.settings.clone());
         }
+        content.set_scopes(Self::scopes_for(global_variables, current_project.as_ref()));
 
-        let content = Content::new();
         let footer = Footer::new();
 
         UiComponents {
@@ -114,12 +186,21 @@ impl App {
             header,
             sidebar,
             content,
+            environments,
+            settings,
             footer,
         }
     }
 
     fn update_projects_list(&mut self) {
-        self.projects = self.storage.list_projects();
+        self.projects = match self.storage.list_projects() {
+            Ok(projects) => projects,
+            Err(e) => {
+                self.footer
+                    .set_status(format!("Failed to list projects: {}", e));
+                return;
+            }
+        };
         self.current_project = match self.projects.is_empty() {
             true => None,
             false => self
@@ -127,26 +208,64 @@ impl App {
                 .load_project(&self.projects[0].id)
                 .unwrap_or(None),
         };
+        self.show_environments();
+    }
+
+    /// Shows the current project's environments, variables and settings in
+    /// their panels and the header, and hands the variables in scope and the
+    /// project defaults to the request view.
+    fn show_environments(&mut self) {
+        let (environments, active) = match &self.current_project {
+            Some(project) => (
+                project.environments.clone(),
+                project.active_environment.clone(),
+            ),
+            None => (Vec::new(), None),
+        };
+        self.header.set_environment(active.clone());
+        self.environments.set_environments(environments, active);
+        let (variables, secrets) = match &self.current_project {
+            Some(project) => (project.variables.clone(), project.secrets.clone()),
+            None => Default::default(),
+        };
+        self.environments
+            .set_shared_variables(self.global_variables.clone(), variables, secrets);
+        let (settings, environments) = match &self.current_project {
+            Some(project) => (project.settings.clone(), project.environments.clone()),
+            None => (Default::default(), Vec::new()),
+        };
+        self.settings.set_settings(settings.clone(), environments);
+        self.content.set_settings(settings);
+        self.content.set_scopes(self.scopes());
+    }
+
+    fn scopes(&self) -> Scopes {
+        Self::scopes_for(&self.global_variables, self.current_project.as_ref())
+    }
+
+    fn scopes_for(global: &GlobalVariables, project: Option<&ProjectData>) -> Scopes {
+        let environment = project.and_then(|p| p.active_environment());
+        Scopes {
+            global: global.variables.clone(),
+            project: project.map(|p| p.variables.clone()).unwrap_or_default(),
+            environment: environment
+                .map(|env| env.variables.clone())
+                .unwrap_or_default(),
+            secrets: global
+                .secrets
+                .iter()
+                .chain(project.into_iter().flat_map(|p| &p.secrets))
+                .chain(environment.into_iter().flat_map(|env| &env.secrets))
+                .cloned()
+                .collect(),
+        }
     }
 
     fn handle_project_creation(&mut self, key: KeyCode) {
         match key {
             KeyCode::Enter => {
                 if !self.project_name_buffer.is_empty() {
-                    let project_data = ProjectData {
-                        name: self.project_name_buffer.clone(),
-                        id: Uuid::new_v4().to_string(),
-                        requests: Vec::new(),
-                        environments: Vec::new(),
-                        created_at: SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs() as i64,
-                        updated_at: SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs() as i64,
-                    };
+                    let project_data = ProjectData::new(self.project_name_buffer.clone());
                     let ok = self.storage.save_project(&project_data);
                     match ok {
                         Ok(_) => {
@@ -185,6 +304,89 @@ impl App {
         }
     }
 
+    fn start_naming(&mut self, target: NameTarget) {
+        if self.current_project.is_none() {
+            self.footer
+                .set_status("Create a project before adding requests".to_string());
+            return;
+        }
+
+        self.name_buffer = match &target {
+            NameTarget::RenameRequest(request) => request.name.clone(),
+            NameTarget::RenameFolder(folder) => folder.name.clone(),
+            NameTarget::NewRequest(_) | NameTarget::NewFolder(_) => String::new(),
+        };
+        self.name_target = Some(target);
+        self.mode = Mode::Name;
+        self.update_name_prompt();
+        self.update_footer_hints();
+        self.should_render = true;
+    }
+
+    fn update_name_prompt(&mut self) {
+        let prompt = match &self.name_target {
+            Some(NameTarget::RenameRequest(request)) => format!("Rename '{}' to:", request.name),
+            Some(NameTarget::RenameFolder(folder)) => format!("Rename '{}' to:", folder.name),
+            Some(NameTarget::NewFolder(_)) => "New folder name:".to_string(),
+            Some(NameTarget::NewRequest(_)) | None => "New request name:".to_string(),
+        };
+        self.footer
+            .set_status(format!("{} {}", prompt, self.name_buffer));
+    }
+
+    fn handle_naming(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Enter => {
+                let name = self.name_buffer.trim().to_string();
+                if let Some(target) = self.name_target.take().filter(|_| !name.is_empty()) {
+                    match target {
+                        NameTarget::NewRequest(folder_id) => {
+                            self.add_request(RequestData {
+                                folder_id,
+                                ..RequestData::new(name)
+                            });
+                        }
+                        NameTarget::RenameRequest(request) => {
+                            self.update_request(RequestData { name, ..request });
+                        }
+                        NameTarget::NewFolder(parent_id) => {
+                            let folder = Folder::new(name, parent_id);
+                            let id = folder.id.clone();
+                            self.apply_project_update(ProjectUpdate::AddFolder(folder));
+                            self.sidebar.select(&id);
+                        }
+                        NameTarget::RenameFolder(folder) => {
+                            self.apply_project_update(ProjectUpdate::UpdateFolder(Folder {
+                                name,
+                                ..folder
+                            }));
+                        }
+                    }
+                }
+
+                self.name_buffer.clear();
+                self.name_target = None;
+                self.mode = Mode::Normal;
+                self.update_footer_hints();
+            }
+            KeyCode::Esc => {
+                self.name_buffer.clear();
+                self.name_target = None;
+                self.mode = Mode::Normal;
+                self.update_footer_hints();
+            }
+            KeyCode::Char(c) => {
+                self.name_buffer.push(c);
+                self.update_name_prompt();
+            }
+            KeyCode::Backspace => {
+                self.name_buffer.pop();
+                self.update_name_prompt();
+            }
+            _ => {}
+        }
+    }
+
     fn update_footer_hints(&mut self) {
         let mode_str = match self.mode {
             Mode::Normal => "NORMAL",
@@ -192,6 +394,10 @@ impl App {
             Mode::TabMode => "TAB",
             Mode::CreateProject => "CREATE",
             Mode::EditRequest => "EDIT",
+            Mode::History => "HISTORY",
+            Mode::Name => "NAME",
+            Mode::Environments => "ENV",
+            Mode::Settings => "SETTINGS",
         };
 
         self.footer.set_mode(mode_str.to_string());
@@ -200,34 +406,30 @@ impl App {
     fn handle_component_actions(&mut self, event: &Event) {
         match self.mode {
             Mode::Normal => {
-                if let SidebarAction::Selected(request) =
-                    self.sidebar.tick(Some(event), self.tick_count)
-                {
-                    self.content.set_request(request);
-                    self.should_render = true;
-                }
+                let sidebar_action = self.sidebar.tick(Some(event), self.tick_count);
+                self.handle_sidebar_action(sidebar_action);
 
-                if let HeaderAction::TabChanged(tab_index) =
-                    self.header.tick(Some(event), self.tick_count)
-                {
-                    self.handle_tab_change(tab_index);
+                match self.header.tick(Some(event), self.tick_count) {
+                    HeaderAction::TabChanged(tab_index) => {
+                        self.handle_tab_change(tab_index);
+                    }
+                    HeaderAction::OpenEnvironments => {
+                        self.open_environments();
+                    }
+                    _ => {}
                 }
             }
+            Mode::Environments => {
+                let action = self.environments.tick(Some(event), self.tick_count);
+                self.handle_environment_action(action);
+            }
+            Mode::Settings => {
+                let action = self.settings.tick(Some(event), self.tick_count);
+                self.handle_settings_action(action);
+            }
             Mode::EditRequest => match self.content.tick(Some(event), self.tick_count) {
                 ContentAction::RequestUpdated(request) => {
-                    if let Some(project) = &mut self.current_project {
-                        if let Some(existing_request) =
-                            project.requests.iter_mut().find(|r| r.name == request.name)
-                        {
-                            *existing_request = request.clone();
-                            if let Err(e) = self.storage.save_project(project) {
-                                self.footer
-                                    .set_status(format!("Failed to save project: {}", e));
-                            }
-                            self.sidebar.set_requests(project.requests.clone());
-                            self.content.set_request(request);
-                        }
-                    }
+                    self.update_request(request);
                 }
                 ContentAction::ContentUpdated => {
                     self.should_render = true;
@@ -248,52 +450,20 @@ impl App {
                         self.mode = Mode::Normal;
                         self.update_footer_hints();
                     }
-                    _ => {}
-                }
-
-                match sidebar_action {
-                    SidebarAction::Selected(request) => {
-                        self.content.set_request(request);
-                        self.should_render = true;
-                    }
-                    SidebarAction::EditRequest => {
-                        self.content.enter_edit_mode();
-                        self.mode = Mode::EditRequest;
-                        self.should_render = true;
-                    }
-                    SidebarAction::ProjectUpdate(update) => {
-                        if let Some(project) = &mut self.current_project {
-                            project.apply_update(update);
-                            if let Err(e) = self.storage.save_project(project) {
-                                self.footer
-                                    .set_status(format!("Failed to save project: {}", e));
-                            }
-                            self.sidebar.set_requests(project.requests.clone());
-                            self.should_render = true;
-                        }
+                    HeaderAction::OpenEnvironments => {
+                        self.open_environments();
                     }
                     _ => {}
                 }
 
+                self.handle_sidebar_action(sidebar_action);
+
                 match content_action {
                     ContentAction::ContentUpdated => {
                         self.should_render = true;
                     }
                     ContentAction::RequestUpdated(request) => {
-                        if let Some(project) = &mut self.current_project {
-                            if let Some(existing_request) =
-                                project.requests.iter_mut().find(|r| r.name == request.name)
-                            {
-                                *existing_request = request.clone();
-                                if let Err(e) = self.storage.save_project(project) {
-                                    self.footer
-                                        .set_status(format!("Failed to save project: {}", e));
-                                }
-                                self.sidebar.set_requests(project.requests.clone());
-                                self.content.set_request(request);
-                                self.should_render = true;
-                            }
-                        }
+                        self.update_request(request);
                     }
                     _ => {}
                 }
@@ -301,6 +471,100 @@ impl App {
         }
     }
 
+    fn handle_sidebar_action(&mut self, action: SidebarAction) {
+        match action {
+            SidebarAction::Selected(request) => {
+                self.content.set_request(request);
+                self.should_render = true;
+            }
+            SidebarAction::EditRequest => {
+                self.content.enter_edit_mode();
+                self.mode = Mode::EditRequest;
+                self.should_render = true;
+            }
+            SidebarAction::ProjectUpdate(ProjectUpdate::AddRequest(request)) => {
+                self.add_request(request);
+            }
+            SidebarAction::ProjectUpdate(update) => {
+                self.apply_project_update(update);
+            }
+            SidebarAction::NewRequest(folder_id) => {
+                self.start_naming(NameTarget::NewRequest(folder_id));
+            }
+            SidebarAction::RenameRequest(request) => {
+                self.start_naming(NameTarget::RenameRequest(request));
+            }
+            SidebarAction::NewFolder(parent_id) => {
+                self.start_naming(NameTarget::NewFolder(parent_id));
+            }
+            SidebarAction::RenameFolder(folder) => {
+                self.start_naming(NameTarget::RenameFolder(folder));
+            }
+            SidebarAction::Updated => {
+                self.should_render = true;
+            }
+            SidebarAction::Noop => {}
+        }
+    }
+
+    fn apply_project_update(&mut self, update: ProjectUpdate) {
+        if let Some(project) = &mut self.current_project {
+            project.apply_update(update.clone());
+            if let Err(e) = self.storage.apply_update(project, &update) {
+                self.footer
+                    .set_status(format!("Failed to save project: {}", e));
+            }
+            self.sidebar
+                .set_items(project.folders.clone(), project.requests.clone());
+            // Keep the editor's copy in step, so a later edit does not undo
+            // the move or carry stale fields.
+            let changed_request = match &update {
+                ProjectUpdate::UpdateRequest(RequestData { id, .. }) => Some(id),
+                ProjectUpdate::MoveRequest { id, .. } => {
+                    self.sidebar.select(id);
+                    Some(id)
+                }
+                ProjectUpdate::DeleteRequest(id)
+                    if self.content.request().is_some_and(|r| &r.id == id) =>
+                {
+                    self.content.clear_request();
+                    None
+                }
+                _ => None,
+            };
+            if let Some(stored) =
+                changed_request.and_then(|id| project.requests.iter().find(|r| &r.id == id))
+            {
+                self.content.refresh_request(stored.clone());
+            }
+            self.should_render = true;
+        }
+        self.show_environments();
+    }
+
+    /// Adds `request` to the current project and selects it.
+    fn add_request(&mut self, request: RequestData) {
+        if self.current_project.is_none() {
+            return;
+        }
+        let id = request.id.clone();
+        self.apply_project_update(ProjectUpdate::AddRequest(request.clone()));
+        self.sidebar.select(&id);
+        self.content.set_request(request);
+    }
+
+    fn update_request(&mut self, request: RequestData) {
+        let exists = self
+            .current_project
+            .as_ref()
+            .is_some_and(|p| p.requests.iter().any(|r| r.id == request.id));
+        if exists {
+            self.apply_project_update(ProjectUpdate::UpdateRequest(request.clone()));
+            self.content.set_request(request);
+        }
+        self.should_render = true;
+    }
+
     fn handle_normal_mode(&mut self, key: KeyCode) -> AppAction {
         match key {
             KeyCode::Char(' ') => {
@@ -313,16 +577,360 @@ impl App {
                 self.content.enter_edit_mode();
                 self.should_render = true;
             }
+            KeyCode::Char('s') => {
+                self.send_request();
+            }
+            KeyCode::Char('x') => {
+                self.cancel_request();
+            }
+            KeyCode::Char('h') => {
+                self.open_history();
+            }
+            KeyCode::Char('E') => {
+                self.cycle_environment();
+            }
+            KeyCode::Char('v') => {
+                self.content.toggle_raw();
+                self.should_render = true;
+            }
+            KeyCode::Char('i') => {
+                self.content.toggle_inspector();
+                self.should_render = true;
+            }
+            KeyCode::Char('t') => {
+                self.content.toggle_certificates();
+                self.should_render = true;
+            }
             _ => {
                 let event = Event::Key(KeyEvent::new(key, KeyModifiers::empty()));
-                if let SidebarAction::Selected(request) =
-                    self.sidebar.tick(Some(&event), self.tick_count)
-                {
-                    self.content.set_request(request);
+                let sidebar_action = self.sidebar.tick(Some(&event), self.tick_count);
+                self.handle_sidebar_action(sidebar_action);
+            }
+        }
+        AppAction::Noop
+    }
+
+    fn open_environments(&mut self) {
+        if self.current_project.is_none() {
+            self.footer
+                .set_status("Create a project before adding environments".to_string());
+            return;
+        }
+        self.environments.open();
+        self.mode = Mode::Environments;
+        self.update_footer_hints();
+        self.should_render = true;
+    }
+
+    fn handle_environments_mode(&mut self, key: KeyCode) -> AppAction {
+        let event = Event::Key(KeyEvent::new(key, KeyModifiers::empty()));
+        let action = self.environments.tick(Some(&event), self.tick_count);
+        self.handle_environment_action(action);
+        AppAction::Noop
+    }
+
+    fn handle_environment_action(&mut self, action: EnvironmentAction) {
+        match action {
+            EnvironmentAction::ProjectUpdate(update) => {
+                self.apply_project_update(update);
+            }
+            EnvironmentAction::UpdateGlobals(globals) => {
+                match self.storage.save_global_variables(&globals) {
+                    Ok(()) => {
+                        self.global_variables = globals;
+                        self.show_environments();
+                    }
+                    Err(e) => self
+                        .footer
+                        .set_status(format!("Failed to save global variables: {}", e)),
+                }
+                self.should_render = true;
+            }
+            EnvironmentAction::Updated => {
+                self.should_render = true;
+            }
+            EnvironmentAction::Close => {
+                self.mode = Mode::Normal;
+                self.update_footer_hints();
+                self.should_render = true;
+            }
+            EnvironmentAction::Noop => {}
+        }
+    }
+
+    fn open_settings(&mut self) {
+        if self.current_project.is_none() {
+            self.footer
+                .set_status("Create a project before changing its settings".to_string());
+            return;
+        }
+        self.settings.open();
+        self.mode = Mode::Settings;
+        self.update_footer_hints();
+        self.should_render = true;
+    }
+
+    fn handle_settings_mode(&mut self, key: KeyCode) -> AppAction {
+        let event = Event::Key(KeyEvent::new(key, KeyModifiers::empty()));
+        let action = self.settings.tick(Some(&event), self.tick_count);
+        self.handle_settings_action(action);
+        AppAction::Noop
+    }
+
+    fn handle_settings_action(&mut self, action: SettingsAction) {
+        match action {
+            SettingsAction::ProjectUpdate(update) => {
+                self.apply_project_update(update);
+            }
+            SettingsAction::Updated => {
+                self.should_render = true;
+            }
+            SettingsAction::Close => {
+                self.mode = Mode::Normal;
+                self.update_footer_hints();
+                self.should_render = true;
+            }
+            SettingsAction::Noop => {}
+        }
+    }
+
+    /// Makes the next environment active, going through "none" after the
+    /// last one.
+    fn cycle_environment(&mut self) {
+        let Some(project) = &self.current_project else {
+            return;
+        };
+        let next = match project
+            .active_environment
+            .as_ref()
+            .and_then(|name| project.environments.iter().position(|e| &e.name == name))
+        {
+            Some(index) => project.environments.get(index + 1),
+            None => project.environments.first(),
+        };
+        let name = next.map(|env| env.name.clone());
+        self.apply_project_update(ProjectUpdate::SetActiveEnvironment(name));
+    }
+
+    fn send_request(&mut self) {
+        if let Some(request) = self.content.request() {
+            let settings = self
+                .current_project
+                .as_ref()
+                .map(|p| p.settings.clone())
+                .unwrap_or_default();
+            // The project defaults are merged in before anything is resolved,
+            // so they can use variables too.
+            let request = &settings.apply(request);
+            let variables = self.scopes().resolve(request);
+            if let Some(name) = Self::locked_secret(request, &variables) {
+                self.footer
+                    .set_status(format!("Not sent: {} is locked. {}", name, LOCKED_SECRETS));
+                self.should_render = true;
+                return;
+            }
+            let (resolved, generated) = variables::resolve_request(request, &variables);
+            let token = resolved
+                .auth
+                .as_ref()
+                .and_then(oauth::cache_key)
+                .and_then(|key| self.storage.load_token(&key).ok().flatten());
+            let options = ClientOptions {
+                tls: self.tls_settings(),
+                ..ClientOptions::for_project(&settings)
+            };
+            self.worker.send(resolved, generated, token, options);
+            self.content
+                .set_pending(Some((self.tick_count, Duration::ZERO)));
+            self.should_render = true;
+        }
+    }
+
+    /// The config file's TLS settings, then the current project's, then its
+    /// active environment's.
+    fn tls_settings(&self) -> TlsSettings {
+        let Some(project) = &self.current_project else {
+            return self.global_tls.clone();
+        };
+        let tls = self.global_tls.merge(&project.settings.tls);
+        match project.active_environment() {
+            Some(env) => tls.merge(&env.tls),
+            None => tls,
+        }
+    }
+
+    /// A secret `request` would send that is still encrypted, if any.
+    fn locked_secret(request: &RequestData, variables: &HashMap<String, String>) -> Option<String> {
+        let variable = variables::placeholders(request)
+            .into_iter()
+            .find(|name| variables.get(name).is_some_and(|value| is_encrypted(value)));
+        let auth_locked = request.auth.as_ref().is_some_and(|auth| {
+            auth::fields(auth)
+                .iter()
+                .any(|field| field.input == Input::Secret && is_encrypted(&field.value))
+        });
+        variable.or_else(|| auth_locked.then(|| "its auth".to_string()))
+    }
+
+    fn cancel_request(&mut self) {
+        if let Some(request) = self.worker.cancel() {
+            self.content.set_pending(None);
+            self.content
+                .set_response_error(format!("Request '{}' cancelled", request.name));
+            self.should_render = true;
+        }
+    }
+
+    fn poll_worker(&mut self) {
+        if let Some(action) = self.worker.poll() {
+            self.content.set_pending(None);
+            let (sent, result) = match action {
+                HttpAction::Completed(sent, response) => (sent, Ok(response)),
+                HttpAction::Failed(sent, error) => (sent, Err(error)),
+                HttpAction::Authorizing(url) => {
+                    self.footer
+                        .set_status(format!("Sign in to continue, in the browser or at {}", url));
                     self.should_render = true;
+                    return;
+                }
+                HttpAction::TokenIssued(key, token) => {
+                    if let Err(e) = self.storage.save_token(&key, &token) {
+                        self.footer
+                            .set_status(format!("Failed to cache token: {}", e));
+                    }
+                    return;
+                }
+            };
+
+            let result = result.map(|response| {
+                let response = self.record_response(&sent, response);
+                self.run_captures(&sent, &response);
+                response
+            });
+
+            // Only show the result if the user is still looking at that request.
+            if self.content.request().map(|r| &r.id) == Some(&sent.id) {
+                match result {
+                    Ok(response) => {
+                        self.content.set_response(response);
+                        if self.mode == Mode::History {
+                            let history = self.load_history(&sent);
+                            self.content.set_history(history);
+                        }
+                    }
+                    Err(error) => self
+                        .content
+                        .set_response_error(format!("Request failed: {}", error)),
+                }
+            }
+            self.should_render = true;
+        } else if let Some((pending, elapsed)) = self.worker.pending() {
+            let is_viewing = self.content.request().map(|r| &r.id) == Some(&pending.id);
+            self.content
+                .set_pending(is_viewing.then_some((self.tick_count, elapsed)));
+            self.should_render = true;
+        }
+    }
+
+    /// Saves the response to `request`'s history.
+    fn record_response(&mut self, request: &RequestData, response: ResponseData) -> ResponseData {
+        match self.storage.save_response(&request.id, &response) {
+            Ok(Some(saved)) => return saved,
+            Ok(None) => {}
+            Err(e) => self
+                .footer
+                .set_status(format!("Failed to save response: {}", e)),
+        }
+        response
+    }
+
+    /// Stores the values `request`'s captures find in `response` in the
+    /// active environment, so later requests can use them.
+    fn run_captures(&mut self, request: &RequestData, response: &ResponseData) {
+        let Some(project) = &self.current_project else {
+            return;
+        };
+        if request.captures.is_empty() || !project.requests.iter().any(|r| r.id == request.id) {
+            return;
+        }
+        let Some((index, env)) = project.active_environment.as_ref().and_then(|name| {
+            project
+                .environments
+                .iter()
+                .enumerate()
+                .find(|(_, env)| &env.name == name)
+        }) else {
+            self.footer
+                .set_status("Select an environment to store captured values in".to_string());
+            return;
+        };
+
+        let mut env = env.clone();
+        let mut captured = Vec::new();
+        let mut failed = Vec::new();
+        for (name, result) in capture::run(&request.captures, response) {
+            match result {
+                Ok(value) => {
+                    env.variables.insert(name.clone(), value);
+                    captured.push(name);
                 }
+                Err(e) => failed.push(format!("{} ({})", name, e)),
+            }
+        }
+
+        let mut status = Vec::new();
+        if !captured.is_empty() {
+            status.push(format!(
+                "Captured {} into {}",
+                captured.join(", "),
+                env.name
+            ));
+            self.apply_project_update(ProjectUpdate::UpdateEnvironment(index, env));
+        }
+        if !failed.is_empty() {
+            status.push(format!("Capture failed: {}", failed.join(", ")));
+        }
+        self.footer.set_status(status.join("; "));
+    }
+
+    fn load_history(&mut self, request: &RequestData) -> Vec<ResponseData> {
+        match self.storage.load_history(&request.id) {
+            Ok(history) => history,
+            Err(e) => {
+                self.footer
+                    .set_status(format!("Failed to load history: {}", e));
+                Vec::new()
+            }
+        }
+    }
+
+    fn open_history(&mut self) {
+        if let Some(request) = self.content.request().cloned() {
+            let history = self.load_history(&request);
+            self.content.open_history(history);
+            self.mode = Mode::History;
+            self.update_footer_hints();
+            self.should_render = true;
+        }
+    }
+
+    fn handle_history_mode(&mut self, key: KeyCode) -> AppAction {
+        match key {
+            KeyCode::Esc | KeyCode::Char('h') => {
+                self.content.close_history();
+            }
+            _ => {
+                let event = Event::Key(KeyEvent::new(key, KeyModifiers::empty()));
+                self.content.tick(Some(&event), self.tick_count);
             }
         }
+
+        // Opening an entry returns the content to the response view.
+        if !self.content.is_showing_history() {
+            self.mode = Mode::Normal;
+            self.update_footer_hints();
+        }
+        self.should_render = true;
         AppAction::Noop
     }
 
@@ -355,6 +963,14 @@ impl App {
                 self.should_render = true;
                 AppAction::Noop
             }
+            KeyCode::Char('e') => {
+                self.open_environments();
+                AppAction::Noop
+            }
+            KeyCode::Char('p') => {
+                self.open_settings();
+                AppAction::Noop
+            }
             _ => AppAction::Noop,
         }
     }
@@ -362,8 +978,12 @@ impl App {
     fn handle_tab_change(&mut self, tab_index: usize) {
         if tab_index < self.projects.len() {
             if let Ok(Some(project)) = self.storage.load_project(&self.projects[tab_index].id) {
+                if has_locked_secrets(&project) {
+                    self.footer.set_status(LOCKED_SECRETS.to_string());
+                }
                 self.current_project = Some(project.clone());
-                self.sidebar.set_requests(project.requests);
+                self.sidebar.set_items(project.folders, project.requests);
+                self.show_environments();
                 self.content.clear_request();
                 self.should_render = true;
             }
@@ -391,11 +1011,13 @@ impl App {
                 .collect();
 
             self.header = Header::new(project_tabs);
+            self.show_environments();
 
             if let Some(project) = &self.current_project {
-                self.sidebar.set_requests(project.requests.clone());
+                self.sidebar
+                    .set_items(project.folders.clone(), project.requests.clone());
             } else {
-                self.sidebar.set_requests(Vec::new());
+                self.sidebar.set_items(Vec::new(), Vec::new());
             }
 
             self.content.clear_request();
@@ -440,20 +1062,7 @@ impl App {
                 self.should_render = true;
             }
             ContentAction::RequestUpdated(request) => {
-                if let Some(project) = &mut self.current_project {
-                    if let Some(existing_request) =
-                        project.requests.iter_mut().find(|r| r.name == request.name)
-                    {
-                        *existing_request = request.clone();
-                        if let Err(e) = self.storage.save_project(project) {
-                            self.footer
-                                .set_status(format!("Failed to save project: {}", e));
-                        }
-                        self.sidebar.set_requests(project.requests.clone());
-                        self.content.set_request(request);
-                    }
-                }
-                self.should_render = true;
+                self.update_request(request);
             }
             ContentAction::Noop => {
                 if key == KeyCode::Esc {
@@ -467,6 +1076,7 @@ impl App {
 
     pub fn tick(&mut self, event: Option<&Event>) -> AppAction {
         self.tick_count = self.tick_count.wrapping_add(1);
+        self.poll_worker();
 
         if let Some(event) = event {
             if let Event::Resize(_, _) = event {
@@ -481,6 +1091,11 @@ impl App {
                         self.should_render = true;
                         return AppAction::Noop;
                     }
+                    Mode::Name => {
+                        self.handle_naming(key.code);
+                        self.should_render = true;
+                        return AppAction::Noop;
+                    }
                     Mode::TabMode => {
                         self.handle_tab_events(*key);
                         return AppAction::Noop;
@@ -488,6 +1103,9 @@ impl App {
                     Mode::Normal => return self.handle_normal_mode(key.code),
                     Mode::Command => return self.handle_command_mode(key.code),
                     Mode::EditRequest => return self.handle_edit_mode(key.code),
+                    Mode::History => return self.handle_history_mode(key.code),
+                    Mode::Environments => return self.handle_environments_mode(key.code),
+                    Mode::Settings => return self.handle_settings_mode(key.code),
                 }
             }
 
@@ -507,7 +1125,11 @@ impl App {
 
         self.header.render(frame, header_area, &self.theme);
         self.sidebar.render(frame, sidebar_area, &self.theme);
-        self.content.render(frame, content_area, &self.theme);
+        match self.mode {
+            Mode::Environments => self.environments.render(frame, content_area, &self.theme),
+            Mode::Settings => self.settings.render(frame, content_area, &self.theme),
+            _ => self.content.render(frame, content_area, &self.theme),
+        }
         self.footer.render(frame, footer_area, &self.theme);
 
         self.should_render = false;
@@ -0,0 +1,454 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::persistence::{AuthData, HmacAlgorithm, JwtAlgorithm, OAuthGrant, SignatureEncoding};
+
+/// The auth schemes in the order the editor cycles through them.
+pub const KINDS: [&str; 10] = [
+    "None",
+    "Basic",
+    "Bearer",
+    "API key",
+    "Digest",
+    "OAuth 2.0",
+    "AWS SigV4",
+    "JWT",
+    "HMAC",
+    "Inherit",
+];
+
+/// Position of [`AuthData::Inherit`] in [`KINDS`]. Only requests can inherit,
+/// so the project's own auth cycles through the kinds before it.
+pub const INHERIT: usize = 9;
+
+/// Where an API key is sent, as offered in the editor.
+const PLACEMENTS: &[&str] = &["header", "query"];
+
+/// OAuth2 grants, as offered in the editor.
+const GRANTS: &[&str] = &["client credentials", "password", "authorization code"];
+
+/// JWT signing algorithms, as offered in the editor.
+const JWT_ALGORITHMS: &[&str] = &["HS256", "RS256", "ES256"];
+
+/// HMAC hashes, as offered in the editor.
+const HMAC_ALGORITHMS: &[&str] = &["SHA-256", "SHA-512"];
+
+/// How an HMAC is written into its header, as offered in the editor.
+const ENCODINGS: &[&str] = &["hex", "base64"];
+
+/// What an HMAC signs unless changed.
+const DEFAULT_TEMPLATE: &str = "{method}\\n{path}\\n{timestamp}\\n{body}";
+
+fn grant_name(grant: OAuthGrant) -> &'static str {
+    match grant {
+        OAuthGrant::ClientCredentials => GRANTS[0],
+        OAuthGrant::Password => GRANTS[1],
+        OAuthGrant::AuthorizationCode => GRANTS[2],
+    }
+}
+
+/// Position of `auth`'s scheme in [`KINDS`].
+pub fn kind(auth: &AuthData) -> usize {
+    match auth {
+        AuthData::None => 0,
+        AuthData::Basic { .. } => 1,
+        AuthData::Bearer { .. } => 2,
+        AuthData::ApiKey { .. } => 3,
+        AuthData::Digest { .. } => 4,
+        AuthData::OAuth2 { .. } => 5,
+        AuthData::AwsSigV4 { .. } => 6,
+        AuthData::Jwt { .. } => 7,
+        AuthData::Hmac { .. } => 8,
+        AuthData::Inherit => INHERIT,
+    }
+}
+
+/// An empty auth of the scheme at `index` in [`KINDS`].
+pub fn with_kind(index: usize) -> AuthData {
+    match index {
+        1 => AuthData::Basic {
+            username: String::new(),
+            password: String::new(),
+        },
+        2 => AuthData::Bearer {
+            token: String::new(),
+        },
+        3 => AuthData::ApiKey {
+            key: String::new(),
+            value: String::new(),
+            in_header: true,
+        },
+        4 => AuthData::Digest {
+            username: String::new(),
+            password: String::new(),
+        },
+        5 => AuthData::OAuth2 {
+            grant: OAuthGrant::ClientCredentials,
+            auth_url: String::new(),
+            token_url: String::new(),
+            client_id: String::new(),
+            client_secret: String::new(),
+            scope: String::new(),
+            username: String::new(),
+            password: String::new(),
+        },
+        6 => AuthData::AwsSigV4 {
+            access_key: String::new(),
+            secret_key: String::new(),
+            session_token: String::new(),
+            region: String::new(),
+            service: String::new(),
+        },
+        7 => AuthData::Jwt {
+            algorithm: JwtAlgorithm::Hs256,
+            key: String::new(),
+            claims: "{}".to_string(),
+            expires_in: "300".to_string(),
+        },
+        8 => AuthData::Hmac {
+            algorithm: HmacAlgorithm::Sha256,
+            secret: String::new(),
+            header: "X-Signature".to_string(),
+            template: DEFAULT_TEMPLATE.to_string(),
+            encoding: SignatureEncoding::Hex,
+            timestamp_header: "X-Timestamp".to_string(),
+        },
+        INHERIT => AuthData::Inherit,
+        _ => AuthData::None,
+    }
+}
+
+/// How a field is edited.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    Text,
+    /// Text that is masked when the request's auth is secret.
+    Secret,
+    /// One of a fixed set of values.
+    Choice(&'static [&'static str]),
+}
+
+/// One input of an auth scheme's form.
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub label: &'static str,
+    pub input: Input,
+    pub value: String,
+}
+
+impl Field {
+    fn new(label: &'static str, input: Input, value: &str) -> Self {
+        Field {
+            label,
+            input,
+            value: value.to_string(),
+        }
+    }
+}
+
+/// The inputs of `auth`'s form, in order.
+pub fn fields(auth: &AuthData) -> Vec<Field> {
+    match auth {
+        AuthData::None | AuthData::Inherit => Vec::new(),
+        AuthData::Basic { username, password } | AuthData::Digest { username, password } => vec![
+            Field::new("Username", Input::Text, username),
+            Field::new("Password", Input::Secret, password),
+        ],
+        AuthData::Bearer { token } => vec![Field::new("Token", Input::Secret, token)],
+        AuthData::ApiKey {
+            key,
+            value,
+            in_header,
+        } => vec![
+            Field::new("Key", Input::Text, key),
+            Field::new("Value", Input::Secret, value),
+            Field::new(
+                "Add to",
+                Input::Choice(PLACEMENTS),
+                PLACEMENTS[usize::from(!in_header)],
+            ),
+        ],
+        // Only what the grant uses is shown.
+        AuthData::OAuth2 {
+            grant,
+            auth_url,
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+            username,
+            password,
+        } => {
+            let mut fields = vec![Field::new(
+                "Grant",
+                Input::Choice(GRANTS),
+                grant_name(*grant),
+            )];
+            if *grant == OAuthGrant::AuthorizationCode {
+                fields.push(Field::new("Auth URL", Input::Text, auth_url));
+            }
+            fields.extend([
+                Field::new("Token URL", Input::Text, token_url),
+                Field::new("Client ID", Input::Text, client_id),
+                Field::new("Client secret", Input::Secret, client_secret),
+                Field::new("Scope", Input::Text, scope),
+            ]);
+            if *grant == OAuthGrant::Password {
+                fields.extend([
+                    Field::new("Username", Input::Text, username),
+                    Field::new("Password", Input::Secret, password),
+                ]);
+            }
+            fields
+        }
+        AuthData::AwsSigV4 {
+            access_key,
+            secret_key,
+            session_token,
+            region,
+            service,
+        } => vec![
+            Field::new("Access key", Input::Text, access_key),
+            Field::new("Secret key", Input::Secret, secret_key),
+            Field::new("Session token", Input::Secret, session_token),
+            Field::new("Region", Input::Text, region),
+            Field::new("Service", Input::Text, service),
+        ],
+        AuthData::Jwt {
+            algorithm,
+            key,
+            claims,
+            expires_in,
+        } => vec![
+            Field::new(
+                "Algorithm",
+                Input::Choice(JWT_ALGORITHMS),
+                JWT_ALGORITHMS[*algorithm as usize],
+            ),
+            Field::new("Key", Input::Secret, key),
+            Field::new("Claims", Input::Text, claims),
+            Field::new("Expires in", Input::Text, expires_in),
+        ],
+        AuthData::Hmac {
+            algorithm,
+            secret,
+            header,
+            template,
+            encoding,
+            timestamp_header,
+        } => vec![
+            Field::new(
+                "Algorithm",
+                Input::Choice(HMAC_ALGORITHMS),
+                HMAC_ALGORITHMS[*algorithm as usize],
+            ),
+            Field::new("Secret", Input::Secret, secret),
+            Field::new("Header", Input::Text, header),
+            Field::new("Template", Input::Text, template),
+            Field::new(
+                "Encoding",
+                Input::Choice(ENCODINGS),
+                ENCODINGS[*encoding as usize],
+            ),
+            Field::new("Timestamp header", Input::Text, timestamp_header),
+        ],
+    }
+}
+
+/// Sets the field at `index` of [`fields`]. Out of range indices are ignored.
+pub fn set_field(auth: &mut AuthData, index: usize, text: &str) {
+    let Some(label) = fields(auth).get(index).map(|field| field.label) else {
+        return;
+    };
+    let text = text.to_string();
+    match (auth, label) {
+        (AuthData::Basic { username, .. } | AuthData::Digest { username, .. }, "Username") => {
+            *username = text
+        }
+        (AuthData::Basic { password, .. } | AuthData::Digest { password, .. }, "Password") => {
+            *password = text
+        }
+        (AuthData::Bearer { token }, "Token") => *token = text,
+        (AuthData::ApiKey { key, .. }, "Key") => *key = text,
+        (AuthData::ApiKey { value, .. }, "Value") => *value = text,
+        (AuthData::ApiKey { in_header, .. }, "Add to") => *in_header = text == PLACEMENTS[0],
+        (AuthData::OAuth2 { grant, .. }, "Grant") => {
+            *grant = match GRANTS.iter().position(|g| *g == text) {
+                Some(1) => OAuthGrant::Password,
+                Some(2) => OAuthGrant::AuthorizationCode,
+                _ => OAuthGrant::ClientCredentials,
+            }
+        }
+        (AuthData::OAuth2 { auth_url, .. }, "Auth URL") => *auth_url = text,
+        (AuthData::OAuth2 { token_url, .. }, "Token URL") => *token_url = text,
+        (AuthData::OAuth2 { client_id, .. }, "Client ID") => *client_id = text,
+        (AuthData::OAuth2 { client_secret, .. }, "Client secret") => *client_secret = text,
+        (AuthData::OAuth2 { scope, .. }, "Scope") => *scope = text,
+        (AuthData::OAuth2 { username, .. }, "Username") => *username = text,
+        (AuthData::OAuth2 { password, .. }, "Password") => *password = text,
+        (AuthData::AwsSigV4 { access_key, .. }, "Access key") => *access_key = text,
+        (AuthData::AwsSigV4 { secret_key, .. }, "Secret key") => *secret_key = text,
+        (AuthData::AwsSigV4 { session_token, .. }, "Session token") => *session_token = text,
+        (AuthData::AwsSigV4 { region, .. }, "Region") => *region = text,
+        (AuthData::AwsSigV4 { service, .. }, "Service") => *service = text,
+        (AuthData::Jwt { algorithm, .. }, "Algorithm") => {
+            *algorithm = match JWT_ALGORITHMS.iter().position(|a| *a == text) {
+                Some(1) => JwtAlgorithm::Rs256,
+                Some(2) => JwtAlgorithm::Es256,
+                _ => JwtAlgorithm::Hs256,
+            }
+        }
+        (AuthData::Jwt { key, .. }, "Key") => *key = text,
+        (AuthData::Jwt { claims, .. }, "Claims") => *claims = text,
+        (AuthData::Jwt { expires_in, .. }, "Expires in") => *expires_in = text,
+        (AuthData::Hmac { algorithm, .. }, "Algorithm") => {
+            *algorithm = if text == HMAC_ALGORITHMS[1] {
+                HmacAlgorithm::Sha512
+            } else {
+                HmacAlgorithm::Sha256
+            }
+        }
+        (AuthData::Hmac { secret, .. }, "Secret") => *secret = text,
+        (AuthData::Hmac { header, .. }, "Header") => *header = text,
+        (AuthData::Hmac { template, .. }, "Template") => *template = text,
+        (AuthData::Hmac { encoding, .. }, "Encoding") => {
+            *encoding = if text == ENCODINGS[1] {
+                SignatureEncoding::Base64
+            } else {
+                SignatureEncoding::Hex
+            }
+        }
+        (
+            AuthData::Hmac {
+                timestamp_header, ..
+            },
+            "Timestamp header",
+        ) => *timestamp_header = text,
+        _ => {}
+    }
+}
+
+/// What an auth adds to a request.
+#[derive(Debug, PartialEq)]
+pub enum Placement {
+    Header(String, String),
+    Query(String, String),
+}
+
+/// The header or query parameter `auth` adds, if any. Placeholders should
+/// already be substituted. Digest only answers a challenge, OAuth2 adds
+/// nothing until its token is fetched and swapped in as a bearer token, and
+/// the signing schemes sign the finished request instead. Inherited auth is
+/// replaced by the project's before this is called.
+pub fn apply(auth: &AuthData) -> Option<Placement> {
+    match auth {
+        AuthData::None
+        | AuthData::Inherit
+        | AuthData::Digest { .. }
+        | AuthData::OAuth2 { .. }
+        | AuthData::AwsSigV4 { .. }
+        | AuthData::Jwt { .. }
+        | AuthData::Hmac { .. } => None,
+        AuthData::Basic { username, password } => Some(Placement::Header(
+            "Authorization".to_string(),
+            format!(
+                "Basic {}",
+                STANDARD.encode(format!("{}:{}", username, password))
+            ),
+        )),
+        AuthData::Bearer { token } => Some(Placement::Header(
+            "Authorization".to_string(),
+            format!("Bearer {}", token),
+        )),
+        AuthData::ApiKey {
+            key,
+            value,
+            in_header: true,
+        } => Some(Placement::Header(key.clone(), value.clone())),
+        AuthData::ApiKey {
+            key,
+            value,
+            in_header: false,
+        } => Some(Placement::Query(key.clone(), value.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_round_trip() {
+        for index in 0..KINDS.len() {
+            let mut auth = with_kind(index);
+            assert_eq!(kind(&auth), index);
+            for (i, field) in fields(&auth).iter().enumerate() {
+                let value = match field.input {
+                    Input::Choice(options) => options[options.len() - 1],
+                    _ => "changed",
+                };
+                set_field(&mut auth, i, value);
+                assert_eq!(fields(&auth)[i].value, value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_oauth_fields_follow_grant() {
+        let labels = |auth: &AuthData| -> Vec<&str> {
+            fields(auth).iter().map(|field| field.label).collect()
+        };
+        let mut auth = with_kind(5);
+        assert_eq!(
+            labels(&auth),
+            ["Grant", "Token URL", "Client ID", "Client secret", "Scope"]
+        );
+        set_field(&mut auth, 1, "https://auth.example.com/token");
+        set_field(&mut auth, 0, "password");
+        assert_eq!(labels(&auth)[5..], ["Username", "Password"]);
+        set_field(&mut auth, 6, "hunter2");
+        set_field(&mut auth, 0, "authorization code");
+        assert_eq!(labels(&auth)[..3], ["Grant", "Auth URL", "Token URL"]);
+        match auth {
+            AuthData::OAuth2 {
+                grant,
+                token_url,
+                password,
+                ..
+            } => {
+                assert_eq!(grant, OAuthGrant::AuthorizationCode);
+                assert_eq!(token_url, "https://auth.example.com/token");
+                assert_eq!(password, "hunter2");
+            }
+            auth => panic!("unexpected auth {:?}", auth),
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let basic = AuthData::Basic {
+            username: "aladdin".to_string(),
+            password: "opensesame".to_string(),
+        };
+        assert_eq!(
+            apply(&basic),
+            Some(Placement::Header(
+                "Authorization".to_string(),
+                "Basic YWxhZGRpbjpvcGVuc2VzYW1l".to_string()
+            ))
+        );
+
+        let mut key = with_kind(3);
+        set_field(&mut key, 0, "api_key");
+        set_field(&mut key, 1, "abc");
+        assert_eq!(
+            apply(&key),
+            Some(Placement::Header("api_key".to_string(), "abc".to_string()))
+        );
+        set_field(&mut key, 2, "query");
+        assert_eq!(
+            apply(&key),
+            Some(Placement::Query("api_key".to_string(), "abc".to_string()))
+        );
+        assert_eq!(apply(&AuthData::None), None);
+    }
+}
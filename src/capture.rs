@@ -0,0 +1,244 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::persistence::{Capture, CaptureSource, ResponseData};
+
+/// Parses the `json <path>`, `header <name>` or `regex <pattern>` form used
+/// to type a capture source in the editor.
+pub fn parse_source(text: &str) -> Option<CaptureSource> {
+    let (kind, rest) = text.trim().split_once(char::is_whitespace)?;
+    let rest = rest.trim().to_string();
+    match kind {
+        "json" => Some(CaptureSource::JsonPath(rest)),
+        "header" => Some(CaptureSource::Header(rest)),
+        "regex" => Some(CaptureSource::Regex(rest)),
+        _ => None,
+    }
+}
+
+/// The inverse of [`parse_source`].
+pub fn format_source(source: &CaptureSource) -> String {
+    match source {
+        CaptureSource::JsonPath(path) => format!("json {}", path),
+        CaptureSource::Header(name) => format!("header {}", name),
+        CaptureSource::Regex(pattern) => format!("regex {}", pattern),
+    }
+}
+
+/// Runs every capture against `response`, returning each variable name with
+/// its value or the reason it could not be found.
+pub fn run(captures: &[Capture], response: &ResponseData) -> Vec<(String, Result<String, String>)> {
+    captures
+        .iter()
+        .map(|capture| (capture.variable.clone(), extract(&capture.source, response)))
+        .collect()
+}
+
+pub fn extract(source: &CaptureSource, response: &ResponseData) -> Result<String, String> {
+    let body = response.response_body.as_deref().unwrap_or_default();
+    match source {
+        CaptureSource::JsonPath(path) => {
+            let json: Value =
+                serde_json::from_str(body).map_err(|e| format!("body is not JSON: {}", e))?;
+            match json_path(&json, path)? {
+                Some(Value::String(text)) => Ok(text.clone()),
+                Some(value) => Ok(value.to_string()),
+                None => Err(format!("nothing at {}", path)),
+            }
+        }
+        CaptureSource::Header(name) => response
+            .response_headers
+            .iter()
+            .flatten()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| format!("no {} header", name)),
+        CaptureSource::Regex(pattern) => {
+            let regex = Regex::new(pattern).map_err(|e| format!("invalid regex: {}", e))?;
+            let captures = regex
+                .captures(body)
+                .ok_or_else(|| format!("no match for /{}/", pattern))?;
+            let found = captures.get(1).or_else(|| captures.get(0)).unwrap();
+            Ok(found.as_str().to_string())
+        }
+    }
+}
+
+/// One step of a JSONPath.
+#[derive(Debug, PartialEq)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Wildcard,
+}
+
+/// Splits a JSONPath into steps. Supports `$`, `.key`, `['key']`, `[0]`,
+/// `[-1]` and `*`, which covers picking a value out of a response.
+fn parse_path(path: &str) -> Result<Vec<Step>, String> {
+    let invalid = || format!("invalid JSONPath {}", path);
+    // `data.token` is read as `$.data.token`.
+    let path = path.trim();
+    let normalized = match path.strip_prefix('$') {
+        Some(rest) => rest.to_string(),
+        None if path.starts_with('[') => path.to_string(),
+        None => format!(".{}", path),
+    };
+    let mut rest = normalized.as_str();
+    let mut steps = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            if after.starts_with('.') {
+                return Err(format!("recursive descent is not supported in {}", path));
+            }
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            steps.push(match &after[..end] {
+                "" => return Err(invalid()),
+                "*" => Step::Wildcard,
+                key => Step::Key(key.to_string()),
+            });
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let inner = after[..end].trim();
+            steps.push(if inner == "*" {
+                Step::Wildcard
+            } else if let Some(key) = inner
+                .strip_prefix('\'')
+                .and_then(|k| k.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')))
+            {
+                Step::Key(key.to_string())
+            } else {
+                Step::Index(inner.parse().map_err(|_| invalid())?)
+            });
+            rest = &after[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(steps)
+}
+
+/// The first value `path` selects in `json`.
+fn json_path<'a>(json: &'a Value, path: &str) -> Result<Option<&'a Value>, String> {
+    let mut matches = vec![json];
+    for step in parse_path(path)? {
+        matches = matches
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (&step, value) {
+                    (Step::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                    (Step::Index(index), Value::Array(items)) => {
+                        let index = if *index < 0 {
+                            items.len() as i64 + index
+                        } else {
+                            *index
+                        };
+                        usize::try_from(index)
+                            .ok()
+                            .and_then(|i| items.get(i))
+                            .into_iter()
+                            .collect()
+                    }
+                    (Step::Wildcard, Value::Array(items)) => items.iter().collect(),
+                    (Step::Wildcard, Value::Object(map)) => map.values().collect(),
+                    _ => Vec::new(),
+                }
+            })
+            .collect();
+    }
+    Ok(matches.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> ResponseData {
+        ResponseData {
+            request_id: 0,
+            status_code: Some(200),
+            response_body: Some(body.to_string()),
+            response_headers: Some(vec![("x-session".to_string(), "abc123".to_string())]),
+            response_time: 0,
+            timestamp: 0,
+            http_version: None,
+            variables: Vec::new(),
+            hops: Vec::new(),
+            signing: None,
+            certificates: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("$.data['the token'][0].*").unwrap(),
+            vec![
+                Step::Key("data".to_string()),
+                Step::Key("the token".to_string()),
+                Step::Index(0),
+                Step::Wildcard,
+            ]
+        );
+        assert_eq!(
+            parse_path("data.token").unwrap(),
+            vec![
+                Step::Key("data".to_string()),
+                Step::Key("token".to_string())
+            ]
+        );
+        assert!(parse_path("$").unwrap().is_empty());
+        assert!(parse_path("$..token").is_err());
+        assert!(parse_path("$[x]").is_err());
+        assert!(parse_path("$.a[0").is_err());
+    }
+
+    #[test]
+    fn test_json_path() {
+        let response =
+            response(r#"{"data": {"token": "t0k3n", "id": 7, "items": [{"n": 1}, {"n": 2}]}}"#);
+        let extract = |path: &str| extract(&CaptureSource::JsonPath(path.to_string()), &response);
+
+        assert_eq!(extract("$.data.token"), Ok("t0k3n".to_string()));
+        assert_eq!(extract("$.data.id"), Ok("7".to_string()));
+        assert_eq!(extract("$.data.items[-1].n"), Ok("2".to_string()));
+        assert_eq!(extract("$.data.items[*].n"), Ok("1".to_string()));
+        assert_eq!(
+            extract("$['data']['items'][0]"),
+            Ok(r#"{"n":1}"#.to_string())
+        );
+        assert!(extract("$.data.missing").is_err());
+        assert!(extract("$.data.items[5]").is_err());
+    }
+
+    #[test]
+    fn test_header_and_regex() {
+        let response = response("session id=42; expires soon");
+        assert_eq!(
+            extract(&CaptureSource::Header("X-Session".to_string()), &response),
+            Ok("abc123".to_string())
+        );
+        assert!(extract(&CaptureSource::Header("X-Other".to_string()), &response).is_err());
+        assert_eq!(
+            extract(&CaptureSource::Regex(r"id=(\d+)".to_string()), &response),
+            Ok("42".to_string())
+        );
+        assert_eq!(
+            extract(&CaptureSource::Regex(r"exp\w+".to_string()), &response),
+            Ok("expires".to_string())
+        );
+        assert!(extract(&CaptureSource::Regex("(".to_string()), &response).is_err());
+        assert!(extract(&CaptureSource::JsonPath("$.a".to_string()), &response).is_err());
+    }
+
+    #[test]
+    fn test_source_round_trip() {
+        for text in ["json $.data.token", "header X-Session", r"regex id=(\d+)"] {
+            assert_eq!(format_source(&parse_source(text).unwrap()), text);
+        }
+        assert!(parse_source("xpath //a").is_none());
+        assert!(parse_source("json").is_none());
+    }
+}
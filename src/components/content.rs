@@ -1,20 +1,43 @@
+use chrono::{Local, TimeZone};
 use crossterm::event::{Event, KeyCode, MouseEvent, MouseEventKind};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, Wrap},
     Frame,
 };
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
 
+use crate::auth::{self, Placement};
+use crate::capture;
 use crate::components::Component;
-use crate::persistence::{RequestData, ResponseData};
+use crate::persistence::{
+    AuthData, Capture, CertificateInfo, HttpProtocol, ProjectSettings, RequestData, ResponseData,
+    Signing,
+};
+use crate::sigv4;
 use crate::theme::Theme;
+use crate::variables::{self, Dynamic, Scopes, Segment, MASK};
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// The protocols a request can be sent over, as offered in the editor.
+const PROTOCOLS: [(HttpProtocol, &str); 4] = [
+    (HttpProtocol::Auto, "auto"),
+    (HttpProtocol::Http1, "HTTP/1.1"),
+    (HttpProtocol::Http2, "HTTP/2"),
+    (HttpProtocol::H2c, "h2c"),
+];
 
 #[derive(PartialEq)]
 enum ViewMode {
     View,
     Edit,
+    History,
+    Variables,
 }
 
 #[derive(PartialEq, Clone)]
@@ -25,10 +48,23 @@ enum EditField {
     Headers,
     QueryParams,
     PathParams,
+    Variables,
+    Captures,
     Auth,
+    Protocol,
     Body,
 }
 
+/// A row of the auth form: the scheme, one of its fields, or whether the
+/// credential is secret.
+#[derive(PartialEq, Clone, Copy)]
+enum AuthRow {
+    Kind,
+    Field(usize),
+    Secret,
+}
+
+#[allow(clippy::large_enum_variant)]
 pub enum ContentAction {
     Noop,
     ContentUpdated,
@@ -39,9 +75,31 @@ pub struct Content {
     rect: Option<Rect>,
     request: Option<RequestData>,
     response: Option<ResponseData>,
+    response_error: Option<String>,
+    pending: Option<(u32, Duration)>,
+    history: Vec<ResponseData>,
+    history_index: usize,
     view_mode: ViewMode,
     edit_field: EditField,
     edit_buffer: String,
+    /// Selected row of the auth form.
+    auth_row: usize,
+    /// Auth schemes switched away from while editing, so switching back
+    /// restores what was typed.
+    auth_stash: Vec<AuthData>,
+    scopes: Scopes,
+    /// The project's defaults, filled in when showing resolved values.
+    settings: ProjectSettings,
+    /// Values for the request's `{{name}}` placeholders, resolved from
+    /// `scopes` and the request's own variables. Shown in place of the
+    /// templates unless `show_raw` is set.
+    variables: HashMap<String, String>,
+    show_raw: bool,
+    /// Whether the response's certificates are shown in place of its body.
+    show_certificates: bool,
+    /// Whether the response was picked from the history, which does not keep
+    /// certificates.
+    response_from_history: bool,
 }
 
 impl Content {
@@ -50,23 +108,137 @@ impl Content {
             rect: None,
             request: None,
             response: None,
+            response_error: None,
+            pending: None,
+            history: Vec::new(),
+            history_index: 0,
             view_mode: ViewMode::View,
             edit_field: EditField::None,
             edit_buffer: String::new(),
+            auth_row: 0,
+            auth_stash: Vec::new(),
+            scopes: Scopes::default(),
+            settings: ProjectSettings::default(),
+            variables: HashMap::new(),
+            show_raw: false,
+            show_certificates: false,
+            response_from_history: false,
         }
     }
 
     pub fn set_request(&mut self, request: RequestData) {
         self.request = Some(request);
         self.response = None;
+        self.response_error = None;
+        self.history.clear();
         self.view_mode = ViewMode::View;
         self.edit_field = EditField::None;
         self.edit_buffer.clear();
+        self.auth_row = 0;
+        self.auth_stash.clear();
+        self.resolve_variables();
+    }
+
+    /// Swaps in a newer copy of the request being shown without leaving the
+    /// current view.
+    pub fn refresh_request(&mut self, request: RequestData) {
+        if self.request.as_ref().is_some_and(|r| r.id == request.id) {
+            self.request = Some(request);
+            self.resolve_variables();
+        }
+    }
+
+    pub fn set_scopes(&mut self, scopes: Scopes) {
+        self.scopes = scopes;
+        self.resolve_variables();
+    }
+
+    pub fn set_settings(&mut self, settings: ProjectSettings) {
+        self.settings = settings;
+        self.resolve_variables();
+    }
+
+    /// Whether the value of `name` is secret for the open request.
+    fn is_secret(&self, name: &str) -> bool {
+        match &self.request {
+            Some(request) => self.scopes.is_secret(request, name),
+            None => self.scopes.secrets.contains(name),
+        }
+    }
+
+    fn resolve_variables(&mut self) {
+        self.variables = match &self.request {
+            Some(request) => self.scopes.resolve(&self.settings.apply(request)),
+            None => HashMap::new(),
+        };
+    }
+
+    /// Switches between the request summary and a table of the variables it
+    /// can use and where each value comes from.
+    pub fn toggle_inspector(&mut self) {
+        self.view_mode = match self.view_mode {
+            ViewMode::Variables => ViewMode::View,
+            _ => ViewMode::Variables,
+        };
+    }
+
+    /// Switches between showing resolved values and the raw templates.
+    pub fn toggle_raw(&mut self) {
+        self.show_raw = !self.show_raw;
+    }
+
+    /// Switches the response between its body and the certificates the
+    /// server presented.
+    pub fn toggle_certificates(&mut self) {
+        self.show_certificates = !self.show_certificates;
     }
 
     pub fn clear_request(&mut self) {
         self.request = None;
         self.response = None;
+        self.response_error = None;
+        self.variables.clear();
+    }
+
+    pub fn request(&self) -> Option<&RequestData> {
+        self.request.as_ref()
+    }
+
+    pub fn set_response(&mut self, response: ResponseData) {
+        self.response = Some(response);
+        self.response_error = None;
+        self.response_from_history = false;
+    }
+
+    pub fn set_response_error(&mut self, error: String) {
+        self.response = None;
+        self.response_error = Some(error);
+    }
+
+    /// Shows a spinner in place of the response while a request is in flight.
+    /// `tick_count` drives the animation frame.
+    pub fn set_pending(&mut self, pending: Option<(u32, Duration)>) {
+        self.pending = pending;
+    }
+
+    pub fn open_history(&mut self, history: Vec<ResponseData>) {
+        self.history = history;
+        self.history_index = 0;
+        self.view_mode = ViewMode::History;
+    }
+
+    pub fn close_history(&mut self) {
+        self.view_mode = ViewMode::View;
+    }
+
+    pub fn is_showing_history(&self) -> bool {
+        self.view_mode == ViewMode::History
+    }
+
+    /// Replaces the listed history entries, keeping the selection in range.
+    pub fn set_history(&mut self, history: Vec<ResponseData>) {
+        self.history = history;
+        self.history_index = self.history_index.min(self.history.len().saturating_sub(1));
     }
 
     pub fn enter_edit_mode(&mut self) {
@@ -84,8 +256,11 @@ impl Content {
             EditField::Url => EditField::Headers,
             EditField::Headers => EditField::QueryParams,
             EditField::QueryParams => EditField::PathParams,
-            EditField::PathParams => EditField::Auth,
-            EditField::Auth => EditField::Body,
+            EditField::PathParams => EditField::Variables,
+            EditField::Variables => EditField::Captures,
+            EditField::Captures => EditField::Auth,
+            EditField::Auth => EditField::Protocol,
+            EditField::Protocol => EditField::Body,
             EditField::Body => EditField::Method,
         }
     }
@@ -98,8 +273,35 @@ impl Content {
             EditField::Headers => EditField::Url,
             EditField::QueryParams => EditField::Headers,
             EditField::PathParams => EditField::QueryParams,
-            EditField::Auth => EditField::PathParams,
-            EditField::Body => EditField::Auth,
+            EditField::Variables => EditField::PathParams,
+            EditField::Captures => EditField::Variables,
+            EditField::Auth => EditField::Captures,
+            EditField::Protocol => EditField::Auth,
+            EditField::Body => EditField::Protocol,
+        }
+    }
+
+    fn handle_history_key(&mut self, key: KeyCode) -> ContentAction {
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.history_index + 1 < self.history.len() {
+                    self.history_index += 1;
+                }
+                ContentAction::ContentUpdated
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.history_index = self.history_index.saturating_sub(1);
+                ContentAction::ContentUpdated
+            }
+            KeyCode::Enter => {
+                if let Some(entry) = self.history.get(self.history_index).cloned() {
+                    self.set_response(entry);
+                    self.response_from_history = true;
+                    self.view_mode = ViewMode::View;
+                }
+                ContentAction::ContentUpdated
+            }
+            _ => ContentAction::Noop,
         }
     }
 
@@ -108,6 +310,23 @@ impl Content {
             return ContentAction::Noop;
         }
 
+        if self.edit_field == EditField::Auth {
+            match key {
+                KeyCode::Up => return self.move_auth_row(false),
+                KeyCode::Down => return self.move_auth_row(true),
+                KeyCode::Left => return self.cycle_auth_choice(false),
+                KeyCode::Right => return self.cycle_auth_choice(true),
+                _ => {}
+            }
+        }
+        if self.edit_field == EditField::Protocol {
+            match key {
+                KeyCode::Left => return self.cycle_protocol(false),
+                KeyCode::Right | KeyCode::Enter => return self.cycle_protocol(true),
+                _ => {}
+            }
+        }
+
         match key {
             KeyCode::Esc => self.handle_escape_key(),
             KeyCode::Tab => self.handle_tab_key(false),
@@ -131,6 +350,7 @@ impl Content {
     }
 
     fn handle_tab_key(&mut self, backwards: bool) -> ContentAction {
+        let changed = self.commit_auth_field();
         self.edit_field = if backwards {
             Self::get_previous_field(&self.edit_field)
         } else {
@@ -148,12 +368,21 @@ impl Content {
                 _ => String::new(),
             };
         }
-        ContentAction::ContentUpdated
+        self.auth_row = 0;
+        self.focus_changed(changed)
+    }
+
+    /// Saves the request if leaving a field changed it.
+    fn focus_changed(&self, changed: bool) -> ContentAction {
+        match &self.request {
+            Some(request) if changed => ContentAction::RequestUpdated(request.clone()),
+            _ => ContentAction::ContentUpdated,
+        }
     }
 
     fn handle_enter_key(&mut self) -> ContentAction {
         if let Some(mut request) = self.request.clone() {
-            let action = match self.edit_field {
+            match self.edit_field {
                 EditField::Method => {
                     request.method = Some(self.edit_buffer.clone());
                     let action = ContentAction::RequestUpdated(request.clone());
@@ -173,20 +402,83 @@ impl Content {
                     action
                 }
                 EditField::Headers => {
-                    let action = self.handle_key_value_entry(&mut request.headers);
-                    self.request = Some(request);
-                    action
+                    let added = self.handle_key_value_entry(&mut request.headers);
+                    self.finish_key_value_entry(request, added)
                 }
                 EditField::QueryParams => {
-                    let action = self.handle_key_value_entry(&mut request.query_params);
-                    self.request = Some(request);
-                    action
+                    let added = self.handle_key_value_entry(&mut request.query_params);
+                    self.finish_key_value_entry(request, added)
                 }
                 EditField::PathParams => {
-                    let action = self.handle_key_value_entry(&mut request.path_params);
-                    self.request = Some(request);
+                    let added = self.handle_key_value_entry(&mut request.path_params);
+                    self.finish_key_value_entry(request, added)
+                }
+                // `name = value` sets an override, `secret name = value` sets
+                // a secret one and `name =` removes it.
+                EditField::Variables => {
+                    let added = match self.edit_buffer.split_once('=') {
+                        Some((name, value)) => {
+                            let (name, value) = (name.trim(), value.trim());
+                            let (name, secret) = match name.strip_prefix("secret ") {
+                                Some(name) => (name.trim(), true),
+                                None => (name, false),
+                            };
+                            request.secrets.remove(name);
+                            if value.is_empty() {
+                                request.variables.remove(name);
+                            } else {
+                                request
+                                    .variables
+                                    .insert(name.to_string(), value.to_string());
+                                if secret {
+                                    request.secrets.insert(name.to_string());
+                                }
+                            }
+                            self.edit_buffer.clear();
+                            true
+                        }
+                        None => false,
+                    };
+                    let action = self.finish_key_value_entry(request, added);
+                    self.resolve_variables();
                     action
                 }
+                // `name = json $.path`, `name = header Name` or
+                // `name = regex pattern` sets a capture; `name =` removes it.
+                EditField::Captures => {
+                    let added = match self.edit_buffer.split_once('=') {
+                        Some((name, source)) if source.trim().is_empty() => {
+                            request.captures.retain(|c| c.variable != name.trim());
+                            true
+                        }
+                        Some((name, source)) => match capture::parse_source(source) {
+                            Some(source) => {
+                                let name = name.trim().to_string();
+                                match request.captures.iter_mut().find(|c| c.variable == name) {
+                                    Some(existing) => existing.source = source,
+                                    None => request.captures.push(Capture {
+                                        variable: name,
+                                        source,
+                                    }),
+                                }
+                                true
+                            }
+                            None => false,
+                        },
+                        None => false,
+                    };
+                    if added {
+                        self.edit_buffer.clear();
+                    }
+                    self.finish_key_value_entry(request, added)
+                }
+                // Saves a text field and moves on; choices change instead.
+                EditField::Auth => match self.auth_row() {
+                    AuthRow::Field(index) if !Self::auth_field_is_choice(&request, index) => {
+                        self.move_auth_row(true)
+                    }
+                    _ => self.cycle_auth_choice(true),
+                },
                 EditField::Body => {
                     request.body = Some(self.edit_buffer.clone());
                     let action = ContentAction::RequestUpdated(request.clone());
@@ -195,35 +487,184 @@ impl Content {
                     action
                 }
                 _ => ContentAction::Noop,
-            };
-            action
+            }
         } else {
             ContentAction::Noop
         }
     }
 
-    fn handle_key_value_entry(
-        &mut self,
-        params: &mut Option<Vec<(String, String)>>,
-    ) -> ContentAction {
-        if let Some(request) = self.request.clone() {
-            let separator = if self.edit_field == EditField::Headers {
-                ':'
-            } else {
-                '='
-            };
-            if let Some((key, value)) = self.edit_buffer.split_once(separator) {
-                let mut items = params.clone().unwrap_or_default();
-                items.push((key.trim().to_string(), value.trim().to_string()));
-                *params = Some(items);
-                self.edit_buffer.clear();
-                ContentAction::RequestUpdated(request)
+    fn handle_key_value_entry(&mut self, params: &mut Option<Vec<(String, String)>>) -> bool {
+        let separator = if self.edit_field == EditField::Headers {
+            ':'
+        } else {
+            '='
+        };
+        if let Some((key, value)) = self.edit_buffer.split_once(separator) {
+            let mut items = params.clone().unwrap_or_default();
+            items.push((key.trim().to_string(), value.trim().to_string()));
+            *params = Some(items);
+            self.edit_buffer.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn finish_key_value_entry(&mut self, request: RequestData, added: bool) -> ContentAction {
+        self.request = Some(request.clone());
+        if added {
+            ContentAction::RequestUpdated(request)
+        } else {
+            ContentAction::ContentUpdated
+        }
+    }
+
+    fn auth(request: &RequestData) -> AuthData {
+        request.auth.clone().unwrap_or(AuthData::None)
+    }
+
+    fn auth_rows(auth: &AuthData) -> Vec<AuthRow> {
+        let fields = (0..auth::fields(auth).len()).map(AuthRow::Field);
+        std::iter::once(AuthRow::Kind)
+            .chain(fields)
+            .chain(std::iter::once(AuthRow::Secret))
+            .collect()
+    }
+
+    fn auth_row(&self) -> AuthRow {
+        let auth = self
+            .request
+            .as_ref()
+            .map(Self::auth)
+            .unwrap_or(AuthData::None);
+        let rows = Self::auth_rows(&auth);
+        rows[self.auth_row.min(rows.len() - 1)]
+    }
+
+    fn auth_field_is_choice(request: &RequestData, index: usize) -> bool {
+        auth::fields(&Self::auth(request))
+            .get(index)
+            .is_some_and(|field| matches!(field.input, auth::Input::Choice(_)))
+    }
+
+    fn is_auth_text_row(&self) -> bool {
+        match (self.auth_row(), &self.request) {
+            (AuthRow::Field(index), Some(request)) => !Self::auth_field_is_choice(request, index),
+            _ => false,
+        }
+    }
+
+    /// Saves what was typed into the selected auth field, then selects the
+    /// next or previous row.
+    fn move_auth_row(&mut self, down: bool) -> ContentAction {
+        let changed = self.commit_auth_field();
+        let Some(request) = self.request.clone() else {
+            return ContentAction::Noop;
+        };
+        let auth = Self::auth(&request);
+
+        let rows = Self::auth_rows(&auth).len();
+        self.auth_row = if down {
+            (self.auth_row + 1).min(rows - 1)
+        } else {
+            self.auth_row.saturating_sub(1)
+        };
+        self.edit_buffer = match self.auth_row() {
+            AuthRow::Field(index) => auth::fields(&auth)[index].value.clone(),
+            _ => String::new(),
+        };
+        self.finish_key_value_entry(request, changed)
+    }
+
+    /// Writes what was typed into the selected auth field into the request.
+    /// Returns whether that changed it.
+    fn commit_auth_field(&mut self) -> bool {
+        if self.edit_field != EditField::Auth || !self.is_auth_text_row() {
+            return false;
+        }
+        let AuthRow::Field(index) = self.auth_row() else {
+            return false;
+        };
+        let Some(request) = &mut self.request else {
+            return false;
+        };
+        let mut auth = Self::auth(request);
+        if auth::fields(&auth)[index].value == self.edit_buffer {
+            return false;
+        }
+        auth::set_field(&mut auth, index, &self.edit_buffer);
+        request.auth = Some(auth);
+        true
+    }
+
+    /// Switches the scheme, a choice field or the secret flag on the
+    /// selected row to its next or previous value.
+    fn cycle_auth_choice(&mut self, forward: bool) -> ContentAction {
+        let Some(mut request) = self.request.clone() else {
+            return ContentAction::Noop;
+        };
+        let step = |index: usize, len: usize| {
+            if forward {
+                (index + 1) % len
             } else {
-                ContentAction::ContentUpdated
+                (index + len - 1) % len
             }
-        } else {
-            ContentAction::Noop
+        };
+        let mut auth = Self::auth(&request);
+        match self.auth_row() {
+            AuthRow::Kind => {
+                let kind = step(auth::kind(&auth), auth::KINDS.len());
+                self.auth_stash
+                    .retain(|a| auth::kind(a) != auth::kind(&auth));
+                self.auth_stash.push(auth);
+                auth = match self.auth_stash.iter().position(|a| auth::kind(a) == kind) {
+                    Some(position) => self.auth_stash.remove(position),
+                    None => auth::with_kind(kind),
+                };
+            }
+            AuthRow::Field(index) => match auth::fields(&auth).remove(index) {
+                auth::Field {
+                    input: auth::Input::Choice(options),
+                    value,
+                    ..
+                } => {
+                    let current = options.iter().position(|o| *o == value).unwrap_or(0);
+                    auth::set_field(&mut auth, index, options[step(current, options.len())]);
+                }
+                _ => return ContentAction::Noop,
+            },
+            AuthRow::Secret => request.secret_auth = !request.secret_auth,
         }
+        request.auth = Some(auth);
+        let action = self.finish_key_value_entry(request, true);
+        self.resolve_variables();
+        action
+    }
+
+    /// Switches the request to the next or previous protocol.
+    fn cycle_protocol(&mut self, forward: bool) -> ContentAction {
+        let Some(mut request) = self.request.clone() else {
+            return ContentAction::Noop;
+        };
+        let current = PROTOCOLS
+            .iter()
+            .position(|(protocol, _)| *protocol == request.protocol)
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % PROTOCOLS.len()
+        } else {
+            (current + PROTOCOLS.len() - 1) % PROTOCOLS.len()
+        };
+        request.protocol = PROTOCOLS[next].0;
+        self.finish_key_value_entry(request, true)
+    }
+
+    fn protocol_name(protocol: HttpProtocol) -> &'static str {
+        PROTOCOLS
+            .iter()
+            .find(|(p, _)| *p == protocol)
+            .map(|(_, name)| *name)
+            .unwrap_or_default()
     }
 
     fn handle_backspace_key(&mut self) -> ContentAction {
@@ -236,6 +677,11 @@ impl Content {
     }
 
     fn handle_char_key(&mut self, c: char) -> ContentAction {
+        if self.edit_field == EditField::Auth && !self.is_auth_text_row()
+            || self.edit_field == EditField::Protocol
+        {
+            return ContentAction::Noop;
+        }
         if self.edit_field != EditField::None {
             self.edit_buffer.push(c);
             ContentAction::ContentUpdated
@@ -244,7 +690,7 @@ impl Content {
         }
     }
 
-    fn create_styled_block(&self, theme: &Theme, is_editing: bool) -> Block {
+    fn create_styled_block(&self, theme: &Theme, is_editing: bool) -> Block<'_> {
         Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(if is_editing {
@@ -259,10 +705,10 @@ impl Content {
         &self,
         icon: &'a str,
         title: &'a str,
-        content: &'a str,
+        content: Vec<Span<'a>>,
         theme: &'a Theme,
     ) -> Line<'a> {
-        Line::from(vec![
+        let mut spans = vec![
             Span::styled(icon, Style::default().fg(theme.general.title_focused)),
             Span::raw(" "),
             Span::styled(
@@ -272,10 +718,108 @@ impl Content {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(": "),
-            Span::styled(content, Style::default().fg(theme.general.text)),
-        ])
+        ];
+        spans.extend(content);
+        Line::from(spans)
+    }
+
+    /// Styles `text` with its placeholders either substituted or shown raw.
+    /// Placeholders without a value are always shown raw, in an error color.
+    fn template_spans(&self, text: &str, theme: &Theme) -> Vec<Span<'static>> {
+        let text_style = Style::default().fg(theme.general.text);
+        variables::parse(text)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => Span::styled(text.to_string(), text_style),
+                Segment::Placeholder { raw, name } => match self.variables.get(name) {
+                    // Generated when sending, so there is no value to show yet.
+                    None if Dynamic::parse(name).is_some() => Span::styled(
+                        raw.to_string(),
+                        Style::default()
+                            .fg(theme.http_methods.patch)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                    None => Span::styled(
+                        raw.to_string(),
+                        Style::default()
+                            .fg(theme.http_methods.delete)
+                            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    ),
+                    Some(_) if self.show_raw => Span::styled(
+                        raw.to_string(),
+                        Style::default().fg(theme.general.title_focused),
+                    ),
+                    Some(_) if self.is_secret(name) => {
+                        Span::styled(MASK, Style::default().fg(theme.http_methods.post))
+                    }
+                    Some(value) => {
+                        Span::styled(value.clone(), Style::default().fg(theme.http_methods.post))
+                    }
+                },
+            })
+            .collect()
     }
 
+    /// Placeholders anywhere in what `request` sends that have no value.
+    fn unresolved_variables(&self, request: &RequestData) -> Vec<String> {
+        variables::placeholders(request)
+            .into_iter()
+            .filter(|name| !self.variables.contains_key(name) && Dynamic::parse(name).is_none())
+            .collect()
+    }
+
+    /// Describes the request's auth, with the credential masked if it is
+    /// secret.
+    fn format_auth(request: &RequestData) -> String {
+        let secret = |value: &str| {
+            if request.secret_auth {
+                MASK.to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        let auth = match &request.auth {
+            Some(AuthData::Basic { username, password }) => {
+                format!("Basic {}:{}", username, secret(password))
+            }
+            Some(AuthData::Bearer { token }) => format!("Bearer {}", secret(token)),
+            Some(AuthData::Digest { username, password }) => {
+                format!("Digest {}:{}", username, secret(password))
+            }
+            Some(AuthData::ApiKey {
+                key,
+                value,
+                in_header,
+            }) => format!(
+                "API key {} = {} (in {})",
+                key,
+                secret(value),
+                if *in_header { "header" } else { "query" }
+            ),
+            Some(auth @ AuthData::OAuth2 { client_id, .. }) => {
+                format!("OAuth 2.0 {} as {}", auth::fields(auth)[0].value, client_id)
+            }
+            Some(AuthData::AwsSigV4 {
+                access_key,
+                region,
+                service,
+                ..
+            }) => format!("AWS SigV4 {} for {} in {}", access_key, service, region),
+            Some(auth @ AuthData::Jwt { .. }) => format!("JWT {}", auth::fields(auth)[0].value),
+            Some(auth @ AuthData::Hmac { header, .. }) => {
+                format!("HMAC {} in {}", auth::fields(auth)[0].value, header)
+            }
+            Some(AuthData::Inherit) => "Inherited from the project".to_string(),
+            Some(AuthData::None) | None => "None".to_string(),
+        };
+        if request.secret_auth {
+            format!("🔒 {}", auth)
+        } else {
+            auth
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn render_editable_field(
         &self,
         frame: &mut Frame,
@@ -286,13 +830,16 @@ impl Content {
         is_editing: bool,
         theme: &Theme,
     ) {
-        let content_text = if is_editing {
-            format!("{}▎", self.edit_buffer)
+        let content = if is_editing {
+            vec![Span::styled(
+                format!("{}▎", self.edit_buffer),
+                Style::default().fg(theme.general.text),
+            )]
         } else {
-            content
+            self.template_spans(&content, theme)
         };
 
-        let line = self.create_field_line(icon, title, &content_text, theme);
+        let line = self.create_field_line(icon, title, content, theme);
         let item = ListItem::new(line).style(Style::default().bg(if is_editing {
             theme.sidebar.selected_bg
         } else {
@@ -328,29 +875,49 @@ impl Content {
         Style::default().fg(color).add_modifier(Modifier::BOLD)
     }
 
+    /// Splits the edit form into its rows. Fields are at even indices with
+    /// spacers between them; the submit button is last. The auth field grows
+    /// to fit its form while it is being edited.
+    fn edit_layout(&self, area: Rect) -> Rc<[Rect]> {
+        let auth_height = match &self.request {
+            Some(request) if self.edit_field == EditField::Auth => {
+                // The rows, the preview and the key hints inside a border.
+                Self::auth_rows(&Self::auth(request)).len() as u16 + 4
+            }
+            _ => 3,
+        };
+        Layout::default()
+            .direction(Direction::Vertical)
+            .margin(2)
+            .constraints(vec![
+                Constraint::Length(3),           // Method
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(3),           // URL
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(3),           // Headers
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(3),           // Query Params
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(3),           // Path Params
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(3),           // Variables
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(3),           // Captures
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(auth_height), // Auth
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(3),           // Protocol
+                Constraint::Length(1),           // Spacer
+                Constraint::Min(4),              // Body
+                Constraint::Length(1),           // Spacer
+                Constraint::Length(3),           // Submit button
+            ])
+            .split(area)
+    }
+
     fn render_request_view(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
         if let Some(request) = &self.request {
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .margin(2)
-                .constraints(vec![
-                    Constraint::Length(3), // Method
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // URL
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Headers
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Query Params
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Path Params
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Auth
-                    Constraint::Length(1), // Spacer
-                    Constraint::Min(4),    // Body
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Submit button
-                ])
-                .split(area);
+            let chunks = self.edit_layout(area);
 
             // Render fields
             self.render_editable_field(
@@ -418,6 +985,97 @@ impl Content {
                 theme,
             );
 
+            let variables_text = if self.edit_field == EditField::Variables {
+                // The value of a secret is masked as it is typed.
+                match self.edit_buffer.split_once('=') {
+                    Some((name, value)) if name.trim_start().starts_with("secret ") => {
+                        format!("{}={}", name, "•".repeat(value.chars().count()))
+                    }
+                    _ => self.edit_buffer.clone(),
+                }
+            } else {
+                let mut variables: Vec<_> = request.variables.iter().collect();
+                variables.sort();
+                variables
+                    .into_iter()
+                    .map(|(name, value)| match request.secrets.contains(name) {
+                        true => format!("secret {} = {}", name, MASK),
+                        false => format!("{} = {}", name, value),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            self.render_editable_field(
+                frame,
+                chunks[10],
+                "Variables",
+                "$",
+                variables_text,
+                self.edit_field == EditField::Variables,
+                theme,
+            );
+
+            let captures_text = if self.edit_field == EditField::Captures {
+                self.edit_buffer.clone()
+            } else {
+                request
+                    .captures
+                    .iter()
+                    .map(|c| format!("{} = {}", c.variable, capture::format_source(&c.source)))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            self.render_editable_field(
+                frame,
+                chunks[12],
+                "Captures",
+                "⇲",
+                captures_text,
+                self.edit_field == EditField::Captures,
+                theme,
+            );
+
+            if self.edit_field == EditField::Auth {
+                self.render_auth_form(frame, chunks[14], request, theme);
+            } else {
+                self.render_editable_field(
+                    frame,
+                    chunks[14],
+                    "Auth",
+                    "🔑",
+                    Self::format_auth(request),
+                    false,
+                    theme,
+                );
+            }
+
+            // A choice, so it is never typed into.
+            let is_editing = self.edit_field == EditField::Protocol;
+            let protocol = Self::protocol_name(request.protocol);
+            let protocol = if is_editing {
+                format!("◀ {} ▶", protocol)
+            } else {
+                protocol.to_string()
+            };
+            let line = self.create_field_line(
+                "⇄",
+                "Protocol",
+                vec![Span::styled(
+                    protocol,
+                    Style::default().fg(theme.general.text),
+                )],
+                theme,
+            );
+            let item = ListItem::new(line).style(Style::default().bg(if is_editing {
+                theme.sidebar.selected_bg
+            } else {
+                theme.general.content_bg
+            }));
+            frame.render_widget(
+                List::new(vec![item]).block(self.create_styled_block(theme, is_editing)),
+                chunks[16],
+            );
+
             let body_text = if self.edit_field == EditField::Body {
                 self.edit_buffer.clone()
             } else {
@@ -425,7 +1083,7 @@ impl Content {
             };
             self.render_editable_field(
                 frame,
-                chunks[12],
+                chunks[18],
                 "Body",
                 "⚪",
                 body_text,
@@ -449,12 +1107,153 @@ impl Content {
                 .style(submit_style)
                 .alignment(Alignment::Center);
 
-            frame.render_widget(submit_para, chunks[14]);
+            frame.render_widget(submit_para, chunks[20]);
         } else {
             self.render_empty_message(frame, area, "No request selected", theme);
         }
     }
 
+    /// The auth scheme selector, the scheme's fields and the secret toggle,
+    /// followed by a preview of what is added to the request.
+    fn render_auth_form(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        request: &RequestData,
+        theme: &Theme,
+    ) {
+        let auth = Self::auth(request);
+        let fields = auth::fields(&auth);
+        let selected = self.auth_row();
+        let text_style = Style::default().fg(theme.general.text);
+        let label_style = Style::default().fg(theme.general.title_focused);
+        let choice = |value: &str, is_selected: bool| {
+            let value = if is_selected {
+                format!("◂ {} ▸", value)
+            } else {
+                value.to_string()
+            };
+            Span::styled(value, text_style.add_modifier(Modifier::BOLD))
+        };
+
+        let mut items: Vec<ListItem> = Self::auth_rows(&auth)
+            .into_iter()
+            .map(|row| {
+                let is_selected = row == selected;
+                let line = match row {
+                    AuthRow::Kind => self.create_field_line(
+                        "🔑",
+                        "Auth",
+                        vec![choice(auth::KINDS[auth::kind(&auth)], is_selected)],
+                        theme,
+                    ),
+                    AuthRow::Field(index) => {
+                        let field = &fields[index];
+                        let masked = field.input == auth::Input::Secret && request.secret_auth;
+                        let value = match &field.input {
+                            auth::Input::Choice(_) => choice(&field.value, is_selected),
+                            _ if is_selected && masked => Span::styled(
+                                format!("{}▎", "•".repeat(self.edit_buffer.chars().count())),
+                                text_style,
+                            ),
+                            _ if is_selected => {
+                                Span::styled(format!("{}▎", self.edit_buffer), text_style)
+                            }
+                            _ if masked => Span::styled(MASK, text_style),
+                            _ => Span::styled(field.value.clone(), text_style),
+                        };
+                        Line::from(vec![
+                            Span::styled(format!("   {}: ", field.label), label_style),
+                            value,
+                        ])
+                    }
+                    AuthRow::Secret => Line::from(vec![
+                        Span::styled("   Secret: ", label_style),
+                        choice(if request.secret_auth { "yes" } else { "no" }, is_selected),
+                    ]),
+                };
+                ListItem::new(line).style(Style::default().bg(if is_selected {
+                    theme.sidebar.selected_bg
+                } else {
+                    theme.general.content_bg
+                }))
+            })
+            .collect();
+
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled("   → ", label_style),
+            Span::styled(
+                self.auth_preview(request),
+                Style::default().fg(theme.http_methods.post),
+            ),
+        ])));
+        items.push(ListItem::new(Line::from(Span::styled(
+            "   ↑/↓ select  ←/→ change  Enter save",
+            Style::default().fg(theme.general.text_unfocused),
+        ))));
+
+        let list = List::new(items).block(self.create_styled_block(theme, true));
+        frame.render_widget(list, area);
+    }
+
+    /// What the request's auth adds once placeholders are substituted, with
+    /// the credential masked if it is secret or comes from a secret variable.
+    fn auth_preview(&self, request: &RequestData) -> String {
+        let auth = Self::auth(request);
+        if auth == AuthData::Inherit {
+            return match &self.settings.auth {
+                Some(_) => format!(
+                    "{} (from the project)",
+                    self.auth_preview(&self.settings.apply(request))
+                ),
+                None => "nothing, the project has no default auth".to_string(),
+            };
+        }
+        let uses_secret = auth::fields(&auth).iter().any(|field| {
+            variables::parse(&field.value).into_iter().any(|segment| {
+                matches!(segment, Segment::Placeholder { name, .. } if self.is_secret(name))
+            })
+        });
+        let masked = request.secret_auth || uses_secret;
+
+        match auth::apply(&variables::resolve_auth(&auth, &self.variables)) {
+            Some(Placement::Header(name, value)) => {
+                let value = match value.split_once(' ') {
+                    _ if !masked => value,
+                    Some((scheme, _)) if name == "Authorization" => format!("{} {}", scheme, MASK),
+                    _ => MASK.to_string(),
+                };
+                format!("header {}: {}", name, value)
+            }
+            Some(Placement::Query(name, value)) => {
+                let value = if masked { MASK.to_string() } else { value };
+                format!("query ?{}={}", name, value)
+            }
+            None if matches!(auth, AuthData::OAuth2 { .. }) => {
+                "header Authorization: Bearer <token, fetched or refreshed when sent>".to_string()
+            }
+            None if matches!(auth, AuthData::Digest { .. }) => {
+                "header Authorization: Digest <answer to the server's 401 challenge>".to_string()
+            }
+            None if matches!(auth, AuthData::Jwt { .. }) => format!(
+                "header Authorization: Bearer <{} JWT, minted when sent>",
+                auth::fields(&auth)[0].value
+            ),
+            None if matches!(auth, AuthData::Hmac { .. }) => {
+                let fields = auth::fields(&auth);
+                format!(
+                    "header {}: <HMAC-{} of the template, signed when sent>",
+                    fields[2].value, fields[0].value
+                )
+            }
+            None if matches!(auth, AuthData::AwsSigV4 { .. }) => {
+                "header Authorization: AWS4-HMAC-SHA256 <signature over the request as sent>"
+                    .to_string()
+            }
+            None => "nothing is added to the request".to_string(),
+        }
+    }
+
     fn format_key_value_list(
         &self,
         items: &Option<Vec<(String, String)>>,
@@ -488,45 +1287,100 @@ impl Content {
         frame.render_widget(message, area);
     }
 
-    fn render_response_view(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
-        if let Some(response) = &self.response {
+    fn render_response_view(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        response: Option<&ResponseData>,
+        theme: &Theme,
+    ) {
+        if let Some(response) = response {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .margin(2)
                 .constraints([
-                    Constraint::Length(3), // Status line
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(2), // Headers title
-                    Constraint::Length(6), // Headers content
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(2), // Body title
-                    Constraint::Min(4),    // Body content
+                    Constraint::Length(3 + response.hops.len() as u16), // Status and hops
+                    Constraint::Length(1),                              // Spacer
+                    Constraint::Length(2),                              // Headers title
+                    Constraint::Length(6),                              // Headers content
+                    Constraint::Length(1),                              // Spacer
+                    Constraint::Length(2),                              // Body title
+                    Constraint::Min(4),                                 // Body content
                 ])
                 .split(area);
 
             // Status line
             let status_style = self.get_status_style(response.status_code, theme);
-            let status_line = Line::from(vec![
+            let mut status_spans = vec![
                 Span::styled(
                     format!(" {} ", response.status_code.unwrap_or(0)),
                     status_style,
                 ),
                 Span::raw(" "),
-                Span::styled(
-                    format!("{}ms", response.response_time),
-                    Style::default()
-                        .fg(theme.general.text)
-                        .add_modifier(Modifier::BOLD),
-                ),
-            ]);
+            ];
+            if let Some(version) = &response.http_version {
+                status_spans.push(Span::styled(
+                    format!("{} ", version),
+                    Style::default().fg(theme.general.text_unfocused),
+                ));
+            }
+            status_spans.push(Span::styled(
+                format!("{}ms", response.response_time),
+                Style::default()
+                    .fg(theme.general.text)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            // What the built-in placeholders were when this was sent.
+            if !response.variables.is_empty() {
+                let generated = response
+                    .variables
+                    .iter()
+                    .map(|(name, value)| format!("{} = {}", name, value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                status_spans.push(Span::styled(
+                    format!("  {}", generated),
+                    Style::default().fg(theme.general.text_unfocused),
+                ));
+            }
+            let mut status_lines = vec![Line::from(status_spans)];
+            // Earlier exchanges, with the challenge that led to the next one.
+            for hop in &response.hops {
+                let mut spans = vec![
+                    Span::styled(
+                        "  before: ",
+                        Style::default().fg(theme.general.text_unfocused),
+                    ),
+                    Span::styled(
+                        format!("{}", hop.status_code),
+                        self.get_status_style(Some(hop.status_code), theme),
+                    ),
+                    Span::styled(
+                        format!(" in {}ms", hop.response_time),
+                        Style::default().fg(theme.general.text),
+                    ),
+                ];
+                spans.extend(
+                    hop.response_headers
+                        .iter()
+                        .filter(|(name, _)| name.eq_ignore_ascii_case("www-authenticate"))
+                        .map(|(_, value)| {
+                            Span::styled(
+                                format!("  {}", value),
+                                Style::default().fg(theme.general.text_unfocused),
+                            )
+                        }),
+                );
+                status_lines.push(Line::from(spans));
+            }
 
-            let status_para = Paragraph::new(status_line)
+            let status_para = Paragraph::new(status_lines)
                 .block(self.create_styled_block(theme, false))
                 .style(Style::default().bg(theme.general.content_bg));
             frame.render_widget(status_para, chunks[0]);
 
             // Headers
-            let headers_title = self.create_field_line("✉", "Response Headers", "", theme);
+            let headers_title = self.create_field_line("✉", "Response Headers", Vec::new(), theme);
             frame.render_widget(
                 Paragraph::new(headers_title).style(Style::default().bg(theme.general.content_bg)),
                 chunks[2],
@@ -561,18 +1415,37 @@ impl Content {
             );
             frame.render_widget(headers_para, chunks[3]);
 
+            if self.show_certificates {
+                let title = self.create_field_line("🔒", "Certificates", Vec::new(), theme);
+                frame.render_widget(
+                    Paragraph::new(title).style(Style::default().bg(theme.general.content_bg)),
+                    chunks[5],
+                );
+                self.render_certificates(frame, chunks[6], &response.certificates, theme);
+                return;
+            }
+
             // Body
-            let body_title = self.create_field_line("⚪", "Response Body", "", theme);
+            let body_title = self.create_field_line("⚪", "Response Body", Vec::new(), theme);
             frame.render_widget(
                 Paragraph::new(body_title).style(Style::default().bg(theme.general.content_bg)),
                 chunks[5],
             );
 
-            let body_content = response
-                .response_body
-                .as_ref()
-                .map(|body| body.as_str())
-                .unwrap_or("No body");
+            let body_content = response.response_body.as_deref().unwrap_or("No body");
+
+            // A rejected AWS signature is explained next to the service's error.
+            let body_area = match &response.signing {
+                Some(signing) if matches!(response.status_code, Some(401 | 403)) => {
+                    let halves = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .split(chunks[6]);
+                    self.render_signing(frame, halves[1], signing, theme);
+                    halves[0]
+                }
+                _ => chunks[6],
+            };
 
             let body_para = Paragraph::new(body_content)
                 .block(
@@ -581,7 +1454,7 @@ impl Content {
                         .borders(Borders::LEFT),
                 )
                 .style(Style::default().fg(theme.general.text));
-            frame.render_widget(body_para, chunks[6]);
+            frame.render_widget(body_para, body_area);
         } else {
             self.render_empty_message(
                 frame,
@@ -592,6 +1465,143 @@ impl Content {
         }
     }
 
+    /// Each certificate of the chain, the server's own first, with how long
+    /// it has left.
+    fn render_certificates(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        certificates: &[CertificateInfo],
+        theme: &Theme,
+    ) {
+        let now = chrono::Utc::now().timestamp();
+        let date = |timestamp: i64| {
+            Local
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .map(|t| t.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        };
+        let mut lines = Vec::new();
+        for (i, cert) in certificates.iter().enumerate() {
+            let days = (cert.not_after - now).div_euclid(86_400);
+            let (expiry, color) = if cert.not_after < now {
+                (
+                    format!("expired {} days ago", -days),
+                    theme.http_methods.delete,
+                )
+            } else if cert.not_before > now {
+                ("not valid yet".to_string(), theme.http_methods.delete)
+            } else if days < 30 {
+                (
+                    format!("expires in {} days", days),
+                    theme.http_methods.patch,
+                )
+            } else {
+                (format!("expires in {} days", days), theme.http_methods.get)
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{}. ", i + 1),
+                    Style::default().fg(theme.general.text_unfocused),
+                ),
+                Span::styled(
+                    cert.subject.clone(),
+                    Style::default()
+                        .fg(theme.general.text)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ]));
+            lines.push(Line::styled(
+                format!("   issued by {}", cert.issuer),
+                Style::default().fg(theme.general.text),
+            ));
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!(
+                        "   valid {} to {}  ",
+                        date(cert.not_before),
+                        date(cert.not_after)
+                    ),
+                    Style::default().fg(theme.general.text),
+                ),
+                Span::styled(expiry, Style::default().fg(color)),
+            ]));
+        }
+        if lines.is_empty() {
+            let message = if self.response_from_history {
+                "Certificates are only kept for the latest response"
+            } else {
+                "No certificates: the response was not received over HTTPS"
+            };
+            lines.push(Line::styled(
+                message,
+                Style::default().fg(theme.general.text_unfocused),
+            ));
+        }
+
+        let para = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .style(Style::default().bg(theme.general.content_bg))
+                    .borders(Borders::LEFT),
+            )
+            .wrap(Wrap { trim: false });
+        frame.render_widget(para, area);
+    }
+
+    /// Names of the headers the request sends whose values come from secret
+    /// variables.
+    fn secret_headers(&self) -> Vec<String> {
+        let Some(request) = &self.request else {
+            return Vec::new();
+        };
+        self.settings
+            .apply(request)
+            .headers
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, value)| {
+                variables::parse(value).into_iter().any(|segment| {
+                    matches!(segment, Segment::Placeholder { name, .. } if self.is_secret(name))
+                })
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    fn render_signing(&self, frame: &mut Frame, area: Rect, signing: &Signing, theme: &Theme) {
+        let heading = |text: &'static str| {
+            Line::from(Span::styled(
+                text,
+                Style::default()
+                    .fg(theme.general.text)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        };
+        let canonical_request =
+            sigv4::mask_headers(&signing.canonical_request, &self.secret_headers(), MASK);
+        let mut lines = vec![heading("Canonical request")];
+        lines.extend(
+            canonical_request
+                .lines()
+                .map(|line| Line::raw(line.to_string())),
+        );
+        lines.push(Line::raw(""));
+        lines.push(heading("String to sign"));
+        lines.extend(signing.string_to_sign.lines().map(Line::raw));
+
+        let para = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .style(Style::default().bg(theme.general.content_bg))
+                    .borders(Borders::LEFT),
+            )
+            .style(Style::default().fg(theme.general.text_unfocused))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(para, area);
+    }
+
     fn render_request_summary(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
         if let Some(request) = &self.request {
             let chunks = Layout::default()
@@ -606,31 +1616,68 @@ impl Content {
 
             // Request summary
             let method_style = self.get_method_style(request.method.as_deref(), theme);
-            let summary_line = Line::from(vec![
+            let mut spans = vec![
                 Span::styled(
                     format!(" {} ", request.method.clone().unwrap_or_default()),
                     method_style,
                 ),
                 Span::raw(" "),
-                Span::styled(
-                    request.url.clone().unwrap_or_default(),
+            ];
+            let url = request.url.as_deref().unwrap_or_default();
+            let url = if self.show_raw {
+                url.to_string()
+            } else {
+                self.settings.url_for(url)
+            };
+            spans.extend(self.template_spans(&url, theme));
+            if !request.protocol.is_auto() {
+                spans.push(Span::styled(
+                    format!(" over {}", Self::protocol_name(request.protocol)),
                     Style::default().fg(theme.general.text),
+                ));
+            }
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                format!(
+                    "(Press 'e' to edit, 's' to send, 'h' for history, 'v' for {})",
+                    if self.show_raw {
+                        "resolved values"
+                    } else {
+                        "raw templates"
+                    }
                 ),
-                Span::raw(" "),
-                Span::styled(
-                    "(Press 'e' to edit)",
-                    Style::default().fg(theme.general.text_unfocused),
-                ),
-            ]);
+                Style::default().fg(theme.general.text_unfocused),
+            ));
+            let summary_line = Line::from(spans);
 
+            let mut summary_block = self.create_styled_block(theme, false);
+            let unresolved = self.unresolved_variables(request);
+            if !unresolved.is_empty() {
+                summary_block = summary_block.title(Span::styled(
+                    format!(" ⚠ Unresolved: {} ", unresolved.join(", ")),
+                    Style::default()
+                        .fg(theme.http_methods.delete)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
             let summary_para = Paragraph::new(summary_line)
-                .block(self.create_styled_block(theme, false))
+                .block(summary_block)
                 .style(Style::default().bg(theme.general.content_bg));
             frame.render_widget(summary_para, chunks[0]);
 
             // Response section
-            if self.response.is_some() {
-                self.render_response_view(frame, chunks[2], theme);
+            if let Some((tick_count, elapsed)) = self.pending {
+                let frame_index = (tick_count as usize / 2) % SPINNER_FRAMES.len();
+                let message = format!(
+                    "{} Sending request... {:.1}s (press 'x' to cancel)",
+                    SPINNER_FRAMES[frame_index],
+                    elapsed.as_secs_f32()
+                );
+                self.render_empty_message(frame, chunks[2], &message, theme);
+            } else if self.response.is_some() {
+                self.render_response_view(frame, chunks[2], self.response.as_ref(), theme);
+            } else if let Some(error) = &self.response_error {
+                self.render_empty_message(frame, chunks[2], error, theme);
             } else {
                 self.render_empty_message(frame, chunks[2], "No response available", theme);
             }
@@ -639,29 +1686,196 @@ impl Content {
         }
     }
 
-    fn handle_mouse_click(&mut self, mouse_event: &MouseEvent, area: Rect) -> ContentAction {
-        if let Some(request) = &self.request {
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .margin(2)
-                .constraints(vec![
-                    Constraint::Length(3), // Method
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // URL
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Headers
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Query Params
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Path Params
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Auth
-                    Constraint::Length(1), // Spacer
-                    Constraint::Min(4),    // Body
-                    Constraint::Length(1), // Spacer
-                    Constraint::Length(3), // Submit button
+    fn format_timestamp(timestamp: i64) -> String {
+        Local
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default()
+    }
+
+    fn render_history_view(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        if self.history.is_empty() {
+            self.render_empty_message(
+                frame,
+                area,
+                "No history yet - Send the request to record a response",
+                theme,
+            );
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(38), Constraint::Min(0)])
+            .split(area);
+
+        let items: Vec<ListItem> = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let is_selected = i == self.history_index;
+                let bg = if is_selected {
+                    theme.sidebar.selected_bg
+                } else {
+                    theme.general.content_bg
+                };
+                // Hops come first, as in `401→200`.
+                let mut spans: Vec<Span> = entry
+                    .hops
+                    .iter()
+                    .map(|hop| {
+                        Span::styled(
+                            format!(" {}→", hop.status_code),
+                            self.get_status_style(Some(hop.status_code), theme),
+                        )
+                    })
+                    .collect();
+                spans.extend([
+                    Span::styled(
+                        format!(" {} ", entry.status_code.unwrap_or(0)),
+                        self.get_status_style(entry.status_code, theme),
+                    ),
+                    Span::styled(
+                        format!("{:>6}ms ", entry.response_time),
+                        Style::default().fg(theme.general.text),
+                    ),
+                    Span::styled(
+                        Self::format_timestamp(entry.timestamp),
+                        Style::default().fg(theme.general.text_unfocused),
+                    ),
+                ]);
+                ListItem::new(Line::from(spans)).style(Style::default().bg(bg))
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            self.create_styled_block(theme, true).title(Span::styled(
+                format!(" History ({}) ", self.history.len()),
+                Style::default()
+                    .fg(theme.general.title_focused)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+        frame.render_widget(list, chunks[0]);
+
+        self.render_response_view(
+            frame,
+            chunks[1],
+            self.history.get(self.history_index),
+            theme,
+        );
+    }
+
+    /// Lists every variable the request can use or refers to, with the
+    /// scope its value comes from and the scopes it overrides.
+    fn render_variable_inspector(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let Some(request) = &self.request else {
+            self.render_empty_message(frame, area, "No request selected", theme);
+            return;
+        };
+
+        let used = variables::placeholders(request);
+        let mut names = self.scopes.names(request);
+        names.extend(
+            used.iter()
+                .filter(|n| !names.contains(n))
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+        names.sort();
+
+        let muted = Style::default().fg(theme.general.text_unfocused);
+        let rows: Vec<Row> = names
+            .iter()
+            .map(|name| {
+                let marker = if used.contains(name) { "●" } else { " " };
+                let definitions = self.scopes.definitions(request, name);
+                let (value, source) = match definitions.first() {
+                    Some((scope, _)) if self.is_secret(name) => (
+                        Span::styled(MASK, Style::default().fg(theme.general.text)),
+                        Span::styled(
+                            scope.label(),
+                            Style::default().fg(theme.general.title_focused),
+                        ),
+                    ),
+                    Some((scope, value)) => (
+                        Span::styled(value.to_string(), Style::default().fg(theme.general.text)),
+                        Span::styled(
+                            scope.label(),
+                            Style::default().fg(theme.general.title_focused),
+                        ),
+                    ),
+                    None if Dynamic::parse(name).is_some() => (
+                        Span::styled(
+                            "generated on send",
+                            Style::default()
+                                .fg(theme.http_methods.patch)
+                                .add_modifier(Modifier::ITALIC),
+                        ),
+                        Span::styled("built-in", Style::default().fg(theme.general.title_focused)),
+                    ),
+                    None => (
+                        Span::styled("unresolved", Style::default().fg(theme.http_methods.delete)),
+                        Span::styled("-", muted),
+                    ),
+                };
+                let overrides = definitions
+                    .iter()
+                    .skip(1)
+                    .map(|(scope, value)| {
+                        let value = if self.is_secret(name) { MASK } else { value };
+                        format!("{} ({})", scope.label(), value)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                Row::new(vec![
+                    Cell::from(Span::styled(
+                        marker,
+                        Style::default().fg(theme.http_methods.post),
+                    )),
+                    Cell::from(Span::styled(
+                        name.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )),
+                    Cell::from(value),
+                    Cell::from(source),
+                    Cell::from(Span::styled(overrides, muted)),
                 ])
-                .split(area);
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(2),
+                Constraint::Percentage(20),
+                Constraint::Percentage(35),
+                Constraint::Length(12),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["", "Name", "Value", "From", "Overrides"])
+                .style(muted.add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            self.create_styled_block(theme, true).title(Span::styled(
+                " Variables (● used by this request; request > environment > project > global) ",
+                Style::default()
+                    .fg(theme.general.title_focused)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        )
+        .style(Style::default().bg(theme.general.content_bg));
+        frame.render_widget(table, area);
+    }
+
+    fn handle_mouse_click(&mut self, mouse_event: &MouseEvent, area: Rect) -> ContentAction {
+        if let Some(request) = self.request.clone() {
+            let chunks = self.edit_layout(area);
 
             let previous_field = self.edit_field.clone();
 
@@ -674,7 +1888,7 @@ impl Content {
             };
 
             // Check which field was clicked
-            self.edit_field = if is_within(chunks[0]) {
+            let clicked = if is_within(chunks[0]) {
                 EditField::Method
             } else if is_within(chunks[2]) {
                 EditField::Url
@@ -684,25 +1898,61 @@ impl Content {
                 EditField::QueryParams
             } else if is_within(chunks[8]) {
                 EditField::PathParams
+            } else if is_within(chunks[10]) {
+                EditField::Variables
             } else if is_within(chunks[12]) {
+                EditField::Captures
+            } else if is_within(chunks[14]) {
+                EditField::Auth
+            } else if is_within(chunks[16]) {
+                EditField::Protocol
+            } else if is_within(chunks[18]) {
                 EditField::Body
             } else {
                 self.edit_field.clone()
             };
 
-            // If field changed, update edit buffer
-            if self.edit_field != previous_field {
+            // If field changed, keep what was typed into the auth form and
+            // update edit buffer
+            if clicked != previous_field {
+                let changed = self.commit_auth_field();
+                self.edit_field = clicked;
                 self.edit_buffer = match self.edit_field {
                     EditField::Method => request.method.clone().unwrap_or_default(),
                     EditField::Url => request.url.clone().unwrap_or_default(),
                     EditField::Body => request.body.clone().unwrap_or_default(),
                     _ => String::new(),
                 };
-                return ContentAction::ContentUpdated;
+                self.auth_row = 0;
+                return self.focus_changed(changed);
+            }
+
+            // Select the clicked row of the auth form
+            if self.edit_field == EditField::Auth && is_within(chunks[14]) {
+                let row = (mouse_event.row - chunks[14].y).saturating_sub(1) as usize;
+                let changed = self.commit_auth_field();
+                let auth = self
+                    .request
+                    .as_ref()
+                    .map(Self::auth)
+                    .unwrap_or(AuthData::None);
+                if row < Self::auth_rows(&auth).len() {
+                    self.auth_row = row;
+                    self.edit_buffer = match self.auth_row() {
+                        AuthRow::Field(index) => auth::fields(&auth)[index].value.clone(),
+                        _ => String::new(),
+                    };
+                }
+                return self.focus_changed(changed);
+            }
+
+            // Clicking the protocol again switches to the next one
+            if self.edit_field == EditField::Protocol && is_within(chunks[16]) {
+                return self.cycle_protocol(true);
             }
 
             // Check if submit button was clicked
-            if is_within(chunks[14]) {
+            if is_within(chunks[20]) {
                 if let Some(request) = self.request.clone() {
                     return ContentAction::RequestUpdated(request);
                 }
@@ -718,7 +1968,10 @@ impl Component for Content {
     fn tick(&mut self, event: Option<&Event>, _: u32) -> Self::Action {
         if let Some(event) = event {
             match event {
-                Event::Key(key_event) => self.handle_edit_key(key_event.code),
+                Event::Key(key_event) => match self.view_mode {
+                    ViewMode::History => self.handle_history_key(key_event.code),
+                    _ => self.handle_edit_key(key_event.code),
+                },
                 Event::Mouse(mouse_event) => match mouse_event.kind {
                     MouseEventKind::Down(crossterm::event::MouseButton::Left) => {
                         if let Some(rect) = self.rect {
@@ -750,6 +2003,8 @@ impl Component for Content {
         let title_prefix = match self.view_mode {
             ViewMode::View => "View",
             ViewMode::Edit => "Edit",
+            ViewMode::History => "History",
+            ViewMode::Variables => "Variables",
         };
 
         let block = Block::default()
@@ -767,6 +2022,145 @@ impl Component for Content {
         match self.view_mode {
             ViewMode::View => self.render_request_summary(frame, inner_rect, theme),
             ViewMode::Edit => self.render_request_view(frame, inner_rect, theme),
+            ViewMode::History => self.render_history_view(frame, inner_rect, theme),
+            ViewMode::Variables => self.render_variable_inspector(frame, inner_rect, theme),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyModifiers, MouseButton};
+
+    fn editing_auth() -> Content {
+        let mut request = RequestData::new("login".to_string());
+        request.auth = Some(AuthData::Basic {
+            username: String::new(),
+            password: String::new(),
+        });
+        let mut content = Content::new();
+        content.set_request(request);
+        content.enter_edit_mode();
+        content.edit_field = EditField::Auth;
+        // The username field, below the scheme selector.
+        content.handle_edit_key(KeyCode::Down);
+        for c in "me".chars() {
+            content.handle_edit_key(KeyCode::Char(c));
+        }
+        content
+    }
+
+    fn username(action: ContentAction) -> String {
+        match action {
+            ContentAction::RequestUpdated(RequestData {
+                auth: Some(AuthData::Basic { username, .. }),
+                ..
+            }) => username,
+            _ => panic!("the request was not updated"),
+        }
+    }
+
+    #[test]
+    fn test_leaving_auth_keeps_typed_text() {
+        for key in [KeyCode::Tab, KeyCode::BackTab] {
+            let mut content = editing_auth();
+            assert_eq!(username(content.handle_edit_key(key)), "me");
+            assert!(content.edit_field != EditField::Auth);
+        }
+
+        // Clicking another field or another row of the form does too.
+        let area = Rect::new(0, 0, 100, 80);
+        let click = |rect: Rect| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: rect.x + 1,
+            row: rect.y + 1,
+            modifiers: KeyModifiers::empty(),
+        };
+        let mut content = editing_auth();
+        let url = content.edit_layout(area)[2];
+        assert_eq!(
+            username(content.handle_mouse_click(&click(url), area)),
+            "me"
+        );
+        assert!(content.edit_field == EditField::Url);
+
+        let mut content = editing_auth();
+        let form = content.edit_layout(area)[14];
+        // The first row inside the border is the scheme selector.
+        assert_eq!(
+            username(content.handle_mouse_click(&click(form), area)),
+            "me"
+        );
+        assert!(content.auth_row() == AuthRow::Kind);
+    }
+
+    #[test]
+    fn test_request_variables_can_be_secret() {
+        let mut content = Content::new();
+        content.set_request(RequestData::new("login".to_string()));
+        content.enter_edit_mode();
+        content.edit_field = EditField::Variables;
+        let mut enter = |line: &str| {
+            for c in line.chars() {
+                content.handle_edit_key(KeyCode::Char(c));
+            }
+            match content.handle_edit_key(KeyCode::Enter) {
+                ContentAction::RequestUpdated(request) => request,
+                _ => panic!("the request was not updated"),
+            }
+        };
+
+        let request = enter("secret otp = 123456");
+        assert_eq!(request.variables["otp"], "123456");
+        assert!(request.secrets.contains("otp"));
+
+        // Setting it again without the keyword makes it plain.
+        let request = enter("otp = 654321");
+        assert_eq!(request.variables["otp"], "654321");
+        assert!(request.secrets.is_empty());
+    }
+
+    #[test]
+    fn test_history_entries_are_marked() {
+        let response = ResponseData {
+            request_id: 1,
+            status_code: Some(200),
+            response_body: None,
+            response_headers: None,
+            response_time: 0,
+            timestamp: 0,
+            http_version: None,
+            variables: Vec::new(),
+            hops: Vec::new(),
+            signing: None,
+            certificates: Vec::new(),
+        };
+        let mut content = Content::new();
+        content.set_request(RequestData::new("login".to_string()));
+        content.open_history(vec![response.clone()]);
+        content.handle_history_key(KeyCode::Enter);
+        assert!(content.response_from_history);
+
+        content.set_response(response);
+        assert!(!content.response_from_history);
+    }
+
+    #[test]
+    fn test_added_header_is_in_the_update() {
+        let mut content = Content::new();
+        content.set_request(RequestData::new("login".to_string()));
+        content.enter_edit_mode();
+        content.edit_field = EditField::Headers;
+        for c in "accept: text/plain".chars() {
+            content.handle_edit_key(KeyCode::Char(c));
+        }
+        match content.handle_edit_key(KeyCode::Enter) {
+            ContentAction::RequestUpdated(request) => assert_eq!(
+                request.headers,
+                Some(vec![("accept".to_string(), "text/plain".to_string())])
+            ),
+            _ => panic!("the request was not updated"),
         }
     }
 }
@@ -0,0 +1,682 @@
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEventKind};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table},
+    Frame,
+};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::components::Component;
+use crate::persistence::{Environment, GlobalVariables, ProjectUpdate};
+use crate::theme::Theme;
+use crate::variables::MASK;
+
+#[allow(clippy::large_enum_variant)]
+pub enum EnvironmentAction {
+    Noop,
+    Updated,
+    ProjectUpdate(ProjectUpdate),
+    /// The global variables were edited; they belong to no project.
+    UpdateGlobals(GlobalVariables),
+    Close,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum Focus {
+    Environments,
+    Variables,
+}
+
+/// A set of variables in the list on the left.
+#[derive(PartialEq, Clone, Copy)]
+enum VariableSet {
+    Global,
+    Project,
+    Environment(usize),
+}
+
+/// The global and project variables are listed above the environments.
+const SHARED_SETS: usize = 2;
+
+/// What the text being typed at the bottom of the panel is for.
+enum InputTarget {
+    NewEnvironment,
+    RenameEnvironment,
+    NewVariable,
+    RenameVariable(String),
+    EditValue(String),
+}
+
+/// Lists the global and project variables and the project's environments on
+/// the left, and the selected set's variables on the right. Every change is
+/// handed back as an action; the panel only shows what it is given.
+pub struct EnvironmentPanel {
+    globals: GlobalVariables,
+    project: HashMap<String, String>,
+    project_secrets: BTreeSet<String>,
+    environments: Vec<Environment>,
+    active: Option<String>,
+    /// Row in the list on the left: the shared sets, then the environments.
+    selected_set: usize,
+    selected_var: usize,
+    focus: Focus,
+    input: Option<(InputTarget, String)>,
+    list_rect: Option<Rect>,
+    table_rect: Option<Rect>,
+}
+
+impl EnvironmentPanel {
+    pub fn new() -> Self {
+        EnvironmentPanel {
+            globals: GlobalVariables::default(),
+            project: HashMap::new(),
+            project_secrets: BTreeSet::new(),
+            environments: Vec::new(),
+            active: None,
+            selected_set: 0,
+            selected_var: 0,
+            focus: Focus::Environments,
+            input: None,
+            list_rect: None,
+            table_rect: None,
+        }
+    }
+
+    pub fn set_environments(&mut self, environments: Vec<Environment>, active: Option<String>) {
+        self.environments = environments;
+        self.active = active;
+        self.selected_set = self
+            .selected_set
+            .min(SHARED_SETS + self.environments.len() - 1);
+        self.clamp_variable();
+    }
+
+    pub fn set_shared_variables(
+        &mut self,
+        globals: GlobalVariables,
+        project: HashMap<String, String>,
+        project_secrets: BTreeSet<String>,
+    ) {
+        self.globals = globals;
+        self.project = project;
+        self.project_secrets = project_secrets;
+        self.clamp_variable();
+    }
+
+    /// Starts on the environment list, with the active environment selected.
+    pub fn open(&mut self) {
+        self.focus = Focus::Environments;
+        self.input = None;
+        if let Some(index) = self
+            .active
+            .as_ref()
+            .and_then(|name| self.environments.iter().position(|e| &e.name == name))
+        {
+            self.selected_set = SHARED_SETS + index;
+        }
+        self.clamp_variable();
+    }
+
+    fn set_at(row: usize) -> VariableSet {
+        match row {
+            0 => VariableSet::Global,
+            1 => VariableSet::Project,
+            _ => VariableSet::Environment(row - SHARED_SETS),
+        }
+    }
+
+    fn selected_set(&self) -> VariableSet {
+        Self::set_at(self.selected_set)
+    }
+
+    fn selected_environment(&self) -> Option<&Environment> {
+        match self.selected_set() {
+            VariableSet::Environment(index) => self.environments.get(index),
+            _ => None,
+        }
+    }
+
+    fn selected_variables(&self) -> Option<&HashMap<String, String>> {
+        match self.selected_set() {
+            VariableSet::Global => Some(&self.globals.variables),
+            VariableSet::Project => Some(&self.project),
+            VariableSet::Environment(index) => self.environments.get(index).map(|e| &e.variables),
+        }
+    }
+
+    fn selected_secrets(&self) -> Option<&BTreeSet<String>> {
+        match self.selected_set() {
+            VariableSet::Global => Some(&self.globals.secrets),
+            VariableSet::Project => Some(&self.project_secrets),
+            VariableSet::Environment(index) => self.environments.get(index).map(|e| &e.secrets),
+        }
+    }
+
+    /// Variable names of the selected set, in the order shown.
+    fn variable_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .selected_variables()
+            .map(|variables| variables.keys().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    fn is_secret(&self, name: &str) -> bool {
+        self.selected_secrets()
+            .is_some_and(|secrets| secrets.contains(name))
+    }
+
+    fn selected_variable(&self) -> Option<String> {
+        self.variable_names().into_iter().nth(self.selected_var)
+    }
+
+    fn clamp_variable(&mut self) {
+        let count = self.variable_names().len();
+        self.selected_var = self.selected_var.min(count.saturating_sub(1));
+    }
+
+    fn select_variable(&mut self, name: &str) {
+        if let Some(index) = self.variable_names().iter().position(|n| n == name) {
+            self.selected_var = index;
+        }
+    }
+
+    fn start_input(&mut self, target: InputTarget) {
+        let text = match &target {
+            InputTarget::RenameEnvironment => self
+                .selected_environment()
+                .map(|env| env.name.clone())
+                .unwrap_or_default(),
+            InputTarget::RenameVariable(name) => name.clone(),
+            InputTarget::EditValue(name) => self
+                .selected_variables()
+                .and_then(|variables| variables.get(name).cloned())
+                .unwrap_or_default(),
+            InputTarget::NewEnvironment | InputTarget::NewVariable => String::new(),
+        };
+        self.input = Some((target, text));
+    }
+
+    /// The selected environment with `change` applied, as an update.
+    fn update_selected(&self, change: impl FnOnce(&mut Environment)) -> EnvironmentAction {
+        match (self.selected_set(), self.selected_environment()) {
+            (VariableSet::Environment(index), Some(env)) => {
+                let mut env = env.clone();
+                change(&mut env);
+                EnvironmentAction::ProjectUpdate(ProjectUpdate::UpdateEnvironment(index, env))
+            }
+            _ => EnvironmentAction::Noop,
+        }
+    }
+
+    /// The selected set's variables and secret names with `change` applied,
+    /// as an update.
+    fn update_variables(
+        &self,
+        change: impl FnOnce(&mut HashMap<String, String>, &mut BTreeSet<String>),
+    ) -> EnvironmentAction {
+        match self.selected_set() {
+            VariableSet::Global => {
+                let mut globals = self.globals.clone();
+                change(&mut globals.variables, &mut globals.secrets);
+                EnvironmentAction::UpdateGlobals(globals)
+            }
+            VariableSet::Project => {
+                let mut variables = self.project.clone();
+                let mut secrets = self.project_secrets.clone();
+                change(&mut variables, &mut secrets);
+                EnvironmentAction::ProjectUpdate(ProjectUpdate::UpdateVariables {
+                    variables,
+                    secrets,
+                })
+            }
+            VariableSet::Environment(_) => {
+                self.update_selected(|env| change(&mut env.variables, &mut env.secrets))
+            }
+        }
+    }
+
+    fn unique_name(&self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut n = 1;
+        while self.environments.iter().any(|e| e.name == name) {
+            n += 1;
+            name = format!("{} {}", base, n);
+        }
+        name
+    }
+
+    fn handle_input(&mut self, key: KeyCode) -> EnvironmentAction {
+        let Some((_, text)) = self.input.as_mut() else {
+            return EnvironmentAction::Noop;
+        };
+
+        match key {
+            KeyCode::Char(c) => {
+                text.push(c);
+                EnvironmentAction::Updated
+            }
+            KeyCode::Backspace => {
+                text.pop();
+                EnvironmentAction::Updated
+            }
+            KeyCode::Esc => {
+                self.input = None;
+                EnvironmentAction::Updated
+            }
+            KeyCode::Enter => {
+                let (target, text) = self.input.take().unwrap();
+                self.submit_input(target, text)
+            }
+            _ => EnvironmentAction::Noop,
+        }
+    }
+
+    fn submit_input(&mut self, target: InputTarget, text: String) -> EnvironmentAction {
+        let name = text.trim().to_string();
+        match target {
+            // Values are taken as typed; everything else is a name.
+            InputTarget::EditValue(variable) => self.update_variables(|variables, _| {
+                variables.insert(variable, text);
+            }),
+            _ if name.is_empty() => EnvironmentAction::Updated,
+            InputTarget::NewEnvironment => {
+                self.selected_set = SHARED_SETS + self.environments.len();
+                self.selected_var = 0;
+                EnvironmentAction::ProjectUpdate(ProjectUpdate::AddEnvironment(Environment {
+                    name: self.unique_name(&name),
+                    variables: Default::default(),
+                    secrets: Default::default(),
+                    tls: Default::default(),
+                }))
+            }
+            InputTarget::RenameEnvironment => {
+                let is_taken = self
+                    .environments
+                    .iter()
+                    .enumerate()
+                    .any(|(i, e)| SHARED_SETS + i != self.selected_set && e.name == name);
+                let name = if is_taken {
+                    self.unique_name(&name)
+                } else {
+                    name
+                };
+                self.update_selected(|env| env.name = name)
+            }
+            InputTarget::NewVariable => {
+                let exists = self
+                    .selected_variables()
+                    .is_some_and(|variables| variables.contains_key(&name));
+                // Go straight on to the value of the new variable.
+                self.start_input(InputTarget::EditValue(name.clone()));
+                if exists {
+                    self.select_variable(&name);
+                    return EnvironmentAction::Updated;
+                }
+                let action = self.update_variables(|variables, _| {
+                    variables.insert(name.clone(), String::new());
+                });
+                let mut names = self.variable_names();
+                names.push(name.clone());
+                names.sort();
+                self.selected_var = names.iter().position(|n| n == &name).unwrap_or(0);
+                action
+            }
+            InputTarget::RenameVariable(old) => {
+                let action = self.update_variables(|variables, secrets| {
+                    if let Some(value) = variables.remove(&old) {
+                        variables.insert(name.clone(), value);
+                    }
+                    if secrets.remove(&old) {
+                        secrets.insert(name.clone());
+                    }
+                });
+                let mut names = self.variable_names();
+                names.retain(|n| n != &old);
+                names.push(name.clone());
+                names.sort();
+                self.selected_var = names.iter().position(|n| n == &name).unwrap_or(0);
+                action
+            }
+        }
+    }
+
+    fn handle_environment_keys(&mut self, key: KeyCode) -> EnvironmentAction {
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.selected_set + 1 < SHARED_SETS + self.environments.len() {
+                    self.selected_set += 1;
+                    self.selected_var = 0;
+                }
+                EnvironmentAction::Updated
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                if self.selected_set > 0 {
+                    self.selected_set -= 1;
+                    self.selected_var = 0;
+                }
+                EnvironmentAction::Updated
+            }
+            KeyCode::Char('n') => {
+                self.start_input(InputTarget::NewEnvironment);
+                EnvironmentAction::Updated
+            }
+            KeyCode::Char('r') if self.selected_environment().is_some() => {
+                self.start_input(InputTarget::RenameEnvironment);
+                EnvironmentAction::Updated
+            }
+            KeyCode::Char('c') => match self.selected_environment() {
+                Some(env) => {
+                    let copy = Environment {
+                        name: self.unique_name(&format!("{} copy", env.name)),
+                        variables: env.variables.clone(),
+                        secrets: env.secrets.clone(),
+                        tls: env.tls.clone(),
+                    };
+                    self.selected_set = SHARED_SETS + self.environments.len();
+                    EnvironmentAction::ProjectUpdate(ProjectUpdate::AddEnvironment(copy))
+                }
+                None => EnvironmentAction::Noop,
+            },
+            KeyCode::Char('d') => match self.selected_set() {
+                VariableSet::Environment(index) => {
+                    EnvironmentAction::ProjectUpdate(ProjectUpdate::DeleteEnvironment(index))
+                }
+                _ => EnvironmentAction::Noop,
+            },
+            // Toggles whether the selected environment is the active one.
+            KeyCode::Char('a') | KeyCode::Char(' ') => match self.selected_environment() {
+                Some(env) => {
+                    let name =
+                        Some(env.name.clone()).filter(|name| self.active.as_ref() != Some(name));
+                    EnvironmentAction::ProjectUpdate(ProjectUpdate::SetActiveEnvironment(name))
+                }
+                None => EnvironmentAction::Noop,
+            },
+            KeyCode::Enter | KeyCode::Tab | KeyCode::Char('l') | KeyCode::Right => {
+                self.focus = Focus::Variables;
+                self.clamp_variable();
+                EnvironmentAction::Updated
+            }
+            KeyCode::Esc | KeyCode::Char('q') => EnvironmentAction::Close,
+            _ => EnvironmentAction::Noop,
+        }
+    }
+
+    fn handle_variable_keys(&mut self, key: KeyCode) -> EnvironmentAction {
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.selected_var + 1 < self.variable_names().len() {
+                    self.selected_var += 1;
+                }
+                EnvironmentAction::Updated
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selected_var = self.selected_var.saturating_sub(1);
+                EnvironmentAction::Updated
+            }
+            KeyCode::Char('n') => {
+                self.start_input(InputTarget::NewVariable);
+                EnvironmentAction::Updated
+            }
+            KeyCode::Enter | KeyCode::Char('e') => match self.selected_variable() {
+                Some(name) => {
+                    self.start_input(InputTarget::EditValue(name));
+                    EnvironmentAction::Updated
+                }
+                None => EnvironmentAction::Noop,
+            },
+            KeyCode::Char('r') => match self.selected_variable() {
+                Some(name) => {
+                    self.start_input(InputTarget::RenameVariable(name));
+                    EnvironmentAction::Updated
+                }
+                None => EnvironmentAction::Noop,
+            },
+            KeyCode::Char('d') => match self.selected_variable() {
+                Some(name) => self.update_variables(|variables, secrets| {
+                    variables.remove(&name);
+                    secrets.remove(&name);
+                }),
+                None => EnvironmentAction::Noop,
+            },
+            KeyCode::Char('s') => match self.selected_variable() {
+                Some(name) => self.update_variables(|_, secrets| {
+                    if !secrets.remove(&name) {
+                        secrets.insert(name);
+                    }
+                }),
+                None => EnvironmentAction::Noop,
+            },
+            KeyCode::Esc | KeyCode::Tab | KeyCode::Char('h') | KeyCode::Left => {
+                self.focus = Focus::Environments;
+                EnvironmentAction::Updated
+            }
+            _ => EnvironmentAction::Noop,
+        }
+    }
+
+    fn handle_click(&mut self, column: u16, row: u16) -> EnvironmentAction {
+        let contains = |rect: Option<Rect>| {
+            rect.is_some_and(|r| {
+                column >= r.x && column < r.x + r.width && row >= r.y && row < r.y + r.height
+            })
+        };
+
+        // Both widgets have a border; the table also has a header row.
+        if let Some(rect) = self.list_rect.filter(|_| contains(self.list_rect)) {
+            let index = row.saturating_sub(rect.y + 1) as usize;
+            if index < SHARED_SETS + self.environments.len() {
+                self.selected_set = index;
+                self.selected_var = 0;
+            }
+            self.focus = Focus::Environments;
+            return EnvironmentAction::Updated;
+        }
+        if let Some(rect) = self.table_rect.filter(|_| contains(self.table_rect)) {
+            if let Some(index) = row.checked_sub(rect.y + 2).map(|i| i as usize) {
+                if index < self.variable_names().len() {
+                    self.selected_var = index;
+                }
+            }
+            self.focus = Focus::Variables;
+            return EnvironmentAction::Updated;
+        }
+        EnvironmentAction::Noop
+    }
+
+    fn create_block(&self, title: &str, is_focused: bool, theme: &Theme) -> Block<'static> {
+        let color = if is_focused {
+            theme.general.title_focused
+        } else {
+            theme.general.text_unfocused
+        };
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color))
+            .style(Style::default().bg(theme.general.content_bg))
+            .title(Span::styled(
+                format!(" {} ", title),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+    }
+
+    fn set_name(&self, set: VariableSet) -> String {
+        match set {
+            VariableSet::Global => "Global".to_string(),
+            VariableSet::Project => "Project".to_string(),
+            VariableSet::Environment(index) => self
+                .environments
+                .get(index)
+                .map(|env| env.name.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn render_environments(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let items: Vec<ListItem> = (0..SHARED_SETS + self.environments.len())
+            .map(|row| {
+                let set = Self::set_at(row);
+                let bg = if row == self.selected_set {
+                    theme.sidebar.selected_bg
+                } else {
+                    theme.general.content_bg
+                };
+                let name = self.set_name(set);
+                // The shared sets always apply, so they are set apart rather
+                // than marked active.
+                let (marker, style) = match set {
+                    VariableSet::Environment(_) if self.active.as_ref() == Some(&name) => {
+                        (" ● ", Style::default().fg(theme.general.text))
+                    }
+                    VariableSet::Environment(_) => {
+                        ("   ", Style::default().fg(theme.general.text_unfocused))
+                    }
+                    VariableSet::Global | VariableSet::Project => (
+                        " ◆ ",
+                        Style::default()
+                            .fg(theme.general.text_unfocused)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(marker, Style::default().fg(theme.http_methods.post)),
+                    Span::styled(name, style),
+                ]))
+                .style(Style::default().bg(bg))
+            })
+            .collect();
+
+        let list = List::new(items).block(self.create_block(
+            &format!("Environments ({})", self.environments.len()),
+            self.focus == Focus::Environments,
+            theme,
+        ));
+        frame.render_widget(list, area);
+    }
+
+    fn render_variables(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let title = format!("Variables · {}", self.set_name(self.selected_set()));
+        let block = self.create_block(&title, self.focus == Focus::Variables, theme);
+
+        let Some(variables) = self.selected_variables() else {
+            frame.render_widget(block, area);
+            return;
+        };
+
+        let rows: Vec<Row> = self
+            .variable_names()
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let bg = if self.focus == Focus::Variables && i == self.selected_var {
+                    theme.sidebar.selected_bg
+                } else {
+                    theme.general.content_bg
+                };
+                let (name, value) = if self.is_secret(&name) {
+                    (format!("{} 🔒", name), MASK.to_string())
+                } else {
+                    let value = variables.get(&name).cloned().unwrap_or_default();
+                    (name, value)
+                };
+                Row::new(vec![
+                    Cell::from(Span::styled(
+                        name,
+                        Style::default().fg(theme.general.title_focused),
+                    )),
+                    Cell::from(Span::styled(value, Style::default().fg(theme.general.text))),
+                ])
+                .style(Style::default().bg(bg))
+            })
+            .collect();
+
+        let table = Table::new(rows, [Constraint::Percentage(35), Constraint::Fill(1)])
+            .header(
+                Row::new(vec!["Name", "Value"]).style(
+                    Style::default()
+                        .fg(theme.general.text_unfocused)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            )
+            .block(block);
+        frame.render_widget(table, area);
+    }
+
+    fn render_input(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let Some((target, text)) = &self.input else {
+            return;
+        };
+        let prompt = match target {
+            InputTarget::NewEnvironment => "New environment name".to_string(),
+            InputTarget::RenameEnvironment => "Rename environment to".to_string(),
+            InputTarget::NewVariable => "New variable name".to_string(),
+            InputTarget::RenameVariable(name) => format!("Rename '{}' to", name),
+            InputTarget::EditValue(name) => format!("Value of '{}'", name),
+        };
+        let text = match target {
+            InputTarget::EditValue(name) if self.is_secret(name) => {
+                "•".repeat(text.chars().count())
+            }
+            _ => text.clone(),
+        };
+        let input = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("{}: ", prompt),
+                Style::default().fg(theme.general.title_focused),
+            ),
+            Span::styled(text, Style::default().fg(theme.general.text)),
+            Span::styled("█", Style::default().fg(theme.general.text_unfocused)),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.general.title_focused))
+                .style(Style::default().bg(theme.general.content_bg)),
+        );
+        frame.render_widget(input, area);
+    }
+}
+
+impl Component for EnvironmentPanel {
+    type Action = EnvironmentAction;
+
+    fn tick(&mut self, event: Option<&Event>, _tick_count: u32) -> Self::Action {
+        match event {
+            Some(Event::Key(key)) if self.input.is_some() => self.handle_input(key.code),
+            Some(Event::Key(key)) => match self.focus {
+                Focus::Environments => self.handle_environment_keys(key.code),
+                Focus::Variables => self.handle_variable_keys(key.code),
+            },
+            Some(Event::Mouse(mouse)) if self.input.is_none() => match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    self.handle_click(mouse.column, mouse.row)
+                }
+                _ => EnvironmentAction::Noop,
+            },
+            _ => EnvironmentAction::Noop,
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, rect: Rect, theme: &Theme) {
+        let input_height = if self.input.is_some() { 3 } else { 0 };
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(input_height)])
+            .split(rect);
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(30), Constraint::Min(0)])
+            .split(rows[0]);
+
+        self.list_rect = Some(columns[0]);
+        self.table_rect = Some(columns[1]);
+
+        self.render_environments(frame, columns[0], theme);
+        self.render_variables(frame, columns[1], theme);
+        self.render_input(frame, rows[1], theme);
+    }
+}
@@ -75,6 +75,54 @@ impl Footer {
             "NORMAL" => {
                 let mut spans =
                     self.render_mode_indicator("NORMAL", theme.footer.mode_normal, theme);
+                spans.extend(self.render_command("e", "edit", theme.http_methods.put, theme));
+                spans.extend(self.render_command(
+                    "n/N",
+                    "new request/folder",
+                    theme.footer.mode_create,
+                    theme,
+                ));
+                spans.extend(self.render_command("r", "rename", theme.http_methods.patch, theme));
+                spans.extend(self.render_command("c", "duplicate", theme.http_methods.head, theme));
+                spans.extend(self.render_command("m", "move", theme.http_methods.post, theme));
+                spans.extend(self.render_command("J/K", "reorder", theme.http_methods.post, theme));
+                spans.extend(self.render_command(
+                    "s",
+                    "send request",
+                    theme.http_methods.get,
+                    theme,
+                ));
+                spans.extend(self.render_command(
+                    "x",
+                    "cancel request",
+                    theme.http_methods.delete,
+                    theme,
+                ));
+                spans.extend(self.render_command("h", "history", theme.footer.mode_tab, theme));
+                spans.extend(self.render_command(
+                    "v",
+                    "raw/resolved",
+                    theme.http_methods.patch,
+                    theme,
+                ));
+                spans.extend(self.render_command(
+                    "i",
+                    "variables",
+                    theme.http_methods.patch,
+                    theme,
+                ));
+                spans.extend(self.render_command(
+                    "t",
+                    "body/certificates",
+                    theme.http_methods.patch,
+                    theme,
+                ));
+                spans.extend(self.render_command(
+                    "E",
+                    "next environment",
+                    theme.http_methods.post,
+                    theme,
+                ));
                 spans.extend(self.render_command(
                     "SPACE",
                     "command mode",
@@ -93,6 +141,18 @@ impl Footer {
                     theme.footer.mode_create,
                     theme,
                 ));
+                spans.extend(self.render_command(
+                    "e",
+                    "environments",
+                    theme.http_methods.post,
+                    theme,
+                ));
+                spans.extend(self.render_command(
+                    "p",
+                    "project settings",
+                    theme.http_methods.patch,
+                    theme,
+                ));
                 spans.extend(self.render_command("q", "quit", theme.http_methods.delete, theme));
                 spans.extend(self.render_command(
                     "n",
@@ -131,6 +191,62 @@ impl Footer {
                 spans.extend(self.render_command("ESC", "back", theme.http_methods.delete, theme));
                 Line::from(spans)
             }
+            "HISTORY" => {
+                let mut spans = self.render_mode_indicator("HISTORY", theme.footer.mode_tab, theme);
+                spans.extend(self.render_command(
+                    "j/k",
+                    "select entry",
+                    theme.http_methods.get,
+                    theme,
+                ));
+                spans.extend(self.render_command(
+                    "ENTER",
+                    "open response",
+                    theme.footer.mode_normal,
+                    theme,
+                ));
+                spans.extend(self.render_command("ESC", "back", theme.http_methods.delete, theme));
+                Line::from(spans)
+            }
+            "ENV" => {
+                let mut spans = self.render_mode_indicator("ENV", theme.http_methods.post, theme);
+                spans.extend(self.render_command("j/k", "select", theme.http_methods.get, theme));
+                spans.extend(self.render_command(
+                    "TAB",
+                    "environments/variables",
+                    theme.footer.mode_tab,
+                    theme,
+                ));
+                spans.extend(self.render_command("n", "new", theme.footer.mode_create, theme));
+                spans.extend(self.render_command("r", "rename", theme.http_methods.patch, theme));
+                spans.extend(self.render_command("c", "clone", theme.http_methods.head, theme));
+                spans.extend(self.render_command(
+                    "ENTER",
+                    "edit value",
+                    theme.http_methods.put,
+                    theme,
+                ));
+                spans.extend(self.render_command(
+                    "a",
+                    "set active",
+                    theme.footer.mode_normal,
+                    theme,
+                ));
+                spans.extend(self.render_command("s", "secret", theme.http_methods.patch, theme));
+                spans.extend(self.render_command("d", "delete", theme.http_methods.delete, theme));
+                spans.extend(self.render_command("ESC", "back", theme.http_methods.delete, theme));
+                Line::from(spans)
+            }
+            "SETTINGS" => {
+                let mut spans =
+                    self.render_mode_indicator("SETTINGS", theme.http_methods.patch, theme);
+                spans.extend(self.render_command("j/k", "select", theme.http_methods.get, theme));
+                spans.extend(self.render_command("ENTER", "edit", theme.http_methods.put, theme));
+                spans.extend(self.render_command("h/l", "change", theme.footer.mode_tab, theme));
+                spans.extend(self.render_command("d", "remove", theme.http_methods.delete, theme));
+                spans.extend(self.render_command("ESC", "back", theme.http_methods.delete, theme));
+                Line::from(spans)
+            }
             "CREATE" => {
                 let mut spans =
                     self.render_mode_indicator("CREATE", theme.footer.mode_create, theme);
@@ -148,6 +264,26 @@ impl Footer {
                 ));
                 Line::from(spans)
             }
+            "NAME" => {
+                let mut spans = self.render_mode_indicator("NAME", theme.footer.mode_create, theme);
+                spans.push(Span::styled(
+                    format!(" {} ", self.status),
+                    Style::default().fg(theme.footer.description),
+                ));
+                spans.extend(self.render_command(
+                    "ENTER",
+                    "confirm",
+                    theme.footer.mode_normal,
+                    theme,
+                ));
+                spans.extend(self.render_command(
+                    "ESC",
+                    "cancel",
+                    theme.http_methods.delete,
+                    theme,
+                ));
+                Line::from(spans)
+            }
             _ => Line::from(vec![Span::raw(self.status.clone())]),
         }
     }
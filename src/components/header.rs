@@ -3,7 +3,7 @@ use crate::theme::Theme;
 
 use crossterm::event::{Event, KeyCode, MouseEvent, MouseEventKind};
 use ratatui::{
-    layout::{Alignment, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Paragraph, Tabs},
@@ -13,6 +13,8 @@ pub enum HeaderAction {
     Noop,
     TabChanged(usize),
     DeleteProject(usize),
+    /// The active environment label was clicked.
+    OpenEnvironments,
 }
 
 pub struct ProjectTab {
@@ -23,6 +25,9 @@ pub struct Header {
     rect: Option<Rect>,
     projects: Vec<ProjectTab>,
     selected_index: usize,
+    /// Name of the current project's active environment.
+    environment: Option<String>,
+    environment_rect: Option<Rect>,
 }
 
 impl Header {
@@ -31,6 +36,8 @@ impl Header {
             rect: None,
             projects,
             selected_index: 0,
+            environment: None,
+            environment_rect: None,
         }
     }
 
@@ -38,6 +45,10 @@ impl Header {
         self.projects.push(project);
     }
 
+    pub fn set_environment(&mut self, environment: Option<String>) {
+        self.environment = environment;
+    }
+
     pub fn handle_key_event(&mut self, key: KeyCode) -> HeaderAction {
         match key {
             KeyCode::Char('d') => {
@@ -116,6 +127,11 @@ impl Component for Header {
                 Event::Mouse(mouse_event) => match mouse_event.kind {
                     MouseEventKind::Down(crossterm::event::MouseButton::Left) => {
                         if let Some(rect) = self.rect {
+                            if let Some(env_rect) = self.environment_rect {
+                                if self.is_mouse_over(mouse_event, &env_rect) {
+                                    return HeaderAction::OpenEnvironments;
+                                }
+                            }
                             if self.is_mouse_over(mouse_event, &rect) {
                                 return self.handle_mouse_event(mouse_event, &rect);
                             }
@@ -138,6 +154,36 @@ impl Component for Header {
         frame.render_widget(block.clone(), rect);
         let inner_rect = block.inner(rect);
 
+        self.environment_rect = None;
+        let inner_rect = if self.projects.is_empty() {
+            inner_rect
+        } else {
+            let (name, name_style) = match &self.environment {
+                Some(name) => (name.as_str(), Style::default().fg(theme.general.text)),
+                None => (
+                    "no environment",
+                    Style::default().fg(theme.general.text_unfocused),
+                ),
+            };
+            let label = Line::from(vec![
+                Span::styled(
+                    " ENV ",
+                    Style::default()
+                        .fg(Color::Black)
+                        .bg(theme.http_methods.post)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!(" {} ", name), name_style),
+            ]);
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(label.width() as u16)])
+                .split(inner_rect);
+            frame.render_widget(Paragraph::new(label), chunks[1]);
+            self.environment_rect = Some(chunks[1]);
+            chunks[0]
+        };
+
         if self.projects.is_empty() {
             let help_text = "Press SPACE + t to create a new project";
             let paragraph = Paragraph::new(Line::from(vec![
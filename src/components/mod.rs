@@ -4,15 +4,19 @@ use ratatui::{layout::Rect, Frame};
 use crate::theme::Theme;
 
 mod content;
+mod environments;
 mod footer;
 mod header;
 mod layout;
+mod settings;
 mod sidebar;
 
 pub use content::{Content, ContentAction};
+pub use environments::{EnvironmentAction, EnvironmentPanel};
 pub use footer::Footer;
 pub use header::{Header, HeaderAction, ProjectTab};
 pub use layout::AppLayout;
+pub use settings::{SettingsAction, SettingsPanel};
 pub use sidebar::{Sidebar, SidebarAction};
 
 pub trait Component {
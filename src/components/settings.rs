@@ -0,0 +1,666 @@
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEventKind};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::auth::{self, Input};
+use crate::components::Component;
+use crate::persistence::{
+    ClientCertificate, Environment, ProjectSettings, ProjectUpdate, TlsSettings, TlsVersion,
+};
+use crate::theme::Theme;
+use crate::variables::MASK;
+
+#[allow(clippy::large_enum_variant)]
+pub enum SettingsAction {
+    Noop,
+    Updated,
+    ProjectUpdate(ProjectUpdate),
+    Close,
+}
+
+/// Client certificate formats, as offered in the editor.
+const CERTIFICATE_FORMATS: &[&str] = &["none", "PEM", "PKCS#12"];
+
+/// Minimum TLS versions, as offered in the editor.
+const TLS_VERSIONS: &[&str] = &["any", "1.2", "1.3"];
+
+/// A line of the settings table.
+#[derive(PartialEq, Clone, Copy)]
+enum SettingRow {
+    BaseUrl,
+    Header(usize),
+    NewHeader,
+    AuthKind,
+    AuthField(usize),
+    SecretAuth,
+    ConnectTimeout,
+    Timeout,
+    /// Whether the TLS rows below are the project's or an environment's.
+    TlsScope,
+    CaFile(usize),
+    NewCaFile,
+    ClientCertificate,
+    CertificateField(usize),
+    MinVersion,
+    InsecureHost(usize),
+    NewInsecureHost,
+}
+
+/// Shows the current project's defaults as one table. Like the environment
+/// panel, every change is handed back as an action.
+pub struct SettingsPanel {
+    settings: ProjectSettings,
+    environments: Vec<Environment>,
+    /// 0 for the project's TLS settings, otherwise one past the index of the
+    /// environment whose settings are shown.
+    tls_scope: usize,
+    selected: usize,
+    /// First row shown, as scrolled to by the last render.
+    offset: usize,
+    /// Text being typed for the selected row.
+    input: Option<String>,
+    table_rect: Option<Rect>,
+}
+
+impl SettingsPanel {
+    pub fn new() -> Self {
+        SettingsPanel {
+            settings: ProjectSettings::default(),
+            environments: Vec::new(),
+            tls_scope: 0,
+            selected: 0,
+            offset: 0,
+            input: None,
+            table_rect: None,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: ProjectSettings, environments: Vec<Environment>) {
+        self.settings = settings;
+        self.environments = environments;
+        self.tls_scope = self.tls_scope.min(self.environments.len());
+        self.selected = self.selected.min(self.rows().len() - 1);
+    }
+
+    pub fn open(&mut self) {
+        self.selected = 0;
+        self.input = None;
+    }
+
+    fn rows(&self) -> Vec<SettingRow> {
+        let mut rows = vec![SettingRow::BaseUrl];
+        rows.extend((0..self.settings.headers.len()).map(SettingRow::Header));
+        rows.extend([SettingRow::NewHeader, SettingRow::AuthKind]);
+        if let Some(auth) = &self.settings.auth {
+            rows.extend((0..auth::fields(auth).len()).map(SettingRow::AuthField));
+            rows.push(SettingRow::SecretAuth);
+        }
+        rows.extend([SettingRow::ConnectTimeout, SettingRow::Timeout]);
+
+        let tls = self.tls();
+        rows.push(SettingRow::TlsScope);
+        rows.extend((0..tls.ca_files.len()).map(SettingRow::CaFile));
+        rows.extend([SettingRow::NewCaFile, SettingRow::ClientCertificate]);
+        if let Some(certificate) = &tls.client_certificate {
+            rows.extend(
+                (0..certificate_fields(certificate).len()).map(SettingRow::CertificateField),
+            );
+        }
+        rows.push(SettingRow::MinVersion);
+        rows.extend((0..tls.insecure_hosts.len()).map(SettingRow::InsecureHost));
+        rows.push(SettingRow::NewInsecureHost);
+        rows
+    }
+
+    /// The TLS settings of the project or environment being edited.
+    fn tls(&self) -> &TlsSettings {
+        match self.tls_scope {
+            0 => &self.settings.tls,
+            scope => &self.environments[scope - 1].tls,
+        }
+    }
+
+    /// The TLS settings being edited with `change` applied, as an update of
+    /// the project or environment they belong to.
+    fn update_tls(&self, change: impl FnOnce(&mut TlsSettings)) -> SettingsAction {
+        match self.tls_scope {
+            0 => self.update(|s| change(&mut s.tls)),
+            scope => {
+                let mut env = self.environments[scope - 1].clone();
+                change(&mut env.tls);
+                SettingsAction::ProjectUpdate(ProjectUpdate::UpdateEnvironment(scope - 1, env))
+            }
+        }
+    }
+
+    fn selected_row(&self) -> SettingRow {
+        let rows = self.rows();
+        rows[self.selected.min(rows.len() - 1)]
+    }
+
+    fn field(&self, index: usize) -> Option<auth::Field> {
+        self.settings
+            .auth
+            .as_ref()
+            .and_then(|auth| auth::fields(auth).into_iter().nth(index))
+    }
+
+    fn is_timeout(row: SettingRow) -> bool {
+        matches!(row, SettingRow::ConnectTimeout | SettingRow::Timeout)
+    }
+
+    /// The settings with `change` applied, as an update.
+    fn update(&self, change: impl FnOnce(&mut ProjectSettings)) -> SettingsAction {
+        let mut settings = self.settings.clone();
+        change(&mut settings);
+        SettingsAction::ProjectUpdate(ProjectUpdate::UpdateSettings(settings))
+    }
+
+    /// Starts typing into the selected row, or cycles it if it is a choice.
+    fn edit(&mut self) -> SettingsAction {
+        let seconds = |timeout: Option<u64>| timeout.map(|s| s.to_string()).unwrap_or_default();
+        let text = match self.selected_row() {
+            SettingRow::BaseUrl => self.settings.base_url.clone(),
+            SettingRow::Header(index) => {
+                let (name, value) = &self.settings.headers[index];
+                format!("{}: {}", name, value)
+            }
+            SettingRow::NewHeader => String::new(),
+            SettingRow::AuthField(index) => match self.field(index) {
+                Some(field) if !matches!(field.input, Input::Choice(_)) => field.value,
+                _ => return self.cycle(true),
+            },
+            SettingRow::AuthKind | SettingRow::SecretAuth => return self.cycle(true),
+            SettingRow::ConnectTimeout => seconds(self.settings.connect_timeout),
+            SettingRow::Timeout => seconds(self.settings.timeout),
+            SettingRow::CaFile(index) => self.tls().ca_files[index].clone(),
+            SettingRow::CertificateField(index) => match &self.tls().client_certificate {
+                Some(certificate) => certificate_fields(certificate)[index].1.clone(),
+                None => String::new(),
+            },
+            SettingRow::InsecureHost(index) => self.tls().insecure_hosts[index].clone(),
+            SettingRow::NewCaFile | SettingRow::NewInsecureHost => String::new(),
+            SettingRow::TlsScope | SettingRow::ClientCertificate | SettingRow::MinVersion => {
+                return self.cycle(true)
+            }
+        };
+        self.input = Some(text);
+        SettingsAction::Updated
+    }
+
+    fn submit(&mut self, text: String) -> SettingsAction {
+        let seconds = text.trim().parse().ok();
+        match self.selected_row() {
+            SettingRow::BaseUrl => self.update(|s| s.base_url = text.trim().to_string()),
+            SettingRow::Header(index) => match parse_header(&text) {
+                Some(header) => self.update(|s| s.headers[index] = header),
+                None => self.update(|s| {
+                    s.headers.remove(index);
+                }),
+            },
+            SettingRow::NewHeader => match parse_header(&text) {
+                Some(header) => self.update(|s| s.headers.push(header)),
+                None => SettingsAction::Updated,
+            },
+            SettingRow::AuthField(index) => self.update(|s| {
+                if let Some(auth) = &mut s.auth {
+                    auth::set_field(auth, index, &text);
+                }
+            }),
+            SettingRow::ConnectTimeout => self.update(|s| s.connect_timeout = seconds),
+            SettingRow::Timeout => self.update(|s| s.timeout = seconds),
+            // Emptying a file or host removes it.
+            SettingRow::CaFile(index) => self.update_tls(|tls| match text.trim() {
+                "" => {
+                    tls.ca_files.remove(index);
+                }
+                file => tls.ca_files[index] = file.to_string(),
+            }),
+            SettingRow::NewCaFile if !text.trim().is_empty() => {
+                self.update_tls(|tls| tls.ca_files.push(text.trim().to_string()))
+            }
+            SettingRow::CertificateField(index) => self.update_tls(|tls| {
+                if let Some(certificate) = &mut tls.client_certificate {
+                    set_certificate_field(certificate, index, &text);
+                }
+            }),
+            SettingRow::InsecureHost(index) => self.update_tls(|tls| match text.trim() {
+                "" => {
+                    tls.insecure_hosts.remove(index);
+                }
+                host => tls.insecure_hosts[index] = host.to_string(),
+            }),
+            SettingRow::NewInsecureHost if !text.trim().is_empty() => {
+                self.update_tls(|tls| tls.insecure_hosts.push(text.trim().to_string()))
+            }
+            _ => SettingsAction::Updated,
+        }
+    }
+
+    /// Switches the scheme, a choice field, the secret flag or one of the TLS
+    /// choices to its next or previous value.
+    fn cycle(&mut self, forward: bool) -> SettingsAction {
+        let step = |index: usize, len: usize| {
+            if forward {
+                (index + 1) % len
+            } else {
+                (index + len - 1) % len
+            }
+        };
+        match self.selected_row() {
+            SettingRow::AuthKind => {
+                let kind = self.settings.auth.as_ref().map(auth::kind).unwrap_or(0);
+                let kind = step(kind, auth::INHERIT);
+                self.update(|s| s.auth = (kind > 0).then(|| auth::with_kind(kind)))
+            }
+            SettingRow::AuthField(index) => match self.field(index) {
+                Some(auth::Field {
+                    input: Input::Choice(options),
+                    value,
+                    ..
+                }) => {
+                    let current = options.iter().position(|o| *o == value).unwrap_or(0);
+                    let next = options[step(current, options.len())];
+                    self.update(|s| {
+                        if let Some(auth) = &mut s.auth {
+                            auth::set_field(auth, index, next);
+                        }
+                    })
+                }
+                _ => SettingsAction::Noop,
+            },
+            SettingRow::SecretAuth => self.update(|s| s.secret_auth = !s.secret_auth),
+            SettingRow::TlsScope => {
+                self.tls_scope = step(self.tls_scope, self.environments.len() + 1);
+                SettingsAction::Updated
+            }
+            SettingRow::ClientCertificate => {
+                let format = match self.tls().client_certificate {
+                    None => 0,
+                    Some(ClientCertificate::Pem { .. }) => 1,
+                    Some(ClientCertificate::Pkcs12 { .. }) => 2,
+                };
+                self.update_tls(|tls| {
+                    tls.client_certificate = match step(format, CERTIFICATE_FORMATS.len()) {
+                        1 => Some(ClientCertificate::Pem {
+                            cert_file: String::new(),
+                            key_file: String::new(),
+                        }),
+                        2 => Some(ClientCertificate::Pkcs12 {
+                            file: String::new(),
+                            password: String::new(),
+                        }),
+                        _ => None,
+                    }
+                })
+            }
+            SettingRow::MinVersion => {
+                let version = match self.tls().min_version {
+                    None => 0,
+                    Some(TlsVersion::Tls12) => 1,
+                    Some(TlsVersion::Tls13) => 2,
+                };
+                self.update_tls(|tls| {
+                    tls.min_version = match step(version, TLS_VERSIONS.len()) {
+                        1 => Some(TlsVersion::Tls12),
+                        2 => Some(TlsVersion::Tls13),
+                        _ => None,
+                    }
+                })
+            }
+            _ => SettingsAction::Noop,
+        }
+    }
+
+    fn handle_input(&mut self, key: KeyCode) -> SettingsAction {
+        let is_timeout = Self::is_timeout(self.selected_row());
+        let Some(text) = self.input.as_mut() else {
+            return SettingsAction::Noop;
+        };
+        match key {
+            KeyCode::Char(c) if is_timeout && !c.is_ascii_digit() => SettingsAction::Noop,
+            KeyCode::Char(c) => {
+                text.push(c);
+                SettingsAction::Updated
+            }
+            KeyCode::Backspace => {
+                text.pop();
+                SettingsAction::Updated
+            }
+            KeyCode::Esc => {
+                self.input = None;
+                SettingsAction::Updated
+            }
+            KeyCode::Enter => {
+                let text = self.input.take().unwrap();
+                self.submit(text)
+            }
+            _ => SettingsAction::Noop,
+        }
+    }
+
+    fn handle_keys(&mut self, key: KeyCode) -> SettingsAction {
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.rows().len() - 1);
+                SettingsAction::Updated
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                SettingsAction::Updated
+            }
+            KeyCode::Enter | KeyCode::Char('e') => self.edit(),
+            KeyCode::Char('l') | KeyCode::Right => self.cycle(true),
+            KeyCode::Char('h') | KeyCode::Left => self.cycle(false),
+            // Removes a header, CA file or host, or goes back to the default.
+            KeyCode::Char('d') => match self.selected_row() {
+                SettingRow::Header(index) => self.update(|s| {
+                    s.headers.remove(index);
+                }),
+                SettingRow::ConnectTimeout => self.update(|s| s.connect_timeout = None),
+                SettingRow::Timeout => self.update(|s| s.timeout = None),
+                SettingRow::CaFile(index) => self.update_tls(|tls| {
+                    tls.ca_files.remove(index);
+                }),
+                SettingRow::ClientCertificate | SettingRow::CertificateField(_) => {
+                    self.update_tls(|tls| tls.client_certificate = None)
+                }
+                SettingRow::MinVersion => self.update_tls(|tls| tls.min_version = None),
+                SettingRow::InsecureHost(index) => self.update_tls(|tls| {
+                    tls.insecure_hosts.remove(index);
+                }),
+                _ => SettingsAction::Noop,
+            },
+            KeyCode::Esc | KeyCode::Char('q') => SettingsAction::Close,
+            _ => SettingsAction::Noop,
+        }
+    }
+
+    fn handle_click(&mut self, column: u16, row: u16) -> SettingsAction {
+        let Some(rect) = self.table_rect else {
+            return SettingsAction::Noop;
+        };
+        let inside = column >= rect.x
+            && column < rect.x + rect.width
+            && row >= rect.y
+            && row < rect.y + rect.height;
+        // The table has a border and a header row.
+        match row
+            .checked_sub(rect.y + 2)
+            .map(|i| i as usize + self.offset)
+        {
+            Some(index) if inside && index < self.rows().len() => {
+                self.selected = index;
+                SettingsAction::Updated
+            }
+            _ => SettingsAction::Noop,
+        }
+    }
+
+    fn label_and_value(&self, row: SettingRow) -> (String, String) {
+        let seconds = |timeout: Option<u64>| match timeout {
+            Some(seconds) => format!("{}s", seconds),
+            None => "default".to_string(),
+        };
+        match row {
+            SettingRow::BaseUrl => ("Base URL".to_string(), self.settings.base_url.clone()),
+            SettingRow::Header(index) => {
+                let (name, value) = &self.settings.headers[index];
+                ("Header".to_string(), format!("{}: {}", name, value))
+            }
+            SettingRow::NewHeader => ("+ Header".to_string(), "Enter to add".to_string()),
+            SettingRow::AuthKind => {
+                let kind = self.settings.auth.as_ref().map(auth::kind).unwrap_or(0);
+                ("Auth".to_string(), format!("◀ {} ▶", auth::KINDS[kind]))
+            }
+            SettingRow::AuthField(index) => match self.field(index) {
+                Some(field) => {
+                    let value = match field.input {
+                        Input::Secret if self.settings.secret_auth && !field.value.is_empty() => {
+                            MASK.to_string()
+                        }
+                        Input::Choice(_) => format!("◀ {} ▶", field.value),
+                        _ => field.value,
+                    };
+                    (format!("  {}", field.label), value)
+                }
+                None => (String::new(), String::new()),
+            },
+            SettingRow::SecretAuth => (
+                "  Secret".to_string(),
+                if self.settings.secret_auth {
+                    "yes"
+                } else {
+                    "no"
+                }
+                .to_string(),
+            ),
+            SettingRow::ConnectTimeout => (
+                "Connect timeout".to_string(),
+                seconds(self.settings.connect_timeout),
+            ),
+            SettingRow::Timeout => ("Timeout".to_string(), seconds(self.settings.timeout)),
+            SettingRow::TlsScope => {
+                let scope = match self.tls_scope {
+                    0 => "Project",
+                    scope => &self.environments[scope - 1].name,
+                };
+                ("TLS for".to_string(), format!("◀ {} ▶", scope))
+            }
+            SettingRow::CaFile(index) => {
+                ("  CA file".to_string(), self.tls().ca_files[index].clone())
+            }
+            SettingRow::NewCaFile => ("  + CA file".to_string(), "Enter to add".to_string()),
+            SettingRow::ClientCertificate => {
+                let format = match self.tls().client_certificate {
+                    None => CERTIFICATE_FORMATS[0],
+                    Some(ClientCertificate::Pem { .. }) => CERTIFICATE_FORMATS[1],
+                    Some(ClientCertificate::Pkcs12 { .. }) => CERTIFICATE_FORMATS[2],
+                };
+                ("  Client cert".to_string(), format!("◀ {} ▶", format))
+            }
+            SettingRow::CertificateField(index) => match &self.tls().client_certificate {
+                Some(certificate) => {
+                    let (label, value, secret) = certificate_fields(certificate)[index].clone();
+                    let value = if secret && !value.is_empty() {
+                        MASK.to_string()
+                    } else {
+                        value
+                    };
+                    (format!("    {}", label), value)
+                }
+                None => (String::new(), String::new()),
+            },
+            SettingRow::MinVersion => {
+                let version = match self.tls().min_version {
+                    None => TLS_VERSIONS[0],
+                    Some(TlsVersion::Tls12) => TLS_VERSIONS[1],
+                    Some(TlsVersion::Tls13) => TLS_VERSIONS[2],
+                };
+                ("  Minimum version".to_string(), format!("◀ {} ▶", version))
+            }
+            SettingRow::InsecureHost(index) => (
+                "  Unverified host".to_string(),
+                self.tls().insecure_hosts[index].clone(),
+            ),
+            SettingRow::NewInsecureHost => (
+                "  + Unverified host".to_string(),
+                "Enter to add".to_string(),
+            ),
+        }
+    }
+
+    /// Renders the rows scrolled to keep the selected one in view, and
+    /// returns the first row shown.
+    fn render_table(&self, frame: &mut Frame, area: Rect, theme: &Theme) -> usize {
+        let rows: Vec<Row> = self
+            .rows()
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let bg = if i == self.selected {
+                    theme.sidebar.selected_bg
+                } else {
+                    theme.general.content_bg
+                };
+                let (label, value) = self.label_and_value(row);
+                Row::new(vec![
+                    Cell::from(Span::styled(
+                        label,
+                        Style::default().fg(theme.general.title_focused),
+                    )),
+                    Cell::from(Span::styled(value, Style::default().fg(theme.general.text))),
+                ])
+                .style(Style::default().bg(bg))
+            })
+            .collect();
+
+        let title = " Project settings · ↑/↓ select  Enter edit  ←/→ change  d remove ";
+        let table = Table::new(rows, [Constraint::Length(20), Constraint::Fill(1)])
+            .header(
+                Row::new(vec!["Setting", "Value"]).style(
+                    Style::default()
+                        .fg(theme.general.text_unfocused)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.general.title_focused))
+                    .style(Style::default().bg(theme.general.content_bg))
+                    .title(Span::styled(
+                        title,
+                        Style::default()
+                            .fg(theme.general.title_focused)
+                            .add_modifier(Modifier::BOLD),
+                    )),
+            );
+        let mut state = TableState::default()
+            .with_offset(self.offset)
+            .with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, area, &mut state);
+        state.offset()
+    }
+
+    fn render_input(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let Some(text) = &self.input else {
+            return;
+        };
+        let row = self.selected_row();
+        let prompt = match row {
+            SettingRow::Header(_) | SettingRow::NewHeader => "Header (Name: value)".to_string(),
+            SettingRow::ConnectTimeout | SettingRow::Timeout => {
+                format!(
+                    "{} in seconds, empty for the default",
+                    self.label_and_value(row).0
+                )
+            }
+            SettingRow::NewCaFile => "PEM file of a CA to trust".to_string(),
+            SettingRow::NewInsecureHost => "Host to accept any certificate from".to_string(),
+            _ => self.label_and_value(row).0.trim().to_string(),
+        };
+        let is_secret = match row {
+            SettingRow::AuthField(index) => {
+                self.settings.secret_auth
+                    && self.field(index).is_some_and(|f| f.input == Input::Secret)
+            }
+            SettingRow::CertificateField(index) => self
+                .tls()
+                .client_certificate
+                .as_ref()
+                .is_some_and(|certificate| certificate_fields(certificate)[index].2),
+            _ => false,
+        };
+        let text = if is_secret {
+            "•".repeat(text.chars().count())
+        } else {
+            text.clone()
+        };
+        let input = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("{}: ", prompt),
+                Style::default().fg(theme.general.title_focused),
+            ),
+            Span::styled(text, Style::default().fg(theme.general.text)),
+            Span::styled("█", Style::default().fg(theme.general.text_unfocused)),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.general.title_focused))
+                .style(Style::default().bg(theme.general.content_bg)),
+        );
+        frame.render_widget(input, area);
+    }
+}
+
+/// The labels and values of a client certificate's inputs, and whether each
+/// is secret.
+fn certificate_fields(certificate: &ClientCertificate) -> Vec<(&'static str, String, bool)> {
+    match certificate {
+        ClientCertificate::Pem {
+            cert_file,
+            key_file,
+        } => vec![
+            ("Certificate file", cert_file.clone(), false),
+            ("Key file", key_file.clone(), false),
+        ],
+        ClientCertificate::Pkcs12 { file, password } => vec![
+            ("PKCS#12 file", file.clone(), false),
+            ("Password", password.clone(), true),
+        ],
+    }
+}
+
+fn set_certificate_field(certificate: &mut ClientCertificate, index: usize, text: &str) {
+    match (certificate, index) {
+        (ClientCertificate::Pem { cert_file, .. }, 0) => *cert_file = text.trim().to_string(),
+        (ClientCertificate::Pem { key_file, .. }, _) => *key_file = text.trim().to_string(),
+        (ClientCertificate::Pkcs12 { file, .. }, 0) => *file = text.trim().to_string(),
+        // Passwords are taken as typed.
+        (ClientCertificate::Pkcs12 { password, .. }, _) => *password = text.to_string(),
+    }
+}
+
+/// Splits `Name: value`. A header needs a name.
+fn parse_header(text: &str) -> Option<(String, String)> {
+    let (name, value) = text.split_once(':').unwrap_or((text, ""));
+    let name = name.trim();
+    (!name.is_empty()).then(|| (name.to_string(), value.trim().to_string()))
+}
+
+impl Component for SettingsPanel {
+    type Action = SettingsAction;
+
+    fn tick(&mut self, event: Option<&Event>, _tick_count: u32) -> Self::Action {
+        match event {
+            Some(Event::Key(key)) if self.input.is_some() => self.handle_input(key.code),
+            Some(Event::Key(key)) => self.handle_keys(key.code),
+            Some(Event::Mouse(mouse)) if self.input.is_none() => match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    self.handle_click(mouse.column, mouse.row)
+                }
+                _ => SettingsAction::Noop,
+            },
+            _ => SettingsAction::Noop,
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, rect: Rect, theme: &Theme) {
+        let input_height = if self.input.is_some() { 3 } else { 0 };
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(input_height)])
+            .split(rect);
+
+        self.table_rect = Some(rows[0]);
+        self.offset = self.render_table(frame, rows[0], theme);
+        self.render_input(frame, rows[1], theme);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::components::Component;
-use crate::persistence::{ProjectUpdate, RequestData};
+use crate::persistence::{Folder, ProjectUpdate, RequestData};
 use crate::theme::*;
-use crossterm::event::{Event, KeyCode, MouseEventKind};
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     layout::Rect,
     style::{Modifier, Style},
@@ -9,18 +9,42 @@ use ratatui::{
     widgets::{Block, List, ListItem},
     Frame,
 };
+use std::collections::HashSet;
 
 pub enum SidebarAction {
     Noop,
+    /// The selection or the tree changed without picking a request.
+    Updated,
     Selected(RequestData),
     ProjectUpdate(ProjectUpdate),
     EditRequest,
+    /// Asks for a name for a new request in the given folder.
+    NewRequest(Option<String>),
+    RenameRequest(RequestData),
+    /// Asks for a name for a new folder under the given parent.
+    NewFolder(Option<String>),
+    RenameFolder(Folder),
+}
+
+/// One visible line of the tree, pointing into `folders` or `requests`.
+#[derive(Clone, Copy)]
+enum Row {
+    Folder { index: usize, depth: usize },
+    Request { index: usize, depth: usize },
 }
 
 pub struct Sidebar {
     rect: Option<Rect>,
     requests: Vec<RequestData>,
+    folders: Vec<Folder>,
+    collapsed: HashSet<String>,
+    rows: Vec<Row>,
     selected_index: Option<usize>,
+    /// A request picked up with `m`, waiting to be dropped into a folder.
+    moving: Option<String>,
+    /// The request being dragged with the mouse and the row it is over.
+    dragging: Option<String>,
+    drop_target: Option<usize>,
 }
 
 impl Sidebar {
@@ -28,54 +52,341 @@ impl Sidebar {
         Sidebar {
             rect: None,
             requests: vec![],
+            folders: vec![],
+            collapsed: HashSet::new(),
+            rows: vec![],
             selected_index: None,
+            moving: None,
+            dragging: None,
+            drop_target: None,
         }
     }
 
-    pub fn set_requests(&mut self, requests: Vec<RequestData>) {
+    /// Replaces the listed folders and requests, keeping the selection on the
+    /// same item when it is still visible.
+    pub fn set_items(&mut self, folders: Vec<Folder>, requests: Vec<RequestData>) {
+        let selected_id = self.selected_row().map(|row| self.row_id(row).to_string());
+        self.folders = folders;
         self.requests = requests;
-        if self.requests.is_empty() {
-            self.selected_index = None;
+        if let Some(id) = &self.moving {
+            if !self.requests.iter().any(|r| &r.id == id) {
+                self.moving = None;
+            }
+        }
+        self.rebuild_rows();
+
+        self.selected_index = if self.rows.is_empty() {
+            None
+        } else {
+            selected_id
+                .and_then(|id| self.position(&id))
+                .or_else(|| Some(self.selected_index.unwrap_or(0).min(self.rows.len() - 1)))
+        };
+    }
+
+    /// Moves the selection to the request or folder with `id`, expanding the
+    /// folders above it.
+    pub fn select(&mut self, id: &str) {
+        let mut parent = self
+            .requests
+            .iter()
+            .find(|r| r.id == id)
+            .and_then(|r| r.folder_id.clone())
+            .or_else(|| {
+                self.folders
+                    .iter()
+                    .find(|f| f.id == id)
+                    .and_then(|f| f.parent_id.clone())
+            });
+        while let Some(folder_id) = parent {
+            self.collapsed.remove(&folder_id);
+            parent = self
+                .folders
+                .iter()
+                .find(|f| f.id == folder_id)
+                .and_then(|f| f.parent_id.clone());
+        }
+        self.rebuild_rows();
+
+        if let Some(index) = self.position(id) {
+            self.selected_index = Some(index);
+        }
+    }
+
+    fn rebuild_rows(&mut self) {
+        let mut rows = Vec::new();
+        self.collect_rows(None, 0, &mut rows);
+        self.rows = rows;
+    }
+
+    /// Folders first, then requests, each in project order. Items whose
+    /// folder no longer exists are shown at the top level.
+    fn collect_rows(&self, parent: Option<&str>, depth: usize, rows: &mut Vec<Row>) {
+        for (index, folder) in self.folders.iter().enumerate() {
+            if self.existing_folder(folder.parent_id.as_deref()) == parent {
+                rows.push(Row::Folder { index, depth });
+                if !self.collapsed.contains(&folder.id) {
+                    self.collect_rows(Some(&folder.id), depth + 1, rows);
+                }
+            }
+        }
+        for (index, request) in self.requests.iter().enumerate() {
+            if self.existing_folder(request.folder_id.as_deref()) == parent {
+                rows.push(Row::Request { index, depth });
+            }
+        }
+    }
+
+    fn existing_folder<'a>(&self, id: Option<&'a str>) -> Option<&'a str> {
+        id.filter(|id| self.folders.iter().any(|f| f.id == *id))
+    }
+
+    fn row_id(&self, row: Row) -> &str {
+        match row {
+            Row::Folder { index, .. } => &self.folders[index].id,
+            Row::Request { index, .. } => &self.requests[index].id,
+        }
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.rows.iter().position(|row| self.row_id(*row) == id)
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.selected_index.and_then(|i| self.rows.get(i).copied())
+    }
+
+    fn selected_request(&self) -> Option<&RequestData> {
+        match self.selected_row() {
+            Some(Row::Request { index, .. }) => Some(&self.requests[index]),
+            _ => None,
+        }
+    }
+
+    /// The folder new items and moved requests go into: the selected folder
+    /// itself, or the folder holding the selected request.
+    fn target_folder(&self) -> Option<String> {
+        match self.selected_row() {
+            Some(Row::Folder { index, .. }) => Some(self.folders[index].id.clone()),
+            Some(Row::Request { index, .. }) => self
+                .existing_folder(self.requests[index].folder_id.as_deref())
+                .map(str::to_string),
+            None => None,
+        }
+    }
+
+    fn select_index(&mut self, index: usize) -> SidebarAction {
+        self.selected_index = Some(index);
+        match self.rows[index] {
+            Row::Request { index, .. } => SidebarAction::Selected(self.requests[index].clone()),
+            Row::Folder { .. } => SidebarAction::Updated,
+        }
+    }
+
+    fn set_collapsed(&mut self, folder_id: String, collapsed: bool) -> SidebarAction {
+        if collapsed {
+            self.collapsed.insert(folder_id.clone());
+        } else {
+            self.collapsed.remove(&folder_id);
+        }
+        self.rebuild_rows();
+        self.selected_index = self.position(&folder_id);
+        SidebarAction::Updated
+    }
+
+    fn toggle_folder(&mut self, index: usize) -> SidebarAction {
+        let id = self.folders[index].id.clone();
+        let collapsed = !self.collapsed.contains(&id);
+        self.set_collapsed(id, collapsed)
+    }
+
+    /// Moves request `id` onto `target`: next to a request, taking its place
+    /// in that request's folder, or to the end of a folder.
+    fn move_request(&self, id: &str, target: Row) -> Option<SidebarAction> {
+        let from = self.requests.iter().position(|r| r.id == id)?;
+        let (folder_id, index) = match target {
+            Row::Request { index, .. } if index == from => return None,
+            Row::Request { index, .. } => (
+                self.existing_folder(self.requests[index].folder_id.as_deref())
+                    .map(str::to_string),
+                index,
+            ),
+            Row::Folder { index, .. } => (
+                Some(self.folders[index].id.clone()),
+                self.requests.len() - 1,
+            ),
+        };
+
+        Some(SidebarAction::ProjectUpdate(ProjectUpdate::MoveRequest {
+            id: id.to_string(),
+            folder_id,
+            index,
+        }))
+    }
+
+    /// Swaps the selected request with its neighbour in the same folder.
+    fn shift_selected(&self, down: bool) -> Option<SidebarAction> {
+        let from = match self.selected_row()? {
+            Row::Request { index, .. } => index,
+            Row::Folder { .. } => return None,
+        };
+        let request = &self.requests[from];
+        let folder = self.existing_folder(request.folder_id.as_deref());
+        let is_sibling =
+            |i: &usize| self.existing_folder(self.requests[*i].folder_id.as_deref()) == folder;
+        let to = if down {
+            (from + 1..self.requests.len()).find(is_sibling)?
         } else {
-            self.selected_index = Some(0);
+            (0..from).rev().find(is_sibling)?
+        };
+
+        Some(SidebarAction::ProjectUpdate(ProjectUpdate::MoveRequest {
+            id: request.id.clone(),
+            folder_id: request.folder_id.clone(),
+            index: to,
+        }))
+    }
+
+    /// Picks up the selected request, or drops the one already picked up onto
+    /// the selected row.
+    fn handle_move(&mut self) -> Option<SidebarAction> {
+        let moving_id = match self.moving.take() {
+            Some(id) => id,
+            None => {
+                self.moving = self.selected_request().map(|r| r.id.clone());
+                return self.moving.as_ref().map(|_| SidebarAction::Updated);
+            }
+        };
+
+        self.selected_row()
+            .and_then(|row| self.move_request(&moving_id, row))
+            .or(Some(SidebarAction::Updated))
+    }
+
+    fn row_at(&self, mouse_event: &MouseEvent) -> Option<usize> {
+        let rect = self.rect?;
+        // The first line holds the title.
+        if !self.is_mouse_over(mouse_event, &rect) || mouse_event.row <= rect.y {
+            return None;
+        }
+        let index = (mouse_event.row - rect.y - 1) as usize;
+        (index < self.rows.len()).then_some(index)
+    }
+
+    /// Clicking selects a request or toggles a folder; dragging a request onto
+    /// another row moves it there.
+    fn handle_mouse(&mut self, mouse_event: &MouseEvent) -> Option<SidebarAction> {
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let index = self.row_at(mouse_event)?;
+                self.selected_index = Some(index);
+                match self.rows[index] {
+                    Row::Folder { index, .. } => Some(self.toggle_folder(index)),
+                    Row::Request { index, .. } => {
+                        self.dragging = Some(self.requests[index].id.clone());
+                        Some(SidebarAction::Selected(self.requests[index].clone()))
+                    }
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                self.dragging.as_ref()?;
+                let over = self.row_at(mouse_event);
+                if over == self.drop_target {
+                    return None;
+                }
+                self.drop_target = over;
+                Some(SidebarAction::Updated)
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.drop_target = None;
+                let id = self.dragging.take()?;
+                self.row_at(mouse_event)
+                    .and_then(|index| self.move_request(&id, self.rows[index]))
+                    .or(Some(SidebarAction::Updated))
+            }
+            _ => None,
         }
     }
 
     fn handle_selection(&mut self, key: KeyCode) -> Option<SidebarAction> {
         match key {
             KeyCode::Char('j') | KeyCode::Down => {
-                if !self.requests.is_empty() {
+                if !self.rows.is_empty() {
                     let new_index = match self.selected_index {
-                        Some(i) if i < self.requests.len() - 1 => Some(i + 1),
-                        None => Some(0),
-                        _ => self.selected_index,
+                        Some(i) if i < self.rows.len() - 1 => i + 1,
+                        Some(i) => i,
+                        None => 0,
                     };
-                    self.selected_index = new_index;
-                    new_index.map(|i| SidebarAction::Selected(self.requests[i].clone()))
+                    Some(self.select_index(new_index))
                 } else {
                     None
                 }
             }
             KeyCode::Char('k') | KeyCode::Up => {
-                if !self.requests.is_empty() {
+                if !self.rows.is_empty() {
                     let new_index = match self.selected_index {
-                        Some(i) if i > 0 => Some(i - 1),
-                        None => Some(self.requests.len() - 1),
-                        _ => self.selected_index,
+                        Some(i) if i > 0 => i - 1,
+                        Some(i) => i,
+                        None => self.rows.len() - 1,
                     };
-                    self.selected_index = new_index;
-                    new_index.map(|i| SidebarAction::Selected(self.requests[i].clone()))
+                    Some(self.select_index(new_index))
                 } else {
                     None
                 }
             }
             KeyCode::Char('e') => Some(SidebarAction::EditRequest),
-            KeyCode::Enter => self
-                .selected_index
-                .map(|i| SidebarAction::Selected(self.requests[i].clone())),
-            KeyCode::Char('d') => self
-                .selected_index
-                .map(|i| SidebarAction::ProjectUpdate(ProjectUpdate::DeleteRequest(i))),
+            KeyCode::Enter => match self.selected_row()? {
+                Row::Folder { index, .. } => Some(self.toggle_folder(index)),
+                Row::Request { index, .. } => {
+                    Some(SidebarAction::Selected(self.requests[index].clone()))
+                }
+            },
+            KeyCode::Right => match self.selected_row()? {
+                Row::Folder { index, .. } => {
+                    Some(self.set_collapsed(self.folders[index].id.clone(), false))
+                }
+                Row::Request { .. } => None,
+            },
+            KeyCode::Left => {
+                if let Some(Row::Folder { index, .. }) = self.selected_row() {
+                    let id = self.folders[index].id.clone();
+                    if !self.collapsed.contains(&id) {
+                        return Some(self.set_collapsed(id, true));
+                    }
+                }
+                // Otherwise jump to the enclosing folder.
+                let parent = match self.selected_row()? {
+                    Row::Folder { index, .. } => self.folders[index].parent_id.clone(),
+                    Row::Request { index, .. } => self.requests[index].folder_id.clone(),
+                };
+                let index = self.position(&parent?)?;
+                Some(self.select_index(index))
+            }
+            KeyCode::Char('d') => match self.selected_row()? {
+                Row::Folder { index, .. } => Some(SidebarAction::ProjectUpdate(
+                    ProjectUpdate::DeleteFolder(self.folders[index].id.clone()),
+                )),
+                Row::Request { index, .. } => Some(SidebarAction::ProjectUpdate(
+                    ProjectUpdate::DeleteRequest(self.requests[index].id.clone()),
+                )),
+            },
+            KeyCode::Char('n') => Some(SidebarAction::NewRequest(self.target_folder())),
+            KeyCode::Char('N') => Some(SidebarAction::NewFolder(self.target_folder())),
+            KeyCode::Char('r') => match self.selected_row()? {
+                Row::Folder { index, .. } => {
+                    Some(SidebarAction::RenameFolder(self.folders[index].clone()))
+                }
+                Row::Request { index, .. } => {
+                    Some(SidebarAction::RenameRequest(self.requests[index].clone()))
+                }
+            },
+            KeyCode::Char('c') => self.selected_request().map(|request| {
+                SidebarAction::ProjectUpdate(ProjectUpdate::AddRequest(request.duplicate()))
+            }),
+            KeyCode::Char('m') => self.handle_move(),
+            KeyCode::Char('J') => self.shift_selected(true),
+            KeyCode::Char('K') => self.shift_selected(false),
+            KeyCode::Esc => self.moving.take().map(|_| SidebarAction::Updated),
             _ => None,
         }
     }
@@ -92,22 +403,11 @@ impl Component for Sidebar {
                         return action;
                     }
                 }
-                Event::Mouse(mouse_event) => match mouse_event.kind {
-                    MouseEventKind::Down(crossterm::event::MouseButton::Left) => {
-                        if let Some(rect) = self.rect {
-                            if self.is_mouse_over(mouse_event, &rect) {
-                                let relative_y = mouse_event.row.saturating_sub(rect.y + 1); // +1 for border
-                                if (relative_y as usize) < self.requests.len() {
-                                    self.selected_index = Some(relative_y as usize);
-                                    return SidebarAction::Selected(
-                                        self.requests[relative_y as usize].clone(),
-                                    );
-                                }
-                            }
-                        }
+                Event::Mouse(mouse_event) => {
+                    if let Some(action) = self.handle_mouse(mouse_event) {
+                        return action;
                     }
-                    _ => {}
-                },
+                }
                 _ => {}
             }
         }
@@ -119,70 +419,99 @@ impl Component for Sidebar {
         self.rect = Some(rect);
 
         let items: Vec<ListItem> = self
-            .requests
+            .rows
             .iter()
             .enumerate()
-            .map(|(i, request)| {
+            .map(|(i, row)| {
                 let is_selected = Some(i) == self.selected_index;
-                let method_style = if let Some(method) = &request.method {
-                    match method.as_str() {
-                        "GET" => Style::default().fg(theme.http_methods.get),
-                        "POST" => Style::default().fg(theme.http_methods.post),
-                        "PUT" => Style::default().fg(theme.http_methods.put),
-                        "DELETE" => Style::default().fg(theme.http_methods.delete),
-                        "PATCH" => Style::default().fg(theme.http_methods.patch),
-                        "HEAD" => Style::default().fg(theme.http_methods.head),
-                        _ => Style::default().fg(theme.http_methods.default),
-                    }
-                } else {
-                    Style::default().fg(theme.http_methods.default)
-                }
-                .add_modifier(Modifier::BOLD)
-                .bg(if is_selected {
+                let bg = if is_selected {
                     theme.sidebar.selected_bg
                 } else {
                     theme.sidebar.bg
-                });
+                };
+
+                let mut spans = vec![Span::styled(
+                    if is_selected { "→ " } else { "  " },
+                    Style::default().bg(bg),
+                )];
 
-                let name_style =
-                    Style::default()
-                        .fg(theme.sidebar.text_unfocused)
-                        .bg(if is_selected {
-                            theme.sidebar.selected_bg
+                match *row {
+                    Row::Folder { index, depth } => {
+                        let folder = &self.folders[index];
+                        let marker = if self.collapsed.contains(&folder.id) {
+                            "▸"
+                        } else {
+                            "▾"
+                        };
+                        spans.push(Span::raw("  ".repeat(depth)));
+                        spans.push(Span::styled(
+                            format!("{} {}", marker, folder.name),
+                            Style::default()
+                                .fg(theme.sidebar.text)
+                                .bg(bg)
+                                .add_modifier(Modifier::BOLD),
+                        ));
+                    }
+                    Row::Request { index, depth } => {
+                        let request = &self.requests[index];
+                        let method_style = if let Some(method) = &request.method {
+                            match method.as_str() {
+                                "GET" => Style::default().fg(theme.http_methods.get),
+                                "POST" => Style::default().fg(theme.http_methods.post),
+                                "PUT" => Style::default().fg(theme.http_methods.put),
+                                "DELETE" => Style::default().fg(theme.http_methods.delete),
+                                "PATCH" => Style::default().fg(theme.http_methods.patch),
+                                "HEAD" => Style::default().fg(theme.http_methods.head),
+                                _ => Style::default().fg(theme.http_methods.default),
+                            }
                         } else {
-                            theme.sidebar.bg
-                        });
+                            Style::default().fg(theme.http_methods.default)
+                        }
+                        .add_modifier(Modifier::BOLD)
+                        .bg(bg);
 
-                let method = format!(" {} ", request.method.clone().unwrap_or("".to_string()));
-                let name = format!(" {}", request.name.clone());
+                        let mut name_style =
+                            Style::default().fg(theme.sidebar.text_unfocused).bg(bg);
+                        if self.moving.as_ref() == Some(&request.id) {
+                            name_style = name_style
+                                .fg(theme.sidebar.title_focused)
+                                .add_modifier(Modifier::ITALIC);
+                        }
 
-                ListItem::new(Line::from(vec![
-                    Span::styled(
-                        if is_selected { "→ " } else { "  " },
-                        Style::default().bg(if is_selected {
-                            theme.sidebar.selected_bg
-                        } else {
-                            theme.sidebar.bg
-                        }),
-                    ),
-                    Span::styled(method, method_style),
-                    Span::raw(" "),
-                    Span::styled(name, name_style),
-                ]))
-                .style(Style::default().bg(if is_selected {
-                    theme.sidebar.selected_bg
-                } else {
-                    theme.sidebar.bg
-                }))
+                        let method =
+                            format!(" {} ", request.method.clone().unwrap_or("".to_string()));
+                        let name = format!(" {}", request.name.clone());
+
+                        spans.push(Span::raw("  ".repeat(depth)));
+                        spans.push(Span::styled(method, method_style));
+                        spans.push(Span::raw(" "));
+                        spans.push(Span::styled(name, name_style));
+                    }
+                }
+
+                let mut style = Style::default().bg(bg);
+                if Some(i) == self.drop_target {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                ListItem::new(Line::from(spans)).style(style)
             })
             .collect();
 
+        let title = match self
+            .moving
+            .as_ref()
+            .and_then(|id| self.requests.iter().find(|r| &r.id == id))
+        {
+            Some(request) => format!(" ⦿ Moving '{}' (m to drop) ", request.name),
+            None => " ⦿ Requests ".to_string(),
+        };
+
         let list = List::new(items)
             .block(
                 Block::default()
                     .style(Style::default().bg(theme.sidebar.bg))
                     .title(Span::styled(
-                        " ⦿ Requests ",
+                        title,
                         Style::default()
                             .fg(theme.sidebar.title_focused)
                             .add_modifier(Modifier::BOLD),
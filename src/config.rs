@@ -1,12 +1,46 @@
+use crate::persistence::TlsSettings;
 use crate::theme::{FooterColors, GeneralColors, HttpMethodColors, SidebarColors, Theme};
 use dirs::home_dir;
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Read from the working directory on top of the user's config, so a
+/// repository can choose how its own collections are stored.
+const WORKSPACE_CONFIG: &str = ".rurl.toml";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     pub theme: Option<ThemeConfig>,
+    pub storage: Option<StorageConfig>,
+    pub secrets: Option<SecretsConfig>,
+    /// Applied to every request, under the project's and environment's own.
+    pub tls: Option<TlsSettings>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StorageConfig {
+    pub backend: Option<StorageBackend>,
+    pub database: Option<PathBuf>,
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SecretsConfig {
+    /// A file whose contents secret values are encrypted with. Without one,
+    /// the passphrase in `RURL_PASSPHRASE` is used.
+    pub key_file: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Sqlite,
+    Files,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -63,22 +97,39 @@ pub struct SidebarColorsConfig {
 
 impl Config {
     pub fn load() -> Self {
-        let config_path = Self::get_config_path();
-        if !config_path.exists() {
-            return Self::default();
+        let mut config = Self::read(&Self::get_config_path()).unwrap_or_default();
+
+        // Sections in the workspace config replace the user's. Keys for
+        // secrets are personal, so only the user's config can name one, and
+        // a repository should not be able to turn off certificate checks.
+        if let Some(workspace) = Self::read(Path::new(WORKSPACE_CONFIG)) {
+            if workspace.theme.is_some() {
+                config.theme = workspace.theme;
+            }
+            if workspace.storage.is_some() {
+                config.storage = workspace.storage;
+            }
+        }
+
+        config
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        if !path.exists() {
+            return None;
         }
 
-        match fs::read_to_string(&config_path) {
+        match fs::read_to_string(path) {
             Ok(contents) => match toml::from_str(&contents) {
-                Ok(config) => config,
+                Ok(config) => Some(config),
                 Err(e) => {
-                    eprintln!("Error parsing config file: {}", e);
-                    Self::default()
+                    eprintln!("Error parsing config file {}: {}", path.display(), e);
+                    None
                 }
             },
             Err(e) => {
-                eprintln!("Error reading config file: {}", e);
-                Self::default()
+                eprintln!("Error reading config file {}: {}", path.display(), e);
+                None
             }
         }
     }
@@ -90,6 +141,29 @@ impl Config {
             .join("config.toml")
     }
 
+    pub fn storage_backend(&self) -> StorageBackend {
+        self.storage
+            .as_ref()
+            .and_then(|s| s.backend)
+            .unwrap_or_default()
+    }
+
+    pub fn database_path(&self) -> Option<PathBuf> {
+        self.storage.as_ref().and_then(|s| s.database.clone())
+    }
+
+    pub fn collection_directory(&self) -> Option<PathBuf> {
+        self.storage.as_ref().and_then(|s| s.directory.clone())
+    }
+
+    pub fn secrets_key_file(&self) -> Option<PathBuf> {
+        self.secrets.as_ref().and_then(|s| s.key_file.clone())
+    }
+
+    pub fn tls(&self) -> TlsSettings {
+        self.tls.clone().unwrap_or_default()
+    }
+
     pub fn create_theme(&self) -> Theme {
         let mut theme = Theme::default();
 
@@ -214,13 +288,31 @@ impl Config {
 
 impl Default for Config {
     fn default() -> Self {
-        Self { theme: None }
+        Self {
+            theme: None,
+            storage: None,
+            secrets: None,
+            tls: None,
+        }
     }
 }
 
 pub fn generate_default_config() -> String {
     r#"# RURL Configuration File
 
+[storage]
+# "sqlite" (default) keeps everything in one database. "files" writes each
+# project as a directory of TOML files that can be committed to a repository.
+# A .rurl.toml in the working directory can override this section.
+# backend = "sqlite"
+
+# Path to the SQLite database. Defaults to <data dir>/rurl/rurl.db
+# Use ":memory:" for a throwaway session that is never written to disk.
+# database = "/path/to/rurl.db"
+
+# Directory used by the "files" backend. Defaults to ./rurl
+# directory = "api/rurl"
+
 [theme]
 # All color values are specified as RGB tuples: (red, green, blue)
 # Each component ranges from 0-255
@@ -0,0 +1,240 @@
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Algorithm {
+    Md5,
+    Sha256,
+}
+
+impl Algorithm {
+    fn name(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha256 => "SHA-256",
+        }
+    }
+
+    fn hash(self, data: &str) -> String {
+        let bytes = match self {
+            Algorithm::Md5 => Md5::digest(data.as_bytes()).to_vec(),
+            Algorithm::Sha256 => Sha256::digest(data.as_bytes()).to_vec(),
+        };
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// What a server asks for in a `WWW-Authenticate: Digest ...` header.
+#[derive(Debug, PartialEq)]
+pub struct Challenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub algorithm: Algorithm,
+    /// Whether the server offered `qop=auth`. Without it the older RFC 2069
+    /// response is sent.
+    pub qop_auth: bool,
+}
+
+impl Challenge {
+    /// Parses one `WWW-Authenticate` header value. Fails if it is not a
+    /// Digest challenge or asks for something that is not supported.
+    pub fn parse(header: &str) -> Result<Self, String> {
+        let (scheme, rest) = header.trim().split_once(' ').unwrap_or((header, ""));
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return Err(format!("not a Digest challenge: {}", header));
+        }
+        let params = parse_params(rest);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+
+        let algorithm = match param("algorithm").as_deref() {
+            None => Algorithm::Md5,
+            Some(name) if name.eq_ignore_ascii_case("MD5") => Algorithm::Md5,
+            Some(name) if name.eq_ignore_ascii_case("SHA-256") => Algorithm::Sha256,
+            Some(name) => return Err(format!("unsupported Digest algorithm {}", name)),
+        };
+        let qop_auth = match param("qop") {
+            None => false,
+            Some(qop) if qop.split(',').any(|q| q.trim() == "auth") => true,
+            Some(qop) => return Err(format!("unsupported Digest qop {}", qop)),
+        };
+        Ok(Challenge {
+            realm: param("realm").ok_or("Digest challenge has no realm")?,
+            nonce: param("nonce").ok_or("Digest challenge has no nonce")?,
+            opaque: param("opaque"),
+            algorithm,
+            qop_auth,
+        })
+    }
+
+    /// Picks the strongest supported challenge among `headers`.
+    pub fn choose<'a>(headers: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut error = "the server sent no Digest challenge".to_string();
+        let mut best: Option<Challenge> = None;
+        for header in headers {
+            match Challenge::parse(header) {
+                Ok(challenge) if best.as_ref().is_none_or(|b| b.algorithm == Algorithm::Md5) => {
+                    best = Some(challenge)
+                }
+                Ok(_) => {}
+                Err(e) => error = e,
+            }
+        }
+        best.ok_or(error)
+    }
+
+    /// The `Authorization` header value answering this challenge.
+    pub fn authorization(&self, username: &str, password: &str, method: &str, uri: &str) -> String {
+        let cnonce = Uuid::new_v4().simple().to_string();
+        self.authorization_with(username, password, method, uri, &cnonce)
+    }
+
+    fn authorization_with(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        // Each challenge is answered once, so the nonce count is always 1.
+        let nc = "00000001";
+        let hash = |data: String| self.algorithm.hash(&data);
+        let ha1 = hash(format!("{}:{}:{}", username, self.realm, password));
+        let ha2 = hash(format!("{}:{}", method, uri));
+        let response = if self.qop_auth {
+            hash(format!(
+                "{}:{}:{}:{}:auth:{}",
+                ha1, self.nonce, nc, cnonce, ha2
+            ))
+        } else {
+            hash(format!("{}:{}:{}", ha1, self.nonce, ha2))
+        };
+
+        let mut header = format!(
+            r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm={}, response="{}""#,
+            quote(username),
+            quote(&self.realm),
+            quote(&self.nonce),
+            quote(uri),
+            self.algorithm.name(),
+            response
+        );
+        if self.qop_auth {
+            header.push_str(&format!(r#", qop=auth, nc={}, cnonce="{}""#, nc, cnonce));
+        }
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(r#", opaque="{}""#, quote(opaque)));
+        }
+        header
+    }
+}
+
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Splits `a=1, b="x, y"` into its pairs, unquoting values.
+fn parse_params(text: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| *c == ',' || c.is_whitespace()).is_some() {}
+        let name: String = std::iter::from_fn(|| chars.next_if(|c| *c != '=')).collect();
+        if chars.next().is_none() {
+            break;
+        }
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            value = std::iter::from_fn(|| chars.next_if(|c| *c != ','))
+                .collect::<String>()
+                .trim()
+                .to_string();
+        }
+        params.push((name.trim().to_string(), value));
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The examples from RFC 7616, section 3.9.1.
+    const CHALLENGE: &str = r#"Digest realm="http-auth@example.org", qop="auth, auth-int", nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    fn response_of(header: &str) -> String {
+        let params = parse_params(header.strip_prefix("Digest ").unwrap());
+        params
+            .into_iter()
+            .find(|(name, _)| name == "response")
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_rfc_7616_examples() {
+        let md5 = Challenge::parse(&format!("{}, algorithm=MD5", CHALLENGE)).unwrap();
+        assert_eq!(md5.algorithm, Algorithm::Md5);
+        assert!(md5.qop_auth);
+        let header =
+            md5.authorization_with("Mufasa", "Circle of Life", "GET", "/dir/index.html", CNONCE);
+        assert_eq!(response_of(&header), "8ca523f5e9506fed4657c9700eebdbec");
+        assert!(header.contains("qop=auth, nc=00000001"));
+        assert!(header.contains(r#"opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#));
+
+        let sha256 = Challenge::parse(&format!("{}, algorithm=SHA-256", CHALLENGE)).unwrap();
+        let header =
+            sha256.authorization_with("Mufasa", "Circle of Life", "GET", "/dir/index.html", CNONCE);
+        assert_eq!(
+            response_of(&header),
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+        );
+        assert!(header.contains("algorithm=SHA-256"));
+    }
+
+    #[test]
+    fn test_parse_and_choose() {
+        assert_eq!(
+            parse_params(r#"realm="a, \"b\"", stale=false,qop=auth"#),
+            vec![
+                ("realm".to_string(), r#"a, "b""#.to_string()),
+                ("stale".to_string(), "false".to_string()),
+                ("qop".to_string(), "auth".to_string()),
+            ]
+        );
+
+        let plain = Challenge::parse(r#"Digest realm="r", nonce="n""#).unwrap();
+        assert_eq!(plain.algorithm, Algorithm::Md5);
+        assert!(!plain.qop_auth);
+        assert!(!plain.authorization("u", "p", "GET", "/").contains("qop="));
+
+        assert!(Challenge::parse(r#"Basic realm="r""#).is_err());
+        assert!(Challenge::parse(r#"Digest realm="r", nonce="n", qop="auth-int""#).is_err());
+        assert!(Challenge::parse(r#"Digest realm="r", nonce="n", algorithm=SHA-512-256"#).is_err());
+
+        let chosen = Challenge::choose([
+            r#"Basic realm="r""#,
+            r#"Digest realm="r", nonce="n", algorithm=MD5"#,
+            r#"Digest realm="r", nonce="n", algorithm=SHA-256"#,
+        ])
+        .unwrap();
+        assert_eq!(chosen.algorithm, Algorithm::Sha256);
+        assert!(Challenge::choose([r#"Basic realm="r""#]).is_err());
+    }
+}
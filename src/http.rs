@@ -0,0 +1,906 @@
+use chrono::Utc;
+use reqwest::blocking::{Client, Request, RequestBuilder};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Method, StatusCode, Version};
+use std::error::Error;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::auth::{self, Placement};
+use crate::digest::Challenge;
+use crate::oauth;
+use crate::persistence::{
+    AuthData, Hop, HttpProtocol, OAuthToken, ProjectSettings, RequestData, ResponseData, Signing,
+    TlsSettings,
+};
+use crate::signing;
+use crate::sigv4::Signer;
+use crate::tls::{self, Chain};
+
+pub type HttpResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+pub enum HttpAction {
+    Completed(RequestData, ResponseData),
+    Failed(RequestData, String),
+    /// The user has to sign in at this URL before the request can be sent.
+    Authorizing(String),
+    /// An OAuth2 token was issued while sending, to be cached under the key.
+    TokenIssued(String, OAuthToken),
+}
+
+/// How the client is set up for a send.
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    pub connect_timeout: Option<Duration>,
+    /// For the whole exchange. Requests wait until they are answered or
+    /// cancelled when unset.
+    pub timeout: Option<Duration>,
+    /// Already merged from every level it can be set at.
+    pub tls: TlsSettings,
+}
+
+impl ClientOptions {
+    /// The project's timeouts. Its TLS settings are left to the caller, who
+    /// knows the global and environment ones to merge them with.
+    pub fn for_project(settings: &ProjectSettings) -> Self {
+        ClientOptions {
+            connect_timeout: settings.connect_timeout.map(Duration::from_secs),
+            timeout: settings.timeout.map(Duration::from_secs),
+            tls: TlsSettings::default(),
+        }
+    }
+
+    /// A client for one send over `protocol`, and where the server's
+    /// certificates will be.
+    pub fn client(&self, protocol: HttpProtocol) -> HttpResult<(Client, Chain)> {
+        let (mut tls, chain) = tls::client_config(&self.tls)?;
+        // Offered while connecting over TLS; the server picks one.
+        tls.alpn_protocols = match protocol {
+            HttpProtocol::Auto => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            HttpProtocol::Http1 => vec![b"http/1.1".to_vec()],
+            HttpProtocol::Http2 | HttpProtocol::H2c => vec![b"h2".to_vec()],
+        };
+        let mut builder = Client::builder().use_preconfigured_tls(tls);
+        builder = match protocol {
+            HttpProtocol::Auto => builder,
+            HttpProtocol::Http1 => builder.http1_only(),
+            HttpProtocol::Http2 | HttpProtocol::H2c => builder.http2_prior_knowledge(),
+        };
+        // `None` turns off reqwest's default of 30s.
+        builder = builder
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout);
+        Ok((builder.build()?, chain))
+    }
+}
+
+struct InFlight {
+    id: u64,
+    request: RequestData,
+    started_at: Instant,
+}
+
+/// Runs requests on background threads and hands the results back through a
+/// channel so the UI loop never blocks on the network.
+pub struct RequestWorker {
+    sender: Sender<(u64, HttpAction)>,
+    receiver: Receiver<(u64, HttpAction)>,
+    next_id: u64,
+    in_flight: Option<InFlight>,
+}
+
+impl RequestWorker {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        RequestWorker {
+            sender,
+            receiver,
+            next_id: 0,
+            in_flight: None,
+        }
+    }
+
+    /// Starts sending `request`, replacing any request that is still pending.
+    /// `generated` is what its built-in placeholders were given, kept with
+    /// the response. `token` is the cached OAuth2 token for its auth, if it
+    /// has one.
+    pub fn send(
+        &mut self,
+        request: RequestData,
+        generated: Vec<(String, String)>,
+        token: Option<OAuthToken>,
+        options: ClientOptions,
+    ) {
+        self.next_id += 1;
+        let id = self.next_id;
+        let sender = self.sender.clone();
+        let job = request.clone();
+
+        thread::spawn(move || {
+            let notify = |action| {
+                let _ = sender.send((id, action));
+            };
+            let result = prepare(&job, token, &options, notify)
+                .and_then(|prepared| execute(&prepared, &options));
+            let action = match result {
+                Ok(response) => HttpAction::Completed(
+                    job,
+                    ResponseData {
+                        variables: generated,
+                        ..response
+                    },
+                ),
+                Err(e) => HttpAction::Failed(job, e.to_string()),
+            };
+            // The receiver only goes away when the app is shutting down.
+            let _ = sender.send((id, action));
+        });
+
+        self.in_flight = Some(InFlight {
+            id,
+            request,
+            started_at: Instant::now(),
+        });
+    }
+
+    /// Abandons the pending request. The worker thread is left to finish on its
+    /// own, but its result is discarded when it arrives.
+    pub fn cancel(&mut self) -> Option<RequestData> {
+        self.in_flight.take().map(|in_flight| in_flight.request)
+    }
+
+    /// The request currently in flight and how long it has been running.
+    pub fn pending(&self) -> Option<(&RequestData, Duration)> {
+        self.in_flight
+            .as_ref()
+            .map(|in_flight| (&in_flight.request, in_flight.started_at.elapsed()))
+    }
+
+    /// Returns the result of the pending request once it is available, and
+    /// anything it reports along the way. Issued tokens are returned even if
+    /// their request was cancelled, so they can still be cached.
+    pub fn poll(&mut self) -> Option<HttpAction> {
+        while let Ok((id, action)) = self.receiver.try_recv() {
+            let current = self.in_flight.as_ref().is_some_and(|f| f.id == id);
+            match action {
+                HttpAction::TokenIssued(..) => return Some(action),
+                HttpAction::Authorizing(_) if current => return Some(action),
+                HttpAction::Completed(..) | HttpAction::Failed(..) if current => {
+                    self.in_flight = None;
+                    return Some(action);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Swaps OAuth2 auth for a bearer token, using `token` if it is still fresh.
+/// The browser is opened if the user has to sign in.
+fn prepare(
+    request: &RequestData,
+    token: Option<OAuthToken>,
+    options: &ClientOptions,
+    notify: impl Fn(HttpAction),
+) -> HttpResult<RequestData> {
+    let Some((auth, key)) = request
+        .auth
+        .as_ref()
+        .and_then(|auth| Some((auth, oauth::cache_key(auth)?)))
+    else {
+        return Ok(request.clone());
+    };
+    let (token, issued) = oauth::authorize(auth, token, options, |url| {
+        let _ = open::that_detached(url);
+        notify(HttpAction::Authorizing(url.to_string()));
+    })?;
+    if issued {
+        notify(HttpAction::TokenIssued(key, token.clone()));
+    }
+    Ok(oauth::with_token(request, &token))
+}
+
+/// Sends `request` and blocks until the full response body has been read.
+/// Digest auth answers the server's challenge with a second request; the
+/// first is kept as a hop.
+pub fn execute(request: &RequestData, options: &ClientOptions) -> HttpResult<ResponseData> {
+    let (client, chain) = options.client(request.protocol)?;
+    let mut outgoing = build_request(&client, request)?.build()?;
+    match (request.protocol, outgoing.url().scheme()) {
+        (HttpProtocol::Http2, "http") => {
+            return Err("HTTP/2 is only agreed on over https; use h2c for plain http".into())
+        }
+        (HttpProtocol::H2c, "https") => {
+            return Err("h2c is HTTP/2 over plain http; use HTTP/2 for https".into())
+        }
+        _ => {}
+    }
+    let signing = sign(&mut outgoing, request.auth.as_ref())?;
+
+    let started = Instant::now();
+    let mut response = client.execute(outgoing)?;
+    let mut hops = Vec::new();
+
+    if let Some(AuthData::Digest { username, password }) = &request.auth {
+        if response.status() == StatusCode::UNAUTHORIZED {
+            hops.push(Hop {
+                status_code: response.status().as_u16() as i32,
+                response_headers: header_pairs(response.headers()),
+                response_time: started.elapsed().as_millis() as i64,
+            });
+            let challenges = response.headers().get_all(WWW_AUTHENTICATE);
+            let challenge = Challenge::choose(challenges.iter().filter_map(|v| v.to_str().ok()))
+                .map_err(|e| format!("Digest auth failed: {}", e))?;
+
+            let mut retry = build_request(&client, request)?.build()?;
+            let uri = match retry.url().query() {
+                Some(query) => format!("{}?{}", retry.url().path(), query),
+                None => retry.url().path().to_string(),
+            };
+            let authorization =
+                challenge.authorization(username, password, retry.method().as_str(), &uri);
+            retry
+                .headers_mut()
+                .insert(AUTHORIZATION, HeaderValue::from_str(&authorization)?);
+            response = client.execute(retry)?;
+        }
+    }
+
+    let status_code = response.status().as_u16() as i32;
+    let http_version = version_name(response.version());
+    let response_headers = header_pairs(response.headers());
+    let response_body = response.text()?;
+    let response_time = started.elapsed().as_millis() as i64;
+    let certificates = tls::describe(&chain.lock().unwrap());
+
+    Ok(ResponseData {
+        request_id: 0,
+        status_code: Some(status_code),
+        response_body: Some(response_body),
+        response_headers: Some(response_headers),
+        response_time,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64,
+        http_version: Some(http_version.to_string()),
+        variables: Vec::new(),
+        hops,
+        signing,
+        certificates,
+    })
+}
+
+fn version_name(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "HTTP/0.9",
+        Version::HTTP_10 => "HTTP/1.0",
+        Version::HTTP_11 => "HTTP/1.1",
+        Version::HTTP_2 => "HTTP/2",
+        Version::HTTP_3 => "HTTP/3",
+        _ => "HTTP",
+    }
+}
+
+/// Adds the headers of the signing schemes, signing the request exactly as it
+/// will be sent. What was signed is returned for AWS Signature V4.
+fn sign(outgoing: &mut Request, auth: Option<&AuthData>) -> HttpResult<Option<Signing>> {
+    match auth {
+        Some(AuthData::AwsSigV4 {
+            access_key,
+            secret_key,
+            session_token,
+            region,
+            service,
+        }) => {
+            let signer = Signer {
+                access_key,
+                secret_key,
+                session_token,
+                region,
+                service,
+            };
+            let (added, signing) = signer.sign(
+                outgoing.method().as_str(),
+                outgoing.url(),
+                &header_pairs(outgoing.headers()),
+                outgoing
+                    .body()
+                    .and_then(|b| b.as_bytes())
+                    .unwrap_or_default(),
+                Utc::now(),
+            );
+            for (name, value) in added {
+                outgoing.headers_mut().insert(
+                    HeaderName::from_bytes(name.as_bytes())?,
+                    HeaderValue::from_str(&value)?,
+                );
+            }
+            Ok(Some(signing))
+        }
+        Some(AuthData::Jwt {
+            algorithm,
+            key,
+            claims,
+            expires_in,
+        }) => {
+            let token = signing::jwt(*algorithm, key, claims, expires_in, Utc::now().timestamp())
+                .map_err(|e| format!("JWT signing failed: {}", e))?;
+            outgoing.headers_mut().insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {}", token))?,
+            );
+            Ok(None)
+        }
+        Some(AuthData::Hmac {
+            algorithm,
+            secret,
+            header,
+            template,
+            encoding,
+            timestamp_header,
+        }) => {
+            let timestamp = Utc::now().timestamp();
+            let url = outgoing.url();
+            let path = match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_string(),
+            };
+            let body = outgoing
+                .body()
+                .and_then(|b| b.as_bytes())
+                .unwrap_or_default();
+            let message = signing::message(
+                template,
+                outgoing.method().as_str(),
+                &path,
+                &String::from_utf8_lossy(body),
+                timestamp,
+            );
+            let signature = signing::hmac_signature(*algorithm, *encoding, secret, &message);
+            let headers = outgoing.headers_mut();
+            headers.insert(
+                HeaderName::from_bytes(header.as_bytes())?,
+                HeaderValue::from_str(&signature)?,
+            );
+            if !timestamp_header.is_empty() {
+                headers.insert(
+                    HeaderName::from_bytes(timestamp_header.as_bytes())?,
+                    HeaderValue::from(timestamp),
+                );
+            }
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).to_string(),
+            )
+        })
+        .collect()
+}
+
+fn build_request(client: &Client, request: &RequestData) -> HttpResult<RequestBuilder> {
+    let method = parse_method(request.method.as_deref())?;
+    let url = build_url(request)?;
+    let mut builder = client.request(method, url);
+
+    if let Some(headers) = &request.headers {
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+    }
+
+    if let Some(query_params) = &request.query_params {
+        builder = builder.query(query_params);
+    }
+
+    builder = match request.auth.as_ref().and_then(auth::apply) {
+        Some(Placement::Header(name, value)) => builder.header(name, value),
+        Some(Placement::Query(name, value)) => builder.query(&[(name, value)]),
+        None => builder,
+    };
+
+    if let Some(body) = request.body.as_ref().filter(|b| !b.is_empty()) {
+        builder = builder.body(body.clone());
+    }
+
+    Ok(builder)
+}
+
+fn parse_method(method: Option<&str>) -> HttpResult<Method> {
+    let method = method
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .unwrap_or("GET");
+    Ok(Method::from_bytes(method.to_uppercase().as_bytes())?)
+}
+
+/// Resolves path parameters into the request URL and defaults the scheme to
+/// `http://` when none is given.
+fn build_url(request: &RequestData) -> HttpResult<String> {
+    let url = request.url.as_deref().map(str::trim).unwrap_or_default();
+    if url.is_empty() {
+        return Err("Request has no URL".into());
+    }
+
+    let url = match &request.path_params {
+        Some(params) => resolve_path_params(url, params),
+        None => url.to_string(),
+    };
+
+    if url.contains("://") {
+        Ok(url)
+    } else {
+        Ok(format!("http://{}", url))
+    }
+}
+
+/// Substitutes `{name}` placeholders anywhere in the URL and `:name` when it
+/// makes up a whole path segment.
+fn resolve_path_params(url: &str, params: &[(String, String)]) -> String {
+    let mut resolved = url.to_string();
+    for (name, value) in params {
+        resolved = resolved.replace(&format!("{{{}}}", name), value);
+    }
+
+    resolved
+        .split('/')
+        .map(|segment| {
+            segment
+                .strip_prefix(':')
+                .and_then(|name| params.iter().find(|(k, _)| k == name))
+                .map_or(segment, |(_, value)| value.as_str())
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{HmacAlgorithm, JwtAlgorithm, OAuthGrant, SignatureEncoding};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_resolve_path_params() {
+        let params = vec![
+            ("id".to_string(), "42".to_string()),
+            ("org".to_string(), "acme".to_string()),
+        ];
+        assert_eq!(
+            resolve_path_params("https://api.example.com/orgs/{org}/users/:id", &params),
+            "https://api.example.com/orgs/acme/users/42"
+        );
+        assert_eq!(
+            resolve_path_params("localhost:8080/users/:idx", &params),
+            "localhost:8080/users/:idx"
+        );
+    }
+
+    fn respond_once(listener: TcpListener, delay: Duration) -> thread::JoinHandle<String> {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            thread::sleep(delay);
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        })
+    }
+
+    /// Answers one HTTP/2 request over plain TCP with "ok", returning its
+    /// method and path.
+    fn respond_h2c_once(listener: TcpListener) -> thread::JoinHandle<String> {
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                listener.set_nonblocking(true).unwrap();
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut connection = h2::server::handshake(stream).await.unwrap();
+                let (request, mut respond) = connection.accept().await.unwrap().unwrap();
+                let response = http::Response::builder().status(200).body(()).unwrap();
+                let mut body = respond.send_response(response, false).unwrap();
+                body.send_data(bytes::Bytes::from_static(b"ok"), true)
+                    .unwrap();
+                // Drives the connection until the client hangs up.
+                while let Some(Ok(_)) = connection.accept().await {}
+                format!("{} {}", request.method(), request.uri().path())
+            })
+        })
+    }
+
+    fn wait_for_result(worker: &mut RequestWorker) -> Option<HttpAction> {
+        for _ in 0..200 {
+            if let Some(action) = worker.poll() {
+                return Some(action);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        None
+    }
+
+    #[test]
+    fn test_worker_delivers_result() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = respond_once(listener, Duration::ZERO);
+
+        let mut worker = RequestWorker::new();
+        let mut request = RequestData::new("ping".to_string());
+        request.url = Some(addr.to_string());
+        let generated = vec![("$uuid".to_string(), "1234".to_string())];
+        worker.send(request, generated.clone(), None, ClientOptions::default());
+        assert!(worker.pending().is_some());
+
+        match wait_for_result(&mut worker) {
+            Some(HttpAction::Completed(sent, response)) => {
+                assert_eq!(sent.name, "ping");
+                assert_eq!(response.status_code, Some(200));
+                assert_eq!(response.variables, generated);
+            }
+            _ => panic!("expected a completed response"),
+        }
+        assert!(worker.pending().is_none());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_worker_discards_cancelled_result() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = respond_once(listener, Duration::from_millis(200));
+
+        let mut worker = RequestWorker::new();
+        let mut request = RequestData::new("slow".to_string());
+        request.url = Some(addr.to_string());
+        worker.send(request, Vec::new(), None, ClientOptions::default());
+
+        assert_eq!(worker.cancel().map(|r| r.name), Some("slow".to_string()));
+        server.join().unwrap();
+        assert!(wait_for_result(&mut worker).is_none());
+    }
+
+    #[test]
+    fn test_worker_swaps_in_oauth_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for body in [r#"{"access_token": "a1", "expires_in": 60}"#, "ok"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let n = stream.read(&mut buf).unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        let auth = AuthData::OAuth2 {
+            grant: OAuthGrant::ClientCredentials,
+            auth_url: String::new(),
+            token_url: format!("http://{}/token", addr),
+            client_id: "rurl".to_string(),
+            client_secret: String::new(),
+            scope: String::new(),
+            username: String::new(),
+            password: String::new(),
+        };
+        let mut request = RequestData::new("items".to_string());
+        request.url = Some(format!("{}/items", addr));
+        request.auth = Some(auth.clone());
+        let mut worker = RequestWorker::new();
+        worker.send(request, Vec::new(), None, ClientOptions::default());
+
+        match wait_for_result(&mut worker) {
+            Some(HttpAction::TokenIssued(key, token)) => {
+                assert_eq!(Some(key), oauth::cache_key(&auth));
+                assert_eq!(token.access_token, "a1");
+            }
+            _ => panic!("expected the token to be reported first"),
+        }
+        match wait_for_result(&mut worker) {
+            Some(HttpAction::Completed(sent, response)) => {
+                assert_eq!(sent.auth, Some(auth));
+                assert_eq!(response.response_body.as_deref(), Some("ok"));
+            }
+            _ => panic!("expected a completed response"),
+        }
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("post /token"));
+        assert!(requests[1].starts_with("get /items"));
+        assert!(requests[1].contains("authorization: bearer a1"));
+    }
+
+    #[test]
+    fn test_digest_answers_challenge() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let replies = [
+                "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"test\"\r\nWWW-Authenticate: Digest realm=\"test\", nonce=\"abc\", qop=\"auth\", algorithm=SHA-256\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            ];
+            let mut requests = Vec::new();
+            for reply in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let n = stream.read(&mut buf).unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_string());
+                stream.write_all(reply.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        let mut request = RequestData::new("secure".to_string());
+        request.url = Some(format!("{}/secure", addr));
+        request.query_params = Some(vec![("x".to_string(), "1".to_string())]);
+        request.auth = Some(AuthData::Digest {
+            username: "admin".to_string(),
+            password: "pw".to_string(),
+        });
+
+        let response = execute(&request, &ClientOptions::default()).unwrap();
+        assert_eq!(response.status_code, Some(200));
+        assert_eq!(response.hops.len(), 1);
+        assert_eq!(response.hops[0].status_code, 401);
+        assert!(response.response_time >= response.hops[0].response_time);
+
+        let requests = server.join().unwrap();
+        assert!(!requests[0].to_lowercase().contains("authorization:"));
+        let authorization = requests[1]
+            .lines()
+            .find_map(|line| line.strip_prefix("authorization: "))
+            .unwrap();
+        assert!(authorization.starts_with(r#"Digest username="admin", realm="test""#));
+        assert!(authorization.contains(r#"uri="/secure?x=1""#));
+        assert!(authorization.contains("algorithm=SHA-256"));
+        assert!(authorization.contains("qop=auth, nc=00000001"));
+    }
+
+    #[test]
+    fn test_aws_sigv4_signs_final_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(
+                    b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+
+        let mut request = RequestData::new("signed".to_string());
+        request.method = Some("POST".to_string());
+        request.url = Some(format!("{}/prod/items", addr));
+        request.query_params = Some(vec![("x".to_string(), "1".to_string())]);
+        request.headers = Some(vec![("X-Trace".to_string(), "t".to_string())]);
+        request.body = Some("{}".to_string());
+        request.auth = Some(AuthData::AwsSigV4 {
+            access_key: "AKID".to_string(),
+            secret_key: "secret".to_string(),
+            session_token: "session".to_string(),
+            region: "eu-west-1".to_string(),
+            service: "execute-api".to_string(),
+        });
+
+        let response = execute(&request, &ClientOptions::default()).unwrap();
+        let signing = response.signing.unwrap();
+        let lines: Vec<&str> = signing.canonical_request.lines().collect();
+        assert_eq!(lines[..3], ["POST", "/prod/items", "x=1"]);
+        assert!(lines.contains(&format!("host:{}", addr).as_str()));
+        assert!(lines.contains(&"x-trace:t"));
+        assert_eq!(
+            lines.last(),
+            Some(&"44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a")
+        );
+
+        let raw = server.join().unwrap();
+        let authorization = raw
+            .lines()
+            .find_map(|line| line.strip_prefix("authorization: "))
+            .unwrap();
+        assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKID/"));
+        assert!(authorization.contains("/eu-west-1/execute-api/aws4_request"));
+        assert!(
+            authorization.contains("SignedHeaders=host;x-amz-date;x-amz-security-token;x-trace,")
+        );
+        assert!(raw.contains("x-amz-security-token: session"));
+    }
+
+    #[test]
+    fn test_jwt_and_hmac_sign_final_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let n = stream.read(&mut buf).unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_string());
+                stream
+                    .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                    .unwrap();
+            }
+            requests
+        });
+        let header = |raw: &str, name: &str| {
+            raw.lines()
+                .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+                .unwrap()
+                .to_string()
+        };
+
+        let mut request = RequestData::new("signed".to_string());
+        request.url = Some(format!("{}/jobs", addr));
+        request.auth = Some(AuthData::Jwt {
+            algorithm: JwtAlgorithm::Hs256,
+            key: "k".to_string(),
+            claims: r#"{"sub":"svc"}"#.to_string(),
+            expires_in: "60".to_string(),
+        });
+        execute(&request, &ClientOptions::default()).unwrap();
+
+        request.method = Some("POST".to_string());
+        request.query_params = Some(vec![("x".to_string(), "1".to_string())]);
+        request.body = Some("payload".to_string());
+        request.auth = Some(AuthData::Hmac {
+            algorithm: HmacAlgorithm::Sha256,
+            secret: "k".to_string(),
+            header: "X-Signature".to_string(),
+            template: "{method} {path} {timestamp} {body}".to_string(),
+            encoding: SignatureEncoding::Hex,
+            timestamp_header: "X-Timestamp".to_string(),
+        });
+        execute(&request, &ClientOptions::default()).unwrap();
+
+        let requests = server.join().unwrap();
+        let token = header(&requests[0], "authorization");
+        assert!(token.starts_with("Bearer eyJ"));
+        assert_eq!(token.matches('.').count(), 2);
+
+        let timestamp = header(&requests[1], "x-timestamp");
+        let message = format!("POST /jobs?x=1 {} payload", timestamp);
+        assert_eq!(
+            header(&requests[1], "x-signature"),
+            signing::hmac_signature(HmacAlgorithm::Sha256, SignatureEncoding::Hex, "k", &message)
+        );
+    }
+
+    #[test]
+    #[ignore = "waits out reqwest's 30s default timeout"]
+    fn test_no_timeout_by_default() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Longer than reqwest's own default timeout.
+        let server = respond_once(listener, Duration::from_secs(31));
+
+        let mut request = RequestData::new("slow".to_string());
+        request.url = Some(addr.to_string());
+        let response = execute(&request, &ClientOptions::default()).unwrap();
+        assert_eq!(response.status_code, Some(200));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_project_timeout_applies() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = respond_once(listener, Duration::from_millis(500));
+
+        let mut request = RequestData::new("slow".to_string());
+        request.url = Some(addr.to_string());
+        let options = ClientOptions {
+            timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let error = execute(&request, &options).unwrap_err();
+        let error = error.downcast::<reqwest::Error>().unwrap();
+        assert!(error.is_timeout(), "{}", error);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_protocol_choice() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = respond_h2c_once(listener);
+
+        let mut request = RequestData::new("h2c".to_string());
+        request.url = Some(format!("http://{}/items", addr));
+        request.protocol = HttpProtocol::H2c;
+        let response = execute(&request, &ClientOptions::default()).unwrap();
+        assert_eq!(server.join().unwrap(), "GET /items");
+        assert_eq!(response.http_version.as_deref(), Some("HTTP/2"));
+        assert_eq!(response.response_body.as_deref(), Some("ok"));
+
+        // Without TLS to negotiate over, HTTP/1.1 is used by default...
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        request.url = Some(format!("http://{}/items", listener.local_addr().unwrap()));
+        let server = respond_once(listener, Duration::ZERO);
+        request.protocol = HttpProtocol::Auto;
+        let response = execute(&request, &ClientOptions::default()).unwrap();
+        assert!(server.join().unwrap().starts_with("GET /items HTTP/1.1"));
+        assert_eq!(response.http_version.as_deref(), Some("HTTP/1.1"));
+
+        // ...and HTTP/2 has to be asked for by the scheme that fits.
+        request.protocol = HttpProtocol::Http2;
+        let error = execute(&request, &ClientOptions::default()).unwrap_err();
+        assert!(error.to_string().contains("use h2c"), "{}", error);
+        request.protocol = HttpProtocol::H2c;
+        request.url = Some("https://localhost/items".to_string());
+        let error = execute(&request, &ClientOptions::default()).unwrap_err();
+        assert!(error.to_string().contains("use HTTP/2"), "{}", error);
+    }
+
+    #[test]
+    fn test_execute_against_local_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            let raw = String::from_utf8_lossy(&buf[..n]).to_string();
+            stream
+                .write_all(
+                    b"HTTP/1.1 201 Created\r\nX-Test: yes\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+                )
+                .unwrap();
+            raw
+        });
+
+        let mut request = RequestData::new("create".to_string());
+        request.method = Some("post".to_string());
+        request.url = Some(format!("{}/items/:id", addr));
+        request.path_params = Some(vec![("id".to_string(), "7".to_string())]);
+        request.query_params = Some(vec![("q".to_string(), "x".to_string())]);
+        request.headers = Some(vec![("X-Custom".to_string(), "1".to_string())]);
+        request.auth = Some(AuthData::Bearer {
+            token: "secret".to_string(),
+        });
+        request.body = Some("{}".to_string());
+
+        let response = execute(&request, &ClientOptions::default()).unwrap();
+        let raw = server.join().unwrap().to_lowercase();
+
+        assert!(raw.starts_with("post /items/7?q=x http/1.1"));
+        assert!(raw.contains("x-custom: 1"));
+        assert!(raw.contains("authorization: bearer secret"));
+        assert_eq!(response.status_code, Some(201));
+        assert_eq!(response.response_body.as_deref(), Some("hello"));
+        assert!(response
+            .response_headers
+            .unwrap()
+            .contains(&("x-test".to_string(), "yes".to_string())));
+    }
+}
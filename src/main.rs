@@ -2,24 +2,119 @@ use crossterm::event;
 use std::{error::Error, time::Duration};
 
 mod app;
+mod auth;
+mod capture;
 mod components;
 mod config;
+mod digest;
+mod http;
+mod oauth;
 mod persistence;
+mod signing;
+mod sigv4;
 mod theme;
+mod tls;
 mod tui;
+mod variables;
 
 use app::{App, AppAction};
-use config::Config;
-use persistence::Storage;
+use config::{Config, StorageBackend};
+use persistence::{FileStorage, SecretStorage, SqliteStorage, Storage, StorageError, Vault};
 use tui::Tui;
 
+/// Read when no key file is configured.
+const PASSPHRASE_VAR: &str = "RURL_PASSPHRASE";
+
+/// Opens the configured backend, with secrets encrypted on the way in.
+fn open_storage(config: &Config) -> Result<Box<dyn Storage>, StorageError> {
+    let vault = match config.secrets_key_file() {
+        Some(path) => Some(Vault::from_key_file(&path)?),
+        None => std::env::var(PASSPHRASE_VAR)
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty())
+            .map(|passphrase| Vault::from_passphrase(&passphrase)),
+    };
+    Ok(Box::new(SecretStorage::new(open_backend(config)?, vault)))
+}
+
+fn open_backend(config: &Config) -> Result<Box<dyn Storage>, StorageError> {
+    match config.storage_backend() {
+        StorageBackend::Files => {
+            let dir = config
+                .collection_directory()
+                .unwrap_or_else(|| FileStorage::DEFAULT_DIR.into());
+            Ok(Box::new(FileStorage::open(dir)?))
+        }
+        StorageBackend::Sqlite => match config.database_path() {
+            Some(path) if path.as_os_str() == ":memory:" => {
+                Ok(Box::new(SqliteStorage::in_memory()?))
+            }
+            Some(path) => Ok(Box::new(SqliteStorage::open(path)?)),
+            None => Ok(Box::new(SqliteStorage::open(
+                SqliteStorage::default_path()?
+            )?)),
+        },
+    }
+}
+
+/// `rurl export <project> [--include-secrets]` prints a project, found by
+/// name or id, as JSON. Secret values are left blank unless asked for.
+fn export(config: &Config, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let include_secrets = args.iter().any(|arg| arg == "--include-secrets");
+    let Some(wanted) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        return Err("usage: rurl export <project> [--include-secrets]".into());
+    };
+
+    let mut storage = open_storage(config)?;
+    let project = storage
+        .list_projects()?
+        .into_iter()
+        .find(|p| &p.name == wanted || &p.id == wanted)
+        .and_then(|p| storage.load_project(&p.id).transpose())
+        .transpose()?
+        .ok_or_else(|| format!("no project named {}", wanted))?;
+
+    let project = if include_secrets {
+        if persistence::has_locked_secrets(&project) {
+            return Err(format!(
+                "secrets could not be decrypted; set {} or secrets.key_file",
+                PASSPHRASE_VAR
+            )
+            .into());
+        }
+        project
+    } else {
+        persistence::without_secrets(&project)
+    };
+    println!("{}", serde_json::to_string_pretty(&project)?);
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let config = Config::load();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("export") {
+        if let Err(e) = export(&config, &args[1..]) {
+            eprintln!("rurl: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let theme = config.create_theme();
 
+    // Open storage before taking over the terminal so errors stay readable.
+    let mut app =
+        match open_storage(&config).and_then(|storage| App::new(storage, theme, config.tls())) {
+            Ok(app) => app,
+            Err(e) => {
+                eprintln!("rurl: {}", e);
+                std::process::exit(1);
+            }
+        };
+
     let mut tui = Tui::new()?;
-    let storage = Storage::new();
-    let mut app = App::new(storage, theme);
 
     tui.terminal.draw(|f| app.render(f))?;
 
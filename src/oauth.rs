@@ -0,0 +1,632 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::http::{ClientOptions, HttpResult};
+use crate::persistence::{AuthData, HttpProtocol, OAuthGrant, OAuthToken, RequestData};
+
+/// Tokens this close to expiring are refreshed first, so they do not run out
+/// while the request is in flight.
+const EXPIRY_MARGIN: i64 = 30;
+
+/// How long the loopback listener waits for the browser to come back.
+const AUTHORIZE_TIMEOUT: Duration = Duration::from_secs(300);
+
+const CALLBACK_PATH: &str = "/callback";
+
+/// The key a token for `auth` is cached under. Requests with the same server,
+/// client, scope and user share a token.
+pub fn cache_key(auth: &AuthData) -> Option<String> {
+    match auth {
+        AuthData::OAuth2 {
+            grant,
+            token_url,
+            client_id,
+            scope,
+            username,
+            ..
+        } => {
+            let user = match grant {
+                OAuthGrant::Password => username.as_str(),
+                _ => "",
+            };
+            Some(format!(
+                "{:?} {} {} {} {}",
+                grant, token_url, client_id, scope, user
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+fn is_fresh(token: &OAuthToken) -> bool {
+    token
+        .expires_at
+        .is_none_or(|expires_at| expires_at - EXPIRY_MARGIN > now())
+}
+
+/// A token for `auth`: `cached` if it is still fresh, otherwise refreshed or
+/// newly requested. The flag is set when the token is new and should be
+/// cached. The token endpoint is reached with the same `options` as the
+/// request. `browse` is called with the sign in URL for the authorization
+/// code grant.
+pub fn authorize(
+    auth: &AuthData,
+    cached: Option<OAuthToken>,
+    options: &ClientOptions,
+    browse: impl Fn(&str),
+) -> HttpResult<(OAuthToken, bool)> {
+    let AuthData::OAuth2 {
+        grant,
+        auth_url,
+        token_url,
+        client_id,
+        client_secret,
+        scope,
+        username,
+        password,
+    } = auth
+    else {
+        return Err("not an OAuth 2.0 auth".into());
+    };
+    let client = TokenClient {
+        client: options.client(HttpProtocol::Auto)?.0,
+        token_url,
+        client_id,
+        client_secret,
+    };
+
+    if let Some(token) = cached {
+        if is_fresh(&token) {
+            return Ok((token, false));
+        }
+        // A refresh that fails falls back to the grant, which gets a new
+        // refresh token too.
+        if let Some(refresh_token) = token.refresh_token {
+            let refreshed = client.request(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &refresh_token),
+            ]);
+            if let Ok(mut token) = refreshed {
+                token.refresh_token.get_or_insert(refresh_token);
+                return Ok((token, true));
+            }
+        }
+    }
+
+    let scope = (!scope.is_empty()).then_some(("scope", scope.as_str()));
+    let token = match grant {
+        OAuthGrant::ClientCredentials => {
+            let form: Vec<_> = [("grant_type", "client_credentials")]
+                .into_iter()
+                .chain(scope)
+                .collect();
+            client.request(&form)?
+        }
+        OAuthGrant::Password => {
+            let form: Vec<_> = [
+                ("grant_type", "password"),
+                ("username", username.as_str()),
+                ("password", password.as_str()),
+            ]
+            .into_iter()
+            .chain(scope)
+            .collect();
+            client.request(&form)?
+        }
+        OAuthGrant::AuthorizationCode => authorization_code(&client, auth_url, scope, browse)?,
+    };
+    Ok((token, true))
+}
+
+/// A copy of `request` that sends `token` as a bearer token in place of its
+/// OAuth2 settings.
+pub fn with_token(request: &RequestData, token: &OAuthToken) -> RequestData {
+    RequestData {
+        auth: Some(AuthData::Bearer {
+            token: token.access_token.clone(),
+        }),
+        ..request.clone()
+    }
+}
+
+struct TokenClient<'a> {
+    client: Client,
+    token_url: &'a str,
+    client_id: &'a str,
+    client_secret: &'a str,
+}
+
+impl TokenClient<'_> {
+    /// Posts `form` to the token endpoint. Confidential clients authenticate
+    /// with HTTP Basic; public ones only send their id.
+    fn request(&self, form: &[(&str, &str)]) -> HttpResult<OAuthToken> {
+        let mut form = form.to_vec();
+        let mut builder = self
+            .client
+            .post(self.token_url)
+            .header("Accept", "application/json");
+        if self.client_secret.is_empty() {
+            form.push(("client_id", self.client_id));
+        } else {
+            builder = builder.basic_auth(self.client_id, Some(self.client_secret));
+        }
+        let response = builder.form(&form).send()?;
+        let status = response.status();
+        parse_token(status.is_success(), &response.text()?)
+    }
+}
+
+fn parse_token(success: bool, body: &str) -> HttpResult<OAuthToken> {
+    let json: Value = serde_json::from_str(body)
+        .map_err(|_| format!("token endpoint did not return JSON: {}", body))?;
+    if let Some(error) = json["error"].as_str() {
+        return Err(match json["error_description"].as_str() {
+            Some(description) => format!("token endpoint returned {}: {}", error, description),
+            None => format!("token endpoint returned {}", error),
+        }
+        .into());
+    }
+    let access_token = json["access_token"]
+        .as_str()
+        .filter(|_| success)
+        .ok_or_else(|| format!("token endpoint returned no access token: {}", body))?;
+    // Some servers send `expires_in` as a string.
+    let expires_in = json["expires_in"]
+        .as_i64()
+        .or_else(|| json["expires_in"].as_str()?.parse().ok());
+    Ok(OAuthToken {
+        access_token: access_token.to_string(),
+        refresh_token: json["refresh_token"].as_str().map(str::to_string),
+        expires_at: expires_in.map(|seconds| now() + seconds),
+    })
+}
+
+/// Sends the user to `auth_url` and exchanges the code the browser brings
+/// back to a loopback listener, using PKCE so no secret is needed.
+fn authorization_code(
+    client: &TokenClient,
+    auth_url: &str,
+    scope: Option<(&str, &str)>,
+    browse: impl Fn(&str),
+) -> HttpResult<OAuthToken> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let redirect_uri = format!(
+        "http://127.0.0.1:{}{}",
+        listener.local_addr()?.port(),
+        CALLBACK_PATH
+    );
+    let verifier = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let state = Uuid::new_v4().simple().to_string();
+
+    let params = [
+        ("response_type", "code"),
+        ("client_id", client.client_id),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("code_challenge", challenge.as_str()),
+        ("code_challenge_method", "S256"),
+        ("state", state.as_str()),
+    ];
+    let url = Url::parse_with_params(auth_url, params.into_iter().chain(scope))?;
+    browse(url.as_str());
+
+    let code = receive_code(&listener, &state)?;
+    client.request(&[
+        ("grant_type", "authorization_code"),
+        ("code", &code),
+        ("redirect_uri", &redirect_uri),
+        ("code_verifier", &verifier),
+    ])
+}
+
+/// Waits for the redirect to `listener` and returns its code.
+fn receive_code(listener: &TcpListener, state: &str) -> HttpResult<String> {
+    listener.set_nonblocking(true)?;
+    let deadline = Instant::now() + AUTHORIZE_TIMEOUT;
+    loop {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                if Instant::now() > deadline {
+                    return Err("timed out waiting for the browser to sign in".into());
+                }
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        stream.set_nonblocking(false)?;
+
+        // Browsers also ask for things like a favicon.
+        let target = read_request_target(&mut stream)?;
+        let url = Url::parse(&format!("http://127.0.0.1{}", target))?;
+        if url.path() != CALLBACK_PATH {
+            respond(&mut stream, "404 Not Found", "Not found")?;
+            continue;
+        }
+
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        // Any page the browser visits can reach the listener, so only the
+        // redirect carrying our state may end the sign in.
+        if param("state").as_deref() != Some(state) {
+            respond(
+                &mut stream,
+                "400 Bad Request",
+                "This is not the sign in rurl is waiting for.",
+            )?;
+            continue;
+        }
+        let result = match param("error") {
+            Some(error) => Err(format!("authorization failed: {}", error)),
+            None => param("code")
+                .ok_or_else(|| "authorization failed: no code was returned".to_string()),
+        };
+        let message = match &result {
+            Ok(_) => "Signed in. You can close this tab and return to rurl.",
+            Err(error) => error.as_str(),
+        };
+        respond(&mut stream, "200 OK", message)?;
+        return Ok(result?);
+    }
+}
+
+/// The target of the request line, such as `/callback?code=...`.
+fn read_request_target(stream: &mut TcpStream) -> HttpResult<String> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < 16 * 1024 {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    head.split_whitespace()
+        .nth(1)
+        .map(str::to_string)
+        .ok_or_else(|| "malformed request to the loopback listener".into())
+}
+
+fn respond(stream: &mut TcpStream, status: &str, message: &str) -> std::io::Result<()> {
+    let body = format!("<!doctype html><p>{}</p>", escape_html(message));
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+
+    /// A stand-in authorization server. Answers `count` token requests with
+    /// whatever `respond` returns for the posted form and the Authorization
+    /// header, then hands back every form it saw.
+    fn token_server(
+        count: usize,
+        respond: impl Fn(&HashMap<String, String>, Option<&str>) -> (u16, String) + Send + 'static,
+    ) -> (String, thread::JoinHandle<Vec<HashMap<String, String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut forms = Vec::new();
+            for _ in 0..count {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                let (head, body) = loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&raw).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| {
+                                let (name, value) = line.split_once(':')?;
+                                name.eq_ignore_ascii_case("content-length")
+                                    .then(|| value.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break (head.to_string(), body.to_string());
+                        }
+                    }
+                };
+                let authorization = head.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("authorization")
+                        .then(|| value.trim().to_string())
+                });
+                let form: HashMap<String, String> = Url::parse(&format!("http://x/?{}", body))
+                    .unwrap()
+                    .query_pairs()
+                    .into_owned()
+                    .collect();
+                let (status, body) = respond(&form, authorization.as_deref());
+                write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+                forms.push(form);
+            }
+            forms
+        });
+        (url, server)
+    }
+
+    fn oauth(grant: OAuthGrant, token_url: &str) -> AuthData {
+        AuthData::OAuth2 {
+            grant,
+            auth_url: String::new(),
+            token_url: token_url.to_string(),
+            client_id: "rurl".to_string(),
+            client_secret: "shh".to_string(),
+            scope: "read".to_string(),
+            username: "me".to_string(),
+            password: "hunter2".to_string(),
+        }
+    }
+
+    fn never_browse(url: &str) {
+        panic!("unexpected browse to {}", url);
+    }
+
+    #[test]
+    fn test_client_credentials_and_cache() {
+        let (url, server) = token_server(1, |_, authorization| {
+            // rurl:shh
+            assert_eq!(authorization, Some("Basic cnVybDpzaGg="));
+            (
+                200,
+                r#"{"access_token": "a1", "token_type": "bearer", "expires_in": 3600}"#.to_string(),
+            )
+        });
+        let auth = oauth(OAuthGrant::ClientCredentials, &url);
+
+        let (token, issued) =
+            authorize(&auth, None, &ClientOptions::default(), never_browse).unwrap();
+        assert!(issued);
+        assert_eq!(token.access_token, "a1");
+        assert!(token.expires_at.unwrap() > now() + 3500);
+
+        // A fresh cached token is used without asking the server again.
+        let (cached, issued) = authorize(
+            &auth,
+            Some(token.clone()),
+            &ClientOptions::default(),
+            never_browse,
+        )
+        .unwrap();
+        assert!(!issued);
+        assert_eq!(cached, token);
+
+        let forms = server.join().unwrap();
+        assert_eq!(forms[0]["grant_type"], "client_credentials");
+        assert_eq!(forms[0]["scope"], "read");
+        assert!(!forms[0].contains_key("client_id"));
+    }
+
+    #[test]
+    fn test_password_grant_and_errors() {
+        let (url, server) = token_server(2, |form, _| match form["password"].as_str() {
+            "hunter2" => (200, r#"{"access_token": "a1"}"#.to_string()),
+            _ => (
+                400,
+                r#"{"error": "invalid_grant", "error_description": "bad password"}"#.to_string(),
+            ),
+        });
+        let auth = oauth(OAuthGrant::Password, &url);
+        let (token, _) = authorize(&auth, None, &ClientOptions::default(), never_browse).unwrap();
+        assert_eq!(token.access_token, "a1");
+        assert_eq!(token.expires_at, None);
+
+        let mut wrong = auth.clone();
+        if let AuthData::OAuth2 { password, .. } = &mut wrong {
+            *password = "wrong".to_string();
+        }
+        let error = authorize(&wrong, None, &ClientOptions::default(), never_browse).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "token endpoint returned invalid_grant: bad password"
+        );
+
+        let forms = server.join().unwrap();
+        assert_eq!(forms[0]["grant_type"], "password");
+        assert_eq!(forms[0]["username"], "me");
+    }
+
+    #[test]
+    fn test_expired_token_is_refreshed() {
+        let (url, server) = token_server(1, |_, _| {
+            (
+                200,
+                r#"{"access_token": "a2", "expires_in": "60"}"#.to_string(),
+            )
+        });
+        let expired = OAuthToken {
+            access_token: "a1".to_string(),
+            refresh_token: Some("r1".to_string()),
+            expires_at: Some(now() + 5),
+        };
+        let auth = oauth(OAuthGrant::ClientCredentials, &url);
+        let (token, issued) = authorize(
+            &auth,
+            Some(expired),
+            &ClientOptions::default(),
+            never_browse,
+        )
+        .unwrap();
+        assert!(issued);
+        assert_eq!(token.access_token, "a2");
+        // The server did not rotate it, so the old refresh token is kept.
+        assert_eq!(token.refresh_token.as_deref(), Some("r1"));
+
+        let forms = server.join().unwrap();
+        assert_eq!(forms[0]["grant_type"], "refresh_token");
+        assert_eq!(forms[0]["refresh_token"], "r1");
+    }
+
+    #[test]
+    fn test_authorization_code_with_pkce() {
+        let (challenge_sender, challenge_receiver) = mpsc::channel::<String>();
+        let (url, server) = token_server(1, move |form, _| {
+            let challenge = challenge_receiver.recv().unwrap();
+            let verified = URL_SAFE_NO_PAD.encode(Sha256::digest(form["code_verifier"].as_bytes()));
+            assert_eq!(verified, challenge);
+            assert_eq!(form["code"], "c0de");
+            (
+                200,
+                r#"{"access_token": "a1", "refresh_token": "r1"}"#.to_string(),
+            )
+        });
+        let mut auth = oauth(OAuthGrant::AuthorizationCode, &url);
+        if let AuthData::OAuth2 {
+            auth_url,
+            client_secret,
+            ..
+        } = &mut auth
+        {
+            *auth_url = "https://auth.example.com/authorize?audience=api".to_string();
+            client_secret.clear();
+        }
+
+        // Plays the browser: signs in and follows the redirect.
+        let browse = move |url: &str| {
+            let url = Url::parse(url).unwrap();
+            let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+            assert_eq!(params["audience"], "api");
+            assert_eq!(params["code_challenge_method"], "S256");
+            challenge_sender
+                .send(params["code_challenge"].clone())
+                .unwrap();
+            // Another page reaching the listener first is turned away
+            // without ending the sign in or echoing what it sent.
+            let forged = format!(
+                "{}?error=%3Cscript%3Ealert(1)%3C/script%3E",
+                params["redirect_uri"]
+            );
+            let redirect = format!(
+                "{}?code=c0de&state={}",
+                params["redirect_uri"], params["state"]
+            );
+            thread::spawn(move || {
+                let response = reqwest::blocking::get(forged).unwrap();
+                assert_eq!(response.status(), 400);
+                assert!(!response.text().unwrap().contains("<script>"));
+                let page = reqwest::blocking::get(redirect).unwrap().text().unwrap();
+                assert!(page.contains("Signed in"));
+            });
+        };
+
+        let (token, issued) = authorize(&auth, None, &ClientOptions::default(), browse).unwrap();
+        assert!(issued);
+        assert_eq!(token.refresh_token.as_deref(), Some("r1"));
+
+        let forms = server.join().unwrap();
+        assert_eq!(forms[0]["grant_type"], "authorization_code");
+        assert_eq!(forms[0]["client_id"], "rurl");
+        assert!(forms[0]["redirect_uri"].starts_with("http://127.0.0.1:"));
+    }
+
+    #[test]
+    fn test_token_request_uses_client_options() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (_stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_millis(500));
+        });
+
+        let auth = oauth(OAuthGrant::ClientCredentials, &url);
+        let options = ClientOptions {
+            timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let error = authorize(&auth, None, &options, never_browse).unwrap_err();
+        let error = error.downcast::<reqwest::Error>().unwrap();
+        assert!(error.is_timeout(), "{}", error);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("authorization failed: <script>alert('x' & \"y\")</script>"),
+            "authorization failed: &lt;script&gt;alert(&#39;x&#39; &amp; &quot;y&quot;)&lt;/script&gt;"
+        );
+    }
+
+    #[test]
+    fn test_cache_key_and_with_token() {
+        let auth = oauth(
+            OAuthGrant::ClientCredentials,
+            "https://auth.example.com/token",
+        );
+        let mut other_user = auth.clone();
+        if let AuthData::OAuth2 { username, .. } = &mut other_user {
+            *username = "you".to_string();
+        }
+        // The username only matters to the password grant.
+        assert_eq!(cache_key(&auth), cache_key(&other_user));
+        assert_eq!(cache_key(&AuthData::None), None);
+
+        let mut request = RequestData::new("me".to_string());
+        request.auth = Some(auth);
+        let token = OAuthToken {
+            access_token: "a1".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+        assert_eq!(
+            with_token(&request, &token).auth,
+            Some(AuthData::Bearer {
+                token: "a1".to_string()
+            })
+        );
+    }
+}
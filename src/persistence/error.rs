@@ -0,0 +1,76 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use super::migrations::MigrationError;
+
+#[derive(Debug)]
+pub enum StorageError {
+    /// No per-user data directory could be determined for the default database.
+    NoDataDir,
+    CreateDir(PathBuf, std::io::Error),
+    Open(PathBuf, rusqlite::Error),
+    Migration(MigrationError),
+    Sqlite(rusqlite::Error),
+    Serialization(serde_json::Error),
+    Io(PathBuf, std::io::Error),
+    /// A collection file could not be parsed, usually after a hand edit.
+    Parse(PathBuf, toml::de::Error),
+    Format(toml::ser::Error),
+    /// A secret needs encrypting but no passphrase or key file was given.
+    SecretsLocked,
+    /// A secret could not be decrypted with the key that was given.
+    Decrypt,
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::NoDataDir => write!(f, "could not determine a data directory"),
+            StorageError::CreateDir(path, e) => {
+                write!(f, "could not create {}: {}", path.display(), e)
+            }
+            StorageError::Open(path, e) => {
+                write!(f, "could not open database {}: {}", path.display(), e)
+            }
+            StorageError::Migration(e) => write!(f, "could not migrate database: {}", e),
+            StorageError::Sqlite(e) => write!(f, "database error: {}", e),
+            StorageError::Serialization(e) => write!(f, "could not serialize data: {}", e),
+            StorageError::Io(path, e) => write!(f, "could not access {}: {}", path.display(), e),
+            StorageError::Parse(path, e) => {
+                write!(f, "could not parse {}: {}", path.display(), e)
+            }
+            StorageError::Format(e) => write!(f, "could not format data: {}", e),
+            StorageError::SecretsLocked => write!(
+                f,
+                "secrets need a passphrase (RURL_PASSPHRASE) or a key file (secrets.key_file) to be saved"
+            ),
+            StorageError::Decrypt => write!(f, "could not decrypt a secret; is the key right?"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<MigrationError> for StorageError {
+    fn from(e: MigrationError) -> Self {
+        StorageError::Migration(e)
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Serialization(e)
+    }
+}
+
+impl From<toml::ser::Error> for StorageError {
+    fn from(e: toml::ser::Error) -> Self {
+        StorageError::Format(e)
+    }
+}
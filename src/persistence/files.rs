@@ -0,0 +1,898 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::{
+    Environment, Folder, GlobalVariables, OAuthToken, ProjectData, ProjectSettings, RequestData,
+    ResponseData, Storage, StorageError, TlsSettings,
+};
+
+const PROJECT_FILE: &str = "project.toml";
+const REQUESTS_DIR: &str = "requests";
+const ENVIRONMENTS_DIR: &str = "environments";
+const HISTORY_DIR: &str = ".history";
+const GLOBALS_FILE: &str = ".globals.toml";
+const TOKENS_FILE: &str = ".tokens.toml";
+
+/// Keeps each project as a directory of TOML files under `root`:
+///
+/// ```text
+/// <root>/.gitignore                      ignores .history/, .globals.toml and .tokens.toml
+/// <root>/.globals.toml                   global variables
+/// <root>/.tokens.toml                    cached OAuth2 tokens
+/// <root>/.history/<request id>.jsonl     response history
+/// <root>/<project>/project.toml
+/// <root>/<project>/requests/<name>-<short id>.toml
+/// <root>/<project>/environments/<name>.toml
+/// ```
+///
+/// Files are named after what they hold and only rewritten when their
+/// contents change, so an edit shows up as a small diff in code review.
+pub struct FileStorage {
+    root: PathBuf,
+    /// Ids of every stored request, read on first use so that saving a
+    /// response does not parse every request file.
+    request_ids: Option<HashSet<String>>,
+}
+
+/// `project.toml` leaves out `updated_at`, which would change on every edit;
+/// it is derived from the requests on load instead.
+#[derive(Serialize, Deserialize)]
+struct ProjectFile {
+    id: String,
+    name: String,
+    created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active_environment: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    secrets: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    variables: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    folders: Vec<Folder>,
+    #[serde(default, skip_serializing_if = "ProjectSettings::is_default")]
+    settings: ProjectSettings,
+}
+
+/// Variables are written in key order so the same environment always
+/// produces the same file.
+#[derive(Serialize, Deserialize)]
+struct EnvironmentFile {
+    name: String,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    secrets: BTreeSet<String>,
+    variables: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "TlsSettings::is_default")]
+    tls: TlsSettings,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GlobalsFile {
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    secrets: BTreeSet<String>,
+    variables: BTreeMap<String, String>,
+}
+
+impl FileStorage {
+    /// The directory used when none is configured, relative to the workspace.
+    pub const DEFAULT_DIR: &'static str = "rurl";
+
+    /// Opens (creating if needed) the collection directory at `root`.
+    pub fn open(root: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root).map_err(|e| StorageError::CreateDir(root.clone(), e))?;
+
+        let gitignore = root.join(".gitignore");
+        if !gitignore.exists() {
+            // Global variables tend to be personal, like a user id, so they
+            // stay out of the repository with the history and tokens.
+            write_file(
+                &gitignore,
+                &format!("{}/\n{}\n{}\n", HISTORY_DIR, GLOBALS_FILE, TOKENS_FILE),
+            )?;
+        }
+
+        Ok(Self {
+            root,
+            request_ids: None,
+        })
+    }
+
+    /// Every project directory with its `project.toml`, ordered by name.
+    fn projects(&self) -> Result<Vec<(PathBuf, ProjectFile)>, StorageError> {
+        let mut projects = Vec::new();
+        for dir in list_dir(&self.root)? {
+            let path = dir.join(PROJECT_FILE);
+            if path.is_file() {
+                projects.push((dir, read_toml::<ProjectFile>(&path)?));
+            }
+        }
+        projects.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+        Ok(projects)
+    }
+
+    fn project_dir(&self, id: &str) -> Result<Option<PathBuf>, StorageError> {
+        Ok(self
+            .projects()?
+            .into_iter()
+            .find(|(_, project)| project.id == id)
+            .map(|(dir, _)| dir))
+    }
+
+    /// Picks a directory for a project that has never been saved, named after
+    /// the project and not clashing with anything already in `root`.
+    fn new_project_dir(&self, project: &ProjectData) -> PathBuf {
+        let base = slug(&project.name, "project");
+        let mut name = base.clone();
+        let mut n = 1;
+        while self.root.join(&name).exists() {
+            n += 1;
+            name = format!("{}-{}", base, n);
+        }
+        self.root.join(name)
+    }
+
+    fn read_tokens(&self) -> Result<BTreeMap<String, OAuthToken>, StorageError> {
+        let path = self.root.join(TOKENS_FILE);
+        if !path.is_file() {
+            return Ok(BTreeMap::new());
+        }
+        read_toml(&path)
+    }
+
+    fn history_path(&self, request_uuid: &str) -> PathBuf {
+        self.root
+            .join(HISTORY_DIR)
+            .join(format!("{}.jsonl", request_uuid))
+    }
+
+    fn remove_history(&self, request_uuid: &str) -> Result<(), StorageError> {
+        remove_file(&self.history_path(request_uuid))
+    }
+
+    fn request_ids(&mut self) -> Result<&mut HashSet<String>, StorageError> {
+        let ids = match self.request_ids.take() {
+            Some(ids) => ids,
+            None => {
+                let mut ids = HashSet::new();
+                for (dir, _) in self.projects()? {
+                    ids.extend(read_requests(&dir)?.into_iter().map(|r| r.id));
+                }
+                ids
+            }
+        };
+        Ok(self.request_ids.insert(ids))
+    }
+}
+
+impl Storage for FileStorage {
+    /// Writes every file of the project, skipping those whose contents are
+    /// unchanged, and removes files for requests and environments that are
+    /// gone.
+    fn save_project(&mut self, project: &ProjectData) -> Result<(), StorageError> {
+        let dir = match self.project_dir(&project.id)? {
+            Some(dir) => dir,
+            None => self.new_project_dir(project),
+        };
+
+        write_file(
+            &dir.join(PROJECT_FILE),
+            &to_toml(&ProjectFile {
+                id: project.id.clone(),
+                name: project.name.clone(),
+                created_at: project.created_at,
+                active_environment: project.active_environment.clone(),
+                secrets: project.secrets.clone(),
+                variables: project.variables.clone().into_iter().collect(),
+                folders: project.folders.clone(),
+                settings: project.settings.clone(),
+            })?,
+        )?;
+
+        let stored = read_requests(&dir)?;
+        let files = project
+            .requests
+            .iter()
+            .map(|request| Ok((request_file_name(request), to_toml(request)?)))
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        sync_dir(&dir.join(REQUESTS_DIR), &files)?;
+        for request in stored {
+            if !project.requests.iter().any(|r| r.id == request.id) {
+                self.remove_history(&request.id)?;
+                if let Some(ids) = &mut self.request_ids {
+                    ids.remove(&request.id);
+                }
+            }
+        }
+        if let Some(ids) = &mut self.request_ids {
+            ids.extend(project.requests.iter().map(|r| r.id.clone()));
+        }
+
+        let names = file_names(
+            project.environments.iter().map(|e| e.name.as_str()),
+            "environment",
+        );
+        let files = names
+            .into_iter()
+            .zip(&project.environments)
+            .map(|(name, env)| {
+                let file = EnvironmentFile {
+                    name: env.name.clone(),
+                    secrets: env.secrets.clone(),
+                    variables: env.variables.clone().into_iter().collect(),
+                    tls: env.tls.clone(),
+                };
+                Ok((name, to_toml(&file)?))
+            })
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        sync_dir(&dir.join(ENVIRONMENTS_DIR), &files)?;
+
+        Ok(())
+    }
+
+    fn load_project(&mut self, id: &str) -> Result<Option<ProjectData>, StorageError> {
+        let dir = match self.project_dir(id)? {
+            Some(dir) => dir,
+            None => return Ok(None),
+        };
+        let file: ProjectFile = read_toml(&dir.join(PROJECT_FILE))?;
+
+        let mut requests = read_requests(&dir)?;
+        requests.sort_by(|a, b| {
+            (a.position, a.created_at, &a.name).cmp(&(b.position, b.created_at, &b.name))
+        });
+
+        let mut environments = Vec::new();
+        for path in toml_files(&dir.join(ENVIRONMENTS_DIR))? {
+            let env: EnvironmentFile = read_toml(&path)?;
+            environments.push(Environment {
+                name: env.name,
+                variables: env.variables.into_iter().collect(),
+                secrets: env.secrets,
+                tls: env.tls,
+            });
+        }
+
+        let updated_at = requests
+            .iter()
+            .map(|r| r.updated_at)
+            .fold(file.created_at, i64::max);
+
+        Ok(Some(ProjectData {
+            id: file.id,
+            name: file.name,
+            requests,
+            folders: file.folders,
+            environments,
+            active_environment: file.active_environment,
+            variables: file.variables.into_iter().collect(),
+            secrets: file.secrets,
+            settings: file.settings,
+            created_at: file.created_at,
+            updated_at,
+        }))
+    }
+
+    fn list_projects(&mut self) -> Result<Vec<ProjectData>, StorageError> {
+        Ok(self
+            .projects()?
+            .into_iter()
+            .map(|(_, file)| ProjectData {
+                id: file.id,
+                name: file.name,
+                requests: Vec::new(),
+                folders: Vec::new(),
+                environments: Vec::new(),
+                active_environment: file.active_environment,
+                variables: HashMap::new(),
+                secrets: BTreeSet::new(),
+                settings: ProjectSettings::default(),
+                created_at: file.created_at,
+                updated_at: file.created_at,
+            })
+            .collect())
+    }
+
+    fn delete_project(&mut self, id: &str) -> Result<(), StorageError> {
+        let dir = match self.project_dir(id)? {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        for request in read_requests(&dir)? {
+            self.remove_history(&request.id)?;
+            if let Some(ids) = &mut self.request_ids {
+                ids.remove(&request.id);
+            }
+        }
+        fs::remove_dir_all(&dir).map_err(|e| StorageError::Io(dir, e))
+    }
+
+    /// Appends the response to the request's history file. `request_id` is
+    /// the response's position in that file.
+    fn save_response(
+        &mut self,
+        request_uuid: &str,
+        response: &ResponseData,
+    ) -> Result<Option<ResponseData>, StorageError> {
+        if !self.request_ids()?.contains(request_uuid) {
+            return Ok(None);
+        }
+
+        let path = self.history_path(request_uuid);
+        let request_id = read_history(&path)?.len() as i64 + 1;
+        let saved = ResponseData {
+            request_id,
+            ..response.clone()
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| StorageError::CreateDir(dir.to_path_buf(), e))?;
+        }
+        let line = serde_json::to_string(&saved)?;
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| StorageError::Io(path, e))?;
+
+        Ok(Some(saved))
+    }
+
+    fn load_history(&mut self, request_uuid: &str) -> Result<Vec<ResponseData>, StorageError> {
+        let mut history = read_history(&self.history_path(request_uuid))?;
+        history.sort_by_key(|r| std::cmp::Reverse((r.timestamp, r.request_id)));
+        Ok(history)
+    }
+
+    fn load_global_variables(&mut self) -> Result<GlobalVariables, StorageError> {
+        let path = self.root.join(GLOBALS_FILE);
+        if !path.is_file() {
+            return Ok(GlobalVariables::default());
+        }
+        let file: GlobalsFile = read_toml(&path)?;
+        Ok(GlobalVariables {
+            variables: file.variables.into_iter().collect(),
+            secrets: file.secrets,
+        })
+    }
+
+    fn save_global_variables(&mut self, globals: &GlobalVariables) -> Result<(), StorageError> {
+        let file = GlobalsFile {
+            secrets: globals.secrets.clone(),
+            variables: globals.variables.clone().into_iter().collect(),
+        };
+        write_file(&self.root.join(GLOBALS_FILE), &to_toml(&file)?)
+    }
+
+    fn load_token(&mut self, key: &str) -> Result<Option<OAuthToken>, StorageError> {
+        Ok(self.read_tokens()?.remove(key))
+    }
+
+    fn save_token(&mut self, key: &str, token: &OAuthToken) -> Result<(), StorageError> {
+        let mut tokens = self.read_tokens()?;
+        tokens.insert(key.to_string(), token.clone());
+        write_file(&self.root.join(TOKENS_FILE), &to_toml(&tokens)?)
+    }
+}
+
+/// Turns a display name into a file name: lowercase ASCII letters and digits
+/// separated by single dashes.
+fn slug(name: &str, fallback: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        fallback.to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Names a request's file `<slug>-<short uuid>.toml`, so it stays the same
+/// while other requests are added, removed or reordered, even if they share
+/// its name.
+fn request_file_name(request: &RequestData) -> String {
+    let short: String = request.id.chars().take(8).collect();
+    format!("{}-{}.toml", slug(&request.name, "request"), short)
+}
+
+/// Gives every item a unique `<slug>.toml` file name, numbering repeats in
+/// order.
+fn file_names<'a>(names: impl Iterator<Item = &'a str>, fallback: &str) -> Vec<String> {
+    let mut taken: Vec<String> = Vec::new();
+    for name in names {
+        let base = slug(name, fallback);
+        let mut candidate = format!("{}.toml", base);
+        let mut n = 1;
+        while taken.contains(&candidate) {
+            n += 1;
+            candidate = format!("{}-{}.toml", base, n);
+        }
+        taken.push(candidate);
+    }
+    taken
+}
+
+/// Makes `dir` hold exactly `files`, leaving files that already match alone.
+fn sync_dir(dir: &Path, files: &[(String, String)]) -> Result<(), StorageError> {
+    for path in toml_files(dir)? {
+        let keep = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| files.iter().any(|(n, _)| n == name));
+        if !keep {
+            remove_file(&path)?;
+        }
+    }
+
+    if !files.is_empty() {
+        fs::create_dir_all(dir).map_err(|e| StorageError::CreateDir(dir.to_path_buf(), e))?;
+    }
+    for (name, contents) in files {
+        write_file(&dir.join(name), contents)?;
+    }
+    Ok(())
+}
+
+fn read_requests(project_dir: &Path) -> Result<Vec<RequestData>, StorageError> {
+    toml_files(&project_dir.join(REQUESTS_DIR))?
+        .iter()
+        .map(|path| read_toml(path))
+        .collect()
+}
+
+fn read_history(path: &Path) -> Result<Vec<ResponseData>, StorageError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(StorageError::Io(path.to_path_buf(), e)),
+    };
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+/// Entries of `dir` in name order, skipping hidden ones. A missing directory
+/// is empty.
+fn list_dir(dir: &Path) -> Result<Vec<PathBuf>, StorageError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(StorageError::Io(dir.to_path_buf(), e)),
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| StorageError::Io(dir.to_path_buf(), e))?
+            .path();
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'));
+        if !hidden {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn toml_files(dir: &Path) -> Result<Vec<PathBuf>, StorageError> {
+    Ok(list_dir(dir)?
+        .into_iter()
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+        .collect())
+}
+
+fn read_toml<T: DeserializeOwned>(path: &Path) -> Result<T, StorageError> {
+    let contents = fs::read_to_string(path).map_err(|e| StorageError::Io(path.to_path_buf(), e))?;
+    toml::from_str(&contents).map_err(|e| StorageError::Parse(path.to_path_buf(), e))
+}
+
+fn to_toml<T: Serialize>(value: &T) -> Result<String, StorageError> {
+    Ok(toml::to_string_pretty(value)?)
+}
+
+/// Writes `contents` unless the file already holds exactly that.
+fn write_file(path: &Path, contents: &str) -> Result<(), StorageError> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| StorageError::CreateDir(dir.to_path_buf(), e))?;
+    }
+    fs::write(path, contents).map_err(|e| StorageError::Io(path.to_path_buf(), e))
+}
+
+fn remove_file(path: &Path) -> Result<(), StorageError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(StorageError::Io(path.to_path_buf(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{
+        AuthData, Capture, CaptureSource, ClientCertificate, HttpProtocol, ProjectUpdate,
+        TlsVersion,
+    };
+    use std::collections::HashMap;
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!("rurl-files-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn sample_project() -> ProjectData {
+        let mut project = ProjectData::new("Billing API".to_string());
+        let mut request = RequestData::new("Create invoice".to_string());
+        request.method = Some("POST".to_string());
+        request.url = Some("https://api.example.com/invoices".to_string());
+        request.headers = Some(vec![(
+            "Content-Type".to_string(),
+            "application/json".to_string(),
+        )]);
+        request.body = Some("{\n  \"amount\": 10\n}".to_string());
+        request.auth = Some(AuthData::ApiKey {
+            key: "X-Key".to_string(),
+            value: "abc".to_string(),
+            in_header: true,
+        });
+        request.captures = vec![
+            Capture {
+                variable: "invoice_id".to_string(),
+                source: CaptureSource::JsonPath("$.id".to_string()),
+            },
+            Capture {
+                variable: "location".to_string(),
+                source: CaptureSource::Header("Location".to_string()),
+            },
+        ];
+        project.requests.push(request);
+        let drafts = Folder::new("Drafts".to_string(), None);
+        let mut list = RequestData::new("List invoices".to_string());
+        list.folder_id = Some(drafts.id.clone());
+        project.folders.push(drafts);
+        project.requests.push(list);
+        project.environments.push(Environment {
+            name: "Staging".to_string(),
+            variables: HashMap::from([
+                ("host".to_string(), "staging.example.com".to_string()),
+                ("api_key".to_string(), "abc".to_string()),
+            ]),
+            secrets: BTreeSet::from(["api_key".to_string()]),
+            tls: Default::default(),
+        });
+        project
+    }
+
+    #[test]
+    fn test_project_round_trip() {
+        let root = temp_root();
+        let mut storage = FileStorage::open(&root).unwrap();
+        let mut project = sample_project();
+        project.active_environment = Some("staging".to_string());
+        project.variables = HashMap::from([
+            ("version".to_string(), "v2".to_string()),
+            ("client_secret".to_string(), "shh".to_string()),
+        ]);
+        project.secrets = BTreeSet::from(["client_secret".to_string()]);
+        project.requests[0].variables = HashMap::from([("id".to_string(), "42".to_string())]);
+        project.requests[0].secrets = BTreeSet::from(["id".to_string()]);
+        project.requests[0].protocol = HttpProtocol::Http2;
+        project.settings.base_url = "https://api.example.com".to_string();
+        project.settings.headers = vec![("Accept".to_string(), "application/json".to_string())];
+        project.settings.timeout = Some(10);
+        project.settings.tls.min_version = Some(TlsVersion::Tls12);
+        project.environments[0].tls = TlsSettings {
+            ca_files: vec!["certs/staging-ca.pem".to_string()],
+            client_certificate: Some(ClientCertificate::Pem {
+                cert_file: "certs/client.pem".to_string(),
+                key_file: "certs/client.key".to_string(),
+            }),
+            ..Default::default()
+        };
+        storage.save_project(&project).unwrap();
+
+        let dir = root.join("billing-api");
+        let requests = dir.join(REQUESTS_DIR);
+        let created = requests.join(request_file_name(&project.requests[0]));
+        assert!(created.is_file());
+        assert!(created
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("create-invoice-"));
+        assert!(requests
+            .join(request_file_name(&project.requests[1]))
+            .is_file());
+        let request = fs::read_to_string(created).unwrap();
+        assert!(request.contains("protocol = \"http2\""));
+        let env = fs::read_to_string(dir.join("environments/staging.toml")).unwrap();
+        assert!(env.find("api_key").unwrap() < env.find("host").unwrap());
+
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.name, project.name);
+        assert_eq!(loaded.requests, project.requests);
+        assert_eq!(loaded.folders, project.folders);
+        assert_eq!(loaded.environments, project.environments);
+        assert_eq!(loaded.active_environment, project.active_environment);
+        assert_eq!(loaded.variables, project.variables);
+        assert_eq!(loaded.settings, project.settings);
+
+        let projects = storage.list_projects().unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].id, project.id);
+
+        storage.delete_project(&project.id).unwrap();
+        assert!(storage.load_project(&project.id).unwrap().is_none());
+        assert!(!dir.exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_updates_only_touch_affected_files() {
+        let root = temp_root();
+        let mut storage = FileStorage::open(&root).unwrap();
+        let mut project = sample_project();
+        storage.save_project(&project).unwrap();
+
+        let requests = root.join("billing-api").join(REQUESTS_DIR);
+        let untouched = requests.join(request_file_name(&project.requests[1]));
+        let old_name = requests.join(request_file_name(&project.requests[0]));
+        let before = fs::metadata(&untouched).unwrap().modified().unwrap();
+
+        let mut renamed = project.requests[0].clone();
+        renamed.name = "Create draft invoice".to_string();
+        let update = ProjectUpdate::UpdateRequest(renamed.clone());
+        project.apply_update(update.clone());
+        storage.apply_update(&project, &update).unwrap();
+
+        assert!(!old_name.exists());
+        assert!(requests.join(request_file_name(&renamed)).is_file());
+        assert_eq!(
+            fs::metadata(&untouched).unwrap().modified().unwrap(),
+            before
+        );
+
+        let deleted = project.requests[1].id.clone();
+        let update = ProjectUpdate::DeleteRequest(deleted);
+        project.apply_update(update.clone());
+        storage.apply_update(&project, &update).unwrap();
+        assert!(!untouched.exists());
+
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.requests.len(), 1);
+        assert_eq!(loaded.requests[0].name, "Create draft invoice");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_requests_with_the_same_name_keep_their_files() {
+        let root = temp_root();
+        let mut storage = FileStorage::open(&root).unwrap();
+        let mut project = sample_project();
+        let copy = RequestData::new(project.requests[0].name.clone());
+        project.apply_update(ProjectUpdate::AddRequest(copy.clone()));
+        storage.save_project(&project).unwrap();
+
+        let requests = root.join("billing-api").join(REQUESTS_DIR);
+        let copy_file = requests.join(request_file_name(&copy));
+        let before = fs::read_to_string(&copy_file).unwrap();
+
+        let update = ProjectUpdate::DeleteRequest(project.requests[0].id.clone());
+        project.apply_update(update.clone());
+        storage.apply_update(&project, &update).unwrap();
+
+        assert_eq!(fs::read_to_string(&copy_file).unwrap(), before);
+        assert_eq!(toml_files(&requests).unwrap().len(), 2);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_moving_a_request_only_rewrites_its_file() {
+        let root = temp_root();
+        let mut storage = FileStorage::open(&root).unwrap();
+        let mut project = ProjectData::new("Moves".to_string());
+        for name in ["one", "two", "three", "four"] {
+            project.apply_update(ProjectUpdate::AddRequest(RequestData::new(
+                name.to_string(),
+            )));
+        }
+        storage.save_project(&project).unwrap();
+
+        let dir = root.join("moves").join(REQUESTS_DIR);
+        let contents = || -> BTreeMap<PathBuf, String> {
+            toml_files(&dir)
+                .unwrap()
+                .into_iter()
+                .map(|path| (path.clone(), fs::read_to_string(path).unwrap()))
+                .collect()
+        };
+        let names = |project: &ProjectData| -> Vec<String> {
+            project.requests.iter().map(|r| r.name.clone()).collect()
+        };
+        let mut move_to = |project: &mut ProjectData, from: usize, index: usize| {
+            let update = ProjectUpdate::MoveRequest {
+                id: project.requests[from].id.clone(),
+                folder_id: None,
+                index,
+            };
+            project.apply_update(update.clone());
+            storage.apply_update(project, &update).unwrap();
+        };
+
+        let before = contents();
+        move_to(&mut project, 3, 1);
+        let after = contents();
+        let changed = before
+            .iter()
+            .filter(|(path, c)| after[*path] != **c)
+            .count();
+        assert_eq!(changed, 1);
+        assert_eq!(names(&project), ["one", "four", "two", "three"]);
+
+        // Positions with no room between them are spread out again.
+        for (i, request) in project.requests.iter_mut().enumerate() {
+            request.position = i as i64;
+        }
+        move_to(&mut project, 3, 1);
+        assert_eq!(names(&project), ["one", "three", "four", "two"]);
+        let positions: Vec<_> = project.requests.iter().map(|r| r.position).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]), "{:?}", positions);
+
+        let loaded = FileStorage::open(&root)
+            .unwrap()
+            .load_project(&project.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(names(&loaded), names(&project));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_request_history() {
+        let root = temp_root();
+        let mut storage = FileStorage::open(&root).unwrap();
+        let project = sample_project();
+        storage.save_project(&project).unwrap();
+        let request_id = project.requests[0].id.clone();
+
+        for (status_code, timestamp) in [(200, 10), (500, 20)] {
+            let response = ResponseData {
+                request_id: 0,
+                status_code: Some(status_code),
+                response_body: Some("{}".to_string()),
+                response_headers: Some(vec![("a".to_string(), "b".to_string())]),
+                response_time: 12,
+                timestamp,
+                http_version: Some("HTTP/2".to_string()),
+                variables: vec![("$timestamp".to_string(), timestamp.to_string())],
+                hops: Vec::new(),
+                signing: None,
+                certificates: Vec::new(),
+            };
+            let saved = storage
+                .save_response(&request_id, &response)
+                .unwrap()
+                .unwrap();
+            assert_ne!(saved.request_id, 0);
+        }
+        assert!(storage
+            .save_response(
+                "missing",
+                &ResponseData {
+                    request_id: 0,
+                    status_code: None,
+                    response_body: None,
+                    response_headers: None,
+                    response_time: 0,
+                    timestamp: 0,
+                    http_version: None,
+                    variables: Vec::new(),
+                    hops: Vec::new(),
+                    signing: None,
+                    certificates: Vec::new(),
+                }
+            )
+            .unwrap()
+            .is_none());
+
+        let history = storage.load_history(&request_id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].status_code, Some(500));
+        assert_eq!(history[0].http_version.as_deref(), Some("HTTP/2"));
+        assert_eq!(
+            history[0].variables,
+            vec![("$timestamp".to_string(), "20".to_string())]
+        );
+
+        let gitignore = fs::read_to_string(root.join(".gitignore")).unwrap();
+        assert!(gitignore.contains(HISTORY_DIR));
+
+        // Requests added and removed after the first response are tracked.
+        let mut project = project;
+        let added = RequestData::new("Void invoice".to_string());
+        let update = ProjectUpdate::AddRequest(added.clone());
+        project.apply_update(update.clone());
+        storage.apply_update(&project, &update).unwrap();
+        assert!(storage
+            .save_response(&added.id, &history[0])
+            .unwrap()
+            .is_some());
+
+        storage.delete_project(&project.id).unwrap();
+        assert!(storage.load_history(&request_id).unwrap().is_empty());
+        assert!(storage
+            .save_response(&request_id, &history[0])
+            .unwrap()
+            .is_none());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_global_variables() {
+        let root = temp_root();
+        let mut storage = FileStorage::open(&root).unwrap();
+        assert_eq!(
+            storage.load_global_variables().unwrap(),
+            GlobalVariables::default()
+        );
+
+        let globals = GlobalVariables {
+            variables: HashMap::from([
+                ("user".to_string(), "me".to_string()),
+                ("password".to_string(), "hunter2".to_string()),
+            ]),
+            secrets: BTreeSet::from(["password".to_string()]),
+        };
+        storage.save_global_variables(&globals).unwrap();
+        assert_eq!(storage.load_global_variables().unwrap(), globals);
+
+        let gitignore = fs::read_to_string(root.join(".gitignore")).unwrap();
+        assert!(gitignore.contains(GLOBALS_FILE));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_oauth_tokens() {
+        let root = temp_root();
+        let mut storage = FileStorage::open(&root).unwrap();
+        let gitignore = fs::read_to_string(root.join(".gitignore")).unwrap();
+        assert!(gitignore.lines().any(|line| line == TOKENS_FILE));
+
+        assert!(storage.load_token("key").unwrap().is_none());
+        let token = OAuthToken {
+            access_token: "a1".to_string(),
+            refresh_token: Some("r1".to_string()),
+            expires_at: Some(1_700_000_000),
+        };
+        storage.save_token("key", &token).unwrap();
+        storage.save_token("other", &token).unwrap();
+        assert_eq!(storage.load_token("key").unwrap(), Some(token));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,580 @@
+use rusqlite::{params, Connection, Transaction};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A single schema change. `version` is written to SQLite's `user_version`
+/// once `up` has run, so each step is applied exactly once per database.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+/// Every schema change in the order it must be applied. Append new steps to
+/// the end and never edit one that has shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create initial tables",
+        up: create_initial_tables,
+    },
+    Migration {
+        version: 2,
+        description: "add stable uuids to requests",
+        up: add_request_uuids,
+    },
+    Migration {
+        version: 3,
+        description: "add folders",
+        up: add_folders,
+    },
+    Migration {
+        version: 4,
+        description: "add request positions",
+        up: add_request_positions,
+    },
+    Migration {
+        version: 5,
+        description: "add active environment to projects",
+        up: add_active_environment,
+    },
+    Migration {
+        version: 6,
+        description: "add global, project and request variables",
+        up: add_variable_scopes,
+    },
+    Migration {
+        version: 7,
+        description: "record generated variables with history",
+        up: add_history_variables,
+    },
+    Migration {
+        version: 8,
+        description: "add request captures",
+        up: add_request_captures,
+    },
+    Migration {
+        version: 9,
+        description: "add secret flags",
+        up: add_secret_flags,
+    },
+    Migration {
+        version: 10,
+        description: "add oauth token cache",
+        up: add_oauth_tokens,
+    },
+    Migration {
+        version: 11,
+        description: "record auth hops with history",
+        up: add_history_hops,
+    },
+    Migration {
+        version: 12,
+        description: "add project settings",
+        up: add_project_settings,
+    },
+    Migration {
+        version: 13,
+        description: "add environment tls settings",
+        up: add_environment_tls,
+    },
+    Migration {
+        version: 14,
+        description: "add request protocol and response http version",
+        up: add_http_versions,
+    },
+    Migration {
+        version: 15,
+        description: "add secret flags to global, project and request variables",
+        up: add_variable_secret_flags,
+    },
+];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The database was written by a newer build that knows more migrations.
+    TooNew {
+        found: u32,
+        latest: u32,
+    },
+    Failed {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+        backup: Option<PathBuf>,
+    },
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::TooNew { found, latest } => write!(
+                f,
+                "database schema version {} is newer than this build supports ({})",
+                found, latest
+            ),
+            MigrationError::Failed {
+                version,
+                description,
+                source,
+                backup,
+            } => {
+                write!(
+                    f,
+                    "migration {} ({}) failed: {}",
+                    version, description, source
+                )?;
+                if let Some(backup) = backup {
+                    write!(
+                        f,
+                        "; the database as it was before migrating is at {}",
+                        backup.display()
+                    )?;
+                }
+                Ok(())
+            }
+            MigrationError::Sqlite(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(e: rusqlite::Error) -> Self {
+        MigrationError::Sqlite(e)
+    }
+}
+
+pub fn run(conn: &mut Connection) -> Result<(), MigrationError> {
+    run_migrations(conn, MIGRATIONS)
+}
+
+fn run_migrations(conn: &mut Connection, migrations: &[Migration]) -> Result<(), MigrationError> {
+    let current = schema_version(conn)?;
+    let latest = migrations.last().map_or(0, |m| m.version);
+    if current > latest {
+        return Err(MigrationError::TooNew {
+            found: current,
+            latest,
+        });
+    }
+
+    let mut pending = migrations.iter().filter(|m| m.version > current).peekable();
+    // Steps commit one at a time, so a later failure leaves the earlier ones
+    // applied. The copy taken up front is the database as it was before any
+    // of them; it is kept only if a step fails, so an older build can still
+    // open it.
+    let backup = match pending.peek() {
+        Some(first) if has_tables(conn)? => backup_database(conn, first.version),
+        _ => None,
+    };
+
+    for migration in pending {
+        let result = conn.transaction().and_then(|tx| {
+            (migration.up)(&tx)?;
+            tx.pragma_update(None, "user_version", migration.version)?;
+            tx.commit()
+        });
+
+        if let Err(source) = result {
+            return Err(MigrationError::Failed {
+                version: migration.version,
+                description: migration.description,
+                source,
+                backup,
+            });
+        }
+    }
+
+    if let Some(backup) = backup {
+        let _ = std::fs::remove_file(backup);
+    }
+    Ok(())
+}
+
+/// Whether the database holds anything yet. A new one is not backed up.
+fn has_tables(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table'")?
+        .exists([])
+}
+
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Copies the database next to the original file. In-memory databases have
+/// nothing to back up.
+fn backup_database(conn: &Connection, version: u32) -> Option<PathBuf> {
+    let path = conn.path().filter(|p| !p.is_empty())?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let backup = PathBuf::from(format!("{}.pre-v{}-{}.bak", path, version, timestamp));
+
+    conn.execute("VACUUM INTO ?1", params![backup.to_string_lossy()])
+        .ok()
+        .map(|_| backup)
+}
+
+fn create_initial_tables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS projects (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS environments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS environment_variables (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            environment_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            FOREIGN KEY(environment_id) REFERENCES environments(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS requests (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            method TEXT,
+            url TEXT,
+            body TEXT,
+            query_params TEXT,
+            path_params TEXT,
+            auth_data TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS headers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            request_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            FOREIGN KEY(request_id) REFERENCES requests(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS request_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            request_id INTEGER NOT NULL,
+            status_code INTEGER,
+            response_body TEXT,
+            response_headers TEXT,
+            response_time INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            FOREIGN KEY(request_id) REFERENCES requests(id)
+        );",
+    )
+}
+
+fn add_request_uuids(tx: &Transaction) -> rusqlite::Result<()> {
+    // Databases opened by builds before versioned migrations may already
+    // have the column.
+    let has_uuid = tx
+        .prepare("SELECT 1 FROM pragma_table_info('requests') WHERE name = 'uuid'")?
+        .exists([])?;
+    if !has_uuid {
+        tx.execute("ALTER TABLE requests ADD COLUMN uuid TEXT", [])?;
+    }
+
+    let missing: Vec<i64> = tx
+        .prepare("SELECT id FROM requests WHERE uuid IS NULL")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for id in missing {
+        tx.execute(
+            "UPDATE requests SET uuid = ?1 WHERE id = ?2",
+            params![uuid::Uuid::new_v4().to_string(), id],
+        )?;
+    }
+
+    tx.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_requests_uuid ON requests(uuid)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn add_folders(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE folders (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            parent_id TEXT,
+            name TEXT NOT NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id)
+        );
+
+        ALTER TABLE requests ADD COLUMN folder_id TEXT;",
+    )
+}
+
+fn add_request_positions(tx: &Transaction) -> rusqlite::Result<()> {
+    // Keep the order requests used to load in, which was rowid order.
+    tx.execute_batch(
+        "ALTER TABLE requests ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+        UPDATE requests SET position = (
+            SELECT COUNT(*) FROM requests AS earlier
+            WHERE earlier.project_id = requests.project_id AND earlier.id < requests.id
+        );",
+    )
+}
+
+fn add_active_environment(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute(
+        "ALTER TABLE projects ADD COLUMN active_environment TEXT",
+        [],
+    )?;
+    Ok(())
+}
+
+fn add_variable_scopes(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE global_variables (
+            name TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE TABLE project_variables (
+            project_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY(project_id, name),
+            FOREIGN KEY(project_id) REFERENCES projects(id)
+        );
+
+        ALTER TABLE requests ADD COLUMN variables TEXT;",
+    )
+}
+
+fn add_history_variables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE request_history ADD COLUMN variables TEXT;")
+}
+
+fn add_request_captures(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE requests ADD COLUMN captures TEXT;")
+}
+
+fn add_secret_flags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE environment_variables ADD COLUMN secret INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE requests ADD COLUMN secret_auth INTEGER NOT NULL DEFAULT 0;",
+    )
+}
+
+fn add_oauth_tokens(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE oauth_tokens (
+            cache_key TEXT PRIMARY KEY,
+            access_token TEXT NOT NULL,
+            refresh_token TEXT,
+            expires_at INTEGER
+        );",
+    )
+}
+
+fn add_history_hops(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE request_history ADD COLUMN hops TEXT;")
+}
+
+fn add_project_settings(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE projects ADD COLUMN settings TEXT;")
+}
+
+fn add_environment_tls(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE environments ADD COLUMN tls TEXT;")
+}
+
+fn add_http_versions(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE requests ADD COLUMN protocol TEXT;
+        ALTER TABLE request_history ADD COLUMN http_version TEXT;",
+    )
+}
+
+fn add_variable_secret_flags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE global_variables ADD COLUMN secret INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE project_variables ADD COLUMN secret INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE requests ADD COLUMN secrets TEXT;",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")
+            .unwrap()
+            .exists(params![name])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_versions_are_sequential() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(
+                migration.version as usize,
+                i + 1,
+                "{}",
+                migration.description
+            );
+        }
+    }
+
+    #[test]
+    fn test_fresh_database_reaches_latest_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+
+        assert_eq!(
+            schema_version(&conn).unwrap(),
+            MIGRATIONS.last().unwrap().version
+        );
+        for table in [
+            "projects",
+            "requests",
+            "headers",
+            "request_history",
+            "folders",
+            "global_variables",
+            "project_variables",
+        ] {
+            assert!(table_exists(&conn, table), "{}", table);
+        }
+
+        // Running again is a no-op.
+        run(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_legacy_requests_get_uuids() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, &MIGRATIONS[..1]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p', 'p', 0, 0);
+             INSERT INTO requests (project_id, name, created_at, updated_at)
+             VALUES ('p', 'legacy', 0, 0), ('p', 'later', 0, 0);",
+        )
+        .unwrap();
+
+        run(&mut conn).unwrap();
+
+        let position: i64 = conn
+            .query_row(
+                "SELECT position FROM requests WHERE name = 'later'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(position, 1);
+
+        let uuid: Option<String> = conn
+            .query_row(
+                "SELECT uuid FROM requests WHERE name = 'legacy'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(uuid.is_some());
+    }
+
+    #[test]
+    fn test_newer_database_is_rejected() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", 999).unwrap();
+        assert!(matches!(
+            run(&mut conn),
+            Err(MigrationError::TooNew { found: 999, .. })
+        ));
+    }
+
+    #[test]
+    fn test_backup_is_removed_after_migrating() {
+        let path = std::env::temp_dir().join(format!("rurl-{}.db", uuid::Uuid::new_v4()));
+        let mut conn = Connection::open(&path).unwrap();
+        run_migrations(&mut conn, &MIGRATIONS[..1]).unwrap();
+        run(&mut conn).unwrap();
+
+        let prefix = format!("{}.pre-", path.file_name().unwrap().to_string_lossy());
+        let backups = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .count();
+        assert_eq!(backups, 0);
+
+        drop(conn);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_failed_migration_backs_up_and_rolls_back() {
+        fn broken(tx: &Transaction) -> rusqlite::Result<()> {
+            tx.execute("CREATE TABLE half_done (id INTEGER)", [])?;
+            tx.execute("THIS IS NOT SQL", []).map(|_| ())
+        }
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "create initial tables",
+                up: create_initial_tables,
+            },
+            Migration {
+                version: 2,
+                description: "add stable uuids to requests",
+                up: add_request_uuids,
+            },
+            Migration {
+                version: 3,
+                description: "broken",
+                up: broken,
+            },
+        ];
+
+        let path = std::env::temp_dir().join(format!("rurl-{}.db", uuid::Uuid::new_v4()));
+        let mut conn = Connection::open(&path).unwrap();
+        run_migrations(&mut conn, &migrations[..1]).unwrap();
+
+        let error = run_migrations(&mut conn, &migrations).unwrap_err();
+        let backup = match &error {
+            MigrationError::Failed {
+                version: 3,
+                backup: Some(backup),
+                ..
+            } => backup.clone(),
+            other => panic!("expected a failed migration, got {:?}", other),
+        };
+        assert!(error.to_string().contains(&backup.display().to_string()));
+
+        // Step 2 stays applied and the broken one is rolled back...
+        assert_eq!(schema_version(&conn).unwrap(), 2);
+        assert!(!table_exists(&conn, "half_done"));
+        // ...while the backup is from before step 2 ran.
+        let backup_conn = Connection::open(&backup).unwrap();
+        assert_eq!(schema_version(&backup_conn).unwrap(), 1);
+        assert!(table_exists(&backup_conn, "projects"));
+
+        drop(backup_conn);
+        drop(conn);
+        let _ = std::fs::remove_file(&backup);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,760 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod error;
+mod files;
+mod migrations;
+mod secrets;
+mod sqlite;
+
+pub use error::StorageError;
+pub use files::FileStorage;
+pub use secrets::{has_locked_secrets, is_encrypted, without_secrets, SecretStorage, Vault};
+pub use sqlite::SqliteStorage;
+
+/// Where projects are kept. [`SqliteStorage`] puts everything in one database;
+/// [`FileStorage`] writes each project as a directory of plain-text files that
+/// can be checked into a repository.
+pub trait Storage {
+    /// Writes the whole project. Used when a project is created or imported;
+    /// edits go through [`Storage::apply_update`].
+    fn save_project(&mut self, project: &ProjectData) -> Result<(), StorageError>;
+
+    fn load_project(&mut self, id: &str) -> Result<Option<ProjectData>, StorageError>;
+
+    /// Lists every project with only `id` and `name` filled in.
+    fn list_projects(&mut self) -> Result<Vec<ProjectData>, StorageError>;
+
+    fn delete_project(&mut self, id: &str) -> Result<(), StorageError>;
+
+    /// Persists a single [`ProjectUpdate`]. `project` is the state after the
+    /// update has been applied in memory, so backends without a cheaper path
+    /// can simply write it out again.
+    fn apply_update(
+        &mut self,
+        project: &ProjectData,
+        update: &ProjectUpdate,
+    ) -> Result<(), StorageError> {
+        let _ = update;
+        self.save_project(project)
+    }
+
+    /// Records a response in the request's history and returns it with
+    /// `request_id` filled in. Returns `None` if the request is not stored.
+    fn save_response(
+        &mut self,
+        request_uuid: &str,
+        response: &ResponseData,
+    ) -> Result<Option<ResponseData>, StorageError>;
+
+    /// Lists past responses for a request, newest first.
+    fn load_history(&mut self, request_uuid: &str) -> Result<Vec<ResponseData>, StorageError>;
+
+    /// Variables shared by every project.
+    fn load_global_variables(&mut self) -> Result<GlobalVariables, StorageError>;
+
+    fn save_global_variables(&mut self, globals: &GlobalVariables) -> Result<(), StorageError>;
+
+    /// A cached OAuth2 token, stored under the key `oauth::cache_key` gives
+    /// for the auth settings it was issued for.
+    fn load_token(&mut self, key: &str) -> Result<Option<OAuthToken>, StorageError>;
+
+    fn save_token(&mut self, key: &str, token: &OAuthToken) -> Result<(), StorageError>;
+}
+
+#[derive(Debug, Clone)]
+pub enum ProjectUpdate {
+    AddRequest(RequestData),
+    /// Replaces a request's contents. Its place in the project is kept; that
+    /// only changes through `MoveRequest`.
+    UpdateRequest(RequestData),
+    /// Moves a request into `folder_id` and to `index` in the project's
+    /// request order, counted after the request has been taken out.
+    MoveRequest {
+        id: String,
+        folder_id: Option<String>,
+        index: usize,
+    },
+    DeleteRequest(String),
+    UpdateName(String),
+    AddEnvironment(Environment),
+    UpdateEnvironment(usize, Environment),
+    DeleteEnvironment(usize),
+    /// Chooses the environment, by name, that requests are sent with.
+    SetActiveEnvironment(Option<String>),
+    /// Replaces the project-level variables and which of them are secret.
+    UpdateVariables {
+        variables: HashMap<String, String>,
+        secrets: BTreeSet<String>,
+    },
+    UpdateSettings(ProjectSettings),
+    AddFolder(Folder),
+    UpdateFolder(Folder),
+    /// Removes a folder. Its requests and subfolders move up to its parent.
+    DeleteFolder(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ProjectData {
+    pub name: String,
+    pub id: String,
+    pub requests: Vec<RequestData>,
+    #[serde(default)]
+    pub folders: Vec<Folder>,
+    pub environments: Vec<Environment>,
+    /// Name of the environment requests are sent with, if any.
+    #[serde(default)]
+    pub active_environment: Option<String>,
+    /// Variables every request in the project can use, whichever
+    /// environment is active.
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// Names of the project variables that are secret.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub secrets: BTreeSet<String>,
+    #[serde(default)]
+    pub settings: ProjectSettings,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Defaults every request in a project is sent with.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct ProjectSettings {
+    /// Request URLs starting with `/` are sent to this URL followed by them.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub base_url: String,
+    /// Sent with every request that does not set a header of the same name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    /// Used by requests whose auth is [`AuthData::Inherit`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthData>,
+    /// Whether the password, token or key in `auth` is secret.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret_auth: bool,
+    /// Seconds to wait for a connection to be made.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+    /// Seconds to wait for the whole exchange, connecting included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "TlsSettings::is_default")]
+    pub tls: TlsSettings,
+}
+
+/// How requests connect over HTTPS. Set in the config file, per project and
+/// per environment; see [`TlsSettings::merge`] for how they combine.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct TlsSettings {
+    /// PEM files of certificate authorities trusted on top of the built-in
+    /// roots.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ca_files: Vec<String>,
+    /// Presented to servers that ask for one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_certificate: Option<ClientCertificate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<TlsVersion>,
+    /// Hosts whose certificates are accepted without being verified.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub insecure_hosts: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum ClientCertificate {
+    /// A certificate chain and its private key. The key may be in the same
+    /// file, in which case `key_file` is empty.
+    Pem {
+        cert_file: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        key_file: String,
+    },
+    /// A PKCS#12 bundle holding both.
+    Pkcs12 {
+        file: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        password: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum TlsVersion {
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+impl TlsSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// These settings overridden by the more specific `other`: CA files and
+    /// insecure hosts add up, the client certificate and minimum version
+    /// are replaced when `other` sets them.
+    pub fn merge(&self, other: &TlsSettings) -> TlsSettings {
+        let mut merged = self.clone();
+        for file in &other.ca_files {
+            if !merged.ca_files.contains(file) {
+                merged.ca_files.push(file.clone());
+            }
+        }
+        for host in &other.insecure_hosts {
+            if !merged.insecure_hosts.contains(host) {
+                merged.insecure_hosts.push(host.clone());
+            }
+        }
+        if other.client_certificate.is_some() {
+            merged.client_certificate = other.client_certificate.clone();
+        }
+        merged.min_version = other.min_version.or(self.min_version);
+        merged
+    }
+}
+
+impl ProjectSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `url` joined to the base URL if it is relative to it.
+    pub fn url_for(&self, url: &str) -> String {
+        let trimmed = url.trim();
+        if self.base_url.is_empty() || !trimmed.starts_with('/') {
+            return url.to_string();
+        }
+        format!("{}{}", self.base_url.trim_end_matches('/'), trimmed)
+    }
+
+    /// A copy of `request` with these defaults filled in, as it is sent.
+    pub fn apply(&self, request: &RequestData) -> RequestData {
+        let mut request = request.clone();
+        request.url = request.url.map(|url| self.url_for(&url));
+
+        let own = request.headers.take().unwrap_or_default();
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .filter(|(name, _)| !own.iter().any(|(n, _)| n.eq_ignore_ascii_case(name)))
+            .cloned()
+            .collect();
+        headers.extend(own);
+        request.headers = Some(headers);
+
+        if request.auth == Some(AuthData::Inherit) {
+            request.auth = self.auth.clone();
+            request.secret_auth = self.secret_auth;
+        }
+        request
+    }
+}
+
+/// A named group of requests. Folders nest through `parent_id`; `None` means
+/// the folder sits at the top of the project.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Folder {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Environment {
+    pub name: String,
+    pub variables: HashMap<String, String>,
+    /// Names of the variables whose values are secret: encrypted at rest and
+    /// masked on screen.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub secrets: BTreeSet<String>,
+    /// Applied over the project's TLS settings while this environment is
+    /// active.
+    #[serde(default, skip_serializing_if = "TlsSettings::is_default")]
+    pub tls: TlsSettings,
+}
+
+/// Variables shared by every project, and which of them are secret.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct GlobalVariables {
+    pub variables: HashMap<String, String>,
+    pub secrets: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RequestData {
+    pub id: String,
+    pub name: String,
+    pub method: Option<String>,
+    pub url: Option<String>,
+    pub headers: Option<Vec<(String, String)>>,
+    pub body: Option<String>,
+    pub query_params: Option<Vec<(String, String)>>,
+    pub path_params: Option<Vec<(String, String)>>,
+    pub auth: Option<AuthData>,
+    /// Whether the password, token or key in `auth` is secret.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret_auth: bool,
+    /// Variables that override every other scope for this request only.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub variables: HashMap<String, String>,
+    /// Names of the request's own variables that are secret.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub secrets: BTreeSet<String>,
+    /// Values copied from each response into the active environment.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub captures: Vec<Capture>,
+    #[serde(default, skip_serializing_if = "HttpProtocol::is_auto")]
+    pub protocol: HttpProtocol,
+    /// The folder holding this request, or `None` at the top of the project.
+    #[serde(default)]
+    pub folder_id: Option<String>,
+    /// Sort key within the project. `ProjectData::requests` is kept in this
+    /// order.
+    #[serde(default)]
+    pub position: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// The HTTP version a request is sent over.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpProtocol {
+    /// HTTP/2 when the server agrees to it while connecting over TLS,
+    /// HTTP/1.1 otherwise.
+    #[default]
+    Auto,
+    Http1,
+    /// HTTP/2 over TLS, failing if the server does not agree to it.
+    Http2,
+    /// HTTP/2 over plain TCP, assuming the server speaks it without asking.
+    H2c,
+}
+
+impl HttpProtocol {
+    pub fn is_auto(&self) -> bool {
+        *self == HttpProtocol::Auto
+    }
+}
+
+/// Sets `variable` in the active environment from each response to the
+/// request it belongs to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Capture {
+    pub variable: String,
+    pub source: CaptureSource,
+}
+
+/// Where in a response a [`Capture`] finds its value.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum CaptureSource {
+    /// A JSONPath into the body, such as `$.data.token`.
+    JsonPath(String),
+    /// A response header, by name.
+    Header(String),
+    /// A regular expression over the body. The first group is used if there
+    /// is one, otherwise the whole match.
+    Regex(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum AuthData {
+    None,
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer {
+        token: String,
+    },
+    ApiKey {
+        key: String,
+        value: String,
+        in_header: bool,
+    },
+    /// Answers the server's 401 challenge, so every send takes two requests.
+    Digest {
+        username: String,
+        password: String,
+    },
+    /// A bearer token obtained from `token_url` when the request is sent.
+    OAuth2 {
+        grant: OAuthGrant,
+        /// Where the user signs in. Only used by the authorization code grant.
+        #[serde(default)]
+        auth_url: String,
+        token_url: String,
+        client_id: String,
+        #[serde(default)]
+        client_secret: String,
+        #[serde(default)]
+        scope: String,
+        /// The resource owner's credentials, for the password grant.
+        #[serde(default)]
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+    /// Signs the request as it is finally sent with AWS Signature V4.
+    AwsSigV4 {
+        access_key: String,
+        secret_key: String,
+        /// Only set for temporary credentials.
+        #[serde(default)]
+        session_token: String,
+        region: String,
+        service: String,
+    },
+    /// A JWT minted from `claims` when the request is sent and added as a
+    /// bearer token.
+    Jwt {
+        algorithm: JwtAlgorithm,
+        /// The shared secret for HS256, otherwise a PEM private key or the
+        /// path of one.
+        key: String,
+        /// A JSON object, usually with placeholders.
+        claims: String,
+        /// Seconds until the token expires. When set, `iat` and `exp` are
+        /// added unless the claims have them.
+        #[serde(default)]
+        expires_in: String,
+    },
+    /// An HMAC over parts of the request, sent in `header`.
+    Hmac {
+        algorithm: HmacAlgorithm,
+        secret: String,
+        header: String,
+        /// What is signed. `{method}`, `{path}`, `{body}` and `{timestamp}`
+        /// are filled in from the request as sent, and `\n` is a newline.
+        template: String,
+        encoding: SignatureEncoding,
+        /// Where the timestamp that was signed is sent, if anywhere.
+        #[serde(default)]
+        timestamp_header: String,
+    },
+    /// Uses the project's default auth, see [`ProjectSettings::auth`].
+    Inherit,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum JwtAlgorithm {
+    Hs256,
+    Rs256,
+    Es256,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HmacAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+    Hex,
+    Base64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthGrant {
+    ClientCredentials,
+    Password,
+    /// Authorization code with PKCE, redirected to a loopback listener.
+    AuthorizationCode,
+}
+
+/// An OAuth2 token cached between sends.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct OAuthToken {
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Unix time the access token expires at, if the server said.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ResponseData {
+    pub request_id: i64,
+    pub status_code: Option<i32>,
+    pub response_body: Option<String>,
+    pub response_headers: Option<Vec<(String, String)>>,
+    pub response_time: i64,
+    pub timestamp: i64,
+    /// The HTTP version the response came over, such as `HTTP/2`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_version: Option<String>,
+    /// Values generated for built-in placeholders such as `{{$uuid}}` when
+    /// the request was sent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<(String, String)>,
+    /// Exchanges made before this response while sending, such as the 401
+    /// carrying a Digest challenge. `response_time` includes them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hops: Vec<Hop>,
+    /// What was signed when the request used AWS Signature V4. Kept out of
+    /// history since the canonical request can hold a session token.
+    #[serde(skip)]
+    pub signing: Option<Signing>,
+    /// The chain an HTTPS server presented, its own certificate first. Only
+    /// kept for the response just received.
+    #[serde(skip)]
+    pub certificates: Vec<CertificateInfo>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    /// Validity bounds, in seconds since the epoch.
+    pub not_before: i64,
+    pub not_after: i64,
+}
+
+/// The intermediate steps of an AWS Signature V4 signature, to compare with
+/// what the service expected when it rejects one.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signing {
+    pub canonical_request: String,
+    pub string_to_sign: String,
+}
+
+/// A response that was answered with another request instead of being shown.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Hop {
+    pub status_code: i32,
+    pub response_headers: Vec<(String, String)>,
+    pub response_time: i64,
+}
+
+impl ProjectData {
+    pub fn new(name: String) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        Self {
+            name,
+            id: uuid::Uuid::new_v4().to_string(),
+            requests: Vec::new(),
+            folders: Vec::new(),
+            environments: Vec::new(),
+            active_environment: None,
+            variables: HashMap::new(),
+            secrets: BTreeSet::new(),
+            settings: ProjectSettings::default(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// The environment requests are sent with.
+    pub fn active_environment(&self) -> Option<&Environment> {
+        let name = self.active_environment.as_ref()?;
+        self.environments.iter().find(|e| &e.name == name)
+    }
+
+    pub fn apply_update(&mut self, update: ProjectUpdate) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        self.updated_at = now;
+
+        match update {
+            ProjectUpdate::AddRequest(request) => {
+                let position = self
+                    .requests
+                    .iter()
+                    .map(|r| r.position + POSITION_STEP)
+                    .max();
+                self.requests.push(RequestData {
+                    position: position.unwrap_or(0),
+                    ..request
+                });
+            }
+            ProjectUpdate::UpdateRequest(request) => {
+                if let Some(existing) = self.requests.iter_mut().find(|r| r.id == request.id) {
+                    *existing = RequestData {
+                        folder_id: existing.folder_id.take(),
+                        position: existing.position,
+                        updated_at: now,
+                        ..request
+                    };
+                }
+            }
+            ProjectUpdate::MoveRequest {
+                id,
+                folder_id,
+                index,
+            } => {
+                if let Some(from) = self.requests.iter().position(|r| r.id == id) {
+                    let mut request = self.requests.remove(from);
+                    request.folder_id = folder_id;
+                    let index = index.min(self.requests.len());
+                    self.requests.insert(index, request);
+
+                    // Only the moved request gets a new position, between its
+                    // neighbours', unless there is no room left there.
+                    let before = index.checked_sub(1).map(|i| self.requests[i].position);
+                    let after = self.requests.get(index + 1).map(|r| r.position);
+                    let position = match (before, after) {
+                        (None, None) => Some(0),
+                        (Some(before), None) => Some(before + POSITION_STEP),
+                        (None, Some(after)) => Some(after - POSITION_STEP),
+                        (Some(before), Some(after)) => {
+                            (after - before > 1).then(|| before + (after - before) / 2)
+                        }
+                    };
+                    match position {
+                        Some(position) => self.requests[index].position = position,
+                        None => {
+                            for (i, request) in self.requests.iter_mut().enumerate() {
+                                request.position = i as i64 * POSITION_STEP;
+                            }
+                        }
+                    }
+                }
+            }
+            ProjectUpdate::DeleteRequest(id) => {
+                self.requests.retain(|r| r.id != id);
+            }
+            ProjectUpdate::UpdateName(new_name) => {
+                self.name = new_name;
+            }
+            ProjectUpdate::AddEnvironment(env) => {
+                self.environments.push(env);
+            }
+            ProjectUpdate::UpdateEnvironment(index, env) => {
+                if let Some(existing) = self.environments.get_mut(index) {
+                    // A rename keeps the environment active.
+                    if self.active_environment.as_ref() == Some(&existing.name) {
+                        self.active_environment = Some(env.name.clone());
+                    }
+                    *existing = env;
+                }
+            }
+            ProjectUpdate::DeleteEnvironment(index) => {
+                if index < self.environments.len() {
+                    let env = self.environments.remove(index);
+                    if self.active_environment.as_ref() == Some(&env.name) {
+                        self.active_environment = None;
+                    }
+                }
+            }
+            ProjectUpdate::SetActiveEnvironment(name) => {
+                self.active_environment = name;
+            }
+            ProjectUpdate::UpdateVariables { variables, secrets } => {
+                self.variables = variables;
+                self.secrets = secrets;
+            }
+            ProjectUpdate::UpdateSettings(settings) => {
+                self.settings = settings;
+            }
+            ProjectUpdate::AddFolder(folder) => {
+                self.folders.push(folder);
+            }
+            ProjectUpdate::UpdateFolder(folder) => {
+                if let Some(existing) = self.folders.iter_mut().find(|f| f.id == folder.id) {
+                    *existing = folder;
+                }
+            }
+            ProjectUpdate::DeleteFolder(id) => {
+                let parent_id = match self.folders.iter().find(|f| f.id == id) {
+                    Some(folder) => folder.parent_id.clone(),
+                    None => return,
+                };
+                for folder in &mut self.folders {
+                    if folder.parent_id.as_ref() == Some(&id) {
+                        folder.parent_id = parent_id.clone();
+                    }
+                }
+                for request in &mut self.requests {
+                    if request.folder_id.as_ref() == Some(&id) {
+                        request.folder_id = parent_id.clone();
+                    }
+                }
+                self.folders.retain(|f| f.id != id);
+            }
+        }
+    }
+}
+
+/// How far apart requests are numbered, so that one can usually be moved
+/// between two others without renumbering the rest.
+const POSITION_STEP: i64 = 1024;
+
+/// Writes a map in key order, so saving the same data always produces the
+/// same file.
+fn serialize_sorted<S: Serializer>(
+    map: &HashMap<String, String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+impl Folder {
+    pub fn new(name: String, parent_id: Option<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            parent_id,
+        }
+    }
+}
+
+impl RequestData {
+    pub fn new(name: String) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            method: None,
+            url: None,
+            headers: None,
+            body: None,
+            query_params: None,
+            path_params: None,
+            auth: Some(AuthData::Inherit),
+            secret_auth: false,
+            variables: HashMap::new(),
+            secrets: BTreeSet::new(),
+            captures: Vec::new(),
+            protocol: HttpProtocol::Auto,
+            folder_id: None,
+            position: 0,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// A copy of this request under a new id, named "<name> copy".
+    pub fn duplicate(&self) -> Self {
+        let copy = Self::new(format!("{} copy", self.name));
+        Self {
+            id: copy.id,
+            name: copy.name,
+            created_at: copy.created_at,
+            updated_at: copy.updated_at,
+            ..self.clone()
+        }
+    }
+}
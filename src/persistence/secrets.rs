@@ -0,0 +1,724 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use super::{
+    AuthData, ClientCertificate, Environment, GlobalVariables, OAuthToken, ProjectData,
+    ProjectSettings, ProjectUpdate, RequestData, ResponseData, Storage, StorageError, TlsSettings,
+};
+
+/// Marks a value as encrypted. The salt and the nonce with the ciphertext
+/// follow, each in base64, so every value can be decrypted on its own.
+const PREFIX: &str = "enc:v1:";
+const SALT_LEN: usize = 16;
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+/// Encrypts and decrypts secret values with keys derived from a passphrase
+/// or the contents of a key file.
+pub struct Vault {
+    material: Vec<u8>,
+    /// Salt for values encrypted by this vault.
+    salt: [u8; SALT_LEN],
+    /// Keys already derived, by salt. Derivation is deliberately slow.
+    keys: HashMap<[u8; SALT_LEN], [u8; 32]>,
+}
+
+impl Vault {
+    pub fn from_passphrase(passphrase: &str) -> Self {
+        Self::new(passphrase.as_bytes().to_vec())
+    }
+
+    pub fn from_key_file(path: &Path) -> Result<Self, StorageError> {
+        let material = fs::read(path).map_err(|e| StorageError::Io(path.to_path_buf(), e))?;
+        Ok(Self::new(material))
+    }
+
+    fn new(material: Vec<u8>) -> Self {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Vault {
+            material,
+            salt,
+            keys: HashMap::new(),
+        }
+    }
+
+    fn cipher(&mut self, salt: [u8; SALT_LEN]) -> ChaCha20Poly1305 {
+        let material = &self.material;
+        let key = self.keys.entry(salt).or_insert_with(|| {
+            let mut key = [0; 32];
+            Argon2::default()
+                .hash_password_into(material, &salt, &mut key)
+                .expect("salt and key lengths are valid for argon2");
+            key
+        });
+        ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+    }
+
+    pub fn encrypt(&mut self, plaintext: &str) -> String {
+        let salt = self.salt;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher(salt)
+            .encrypt(&nonce, plaintext.as_bytes())
+            .expect("encrypting into memory cannot fail");
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        format!(
+            "{}{}:{}",
+            PREFIX,
+            STANDARD.encode(salt),
+            STANDARD.encode(sealed)
+        )
+    }
+
+    pub fn decrypt(&mut self, value: &str) -> Result<String, StorageError> {
+        let decoded = value.strip_prefix(PREFIX).and_then(|rest| {
+            let (salt, sealed) = rest.split_once(':')?;
+            let salt: [u8; SALT_LEN] = STANDARD.decode(salt).ok()?.try_into().ok()?;
+            let sealed = STANDARD.decode(sealed).ok()?;
+            (sealed.len() > 12).then_some((salt, sealed))
+        });
+        let (salt, sealed) = decoded.ok_or(StorageError::Decrypt)?;
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let plaintext = self
+            .cipher(salt)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| StorageError::Decrypt)?;
+        String::from_utf8(plaintext).map_err(|_| StorageError::Decrypt)
+    }
+}
+
+/// Wraps another [`Storage`] so that secret variables and auth fields are
+/// encrypted before they are written and decrypted when they are read.
+///
+/// Without a [`Vault`], or with one holding the wrong key, encrypted values
+/// are loaded as they are and written back unchanged, so nothing is lost;
+/// new secrets cannot be saved until a key is given.
+pub struct SecretStorage {
+    inner: Box<dyn Storage>,
+    vault: Option<Vault>,
+    /// The plaintext and ciphertext of each secret last opened or sealed, by
+    /// where it is. The ciphertext is reused while the plaintext stays the
+    /// same, so unchanged secrets are written out unchanged.
+    sealed: HashMap<String, (String, String)>,
+    /// Encrypted values that were loaded but could not be decrypted. They are
+    /// written back exactly as they were read.
+    locked: HashSet<String>,
+}
+
+impl SecretStorage {
+    pub fn new(inner: Box<dyn Storage>, vault: Option<Vault>) -> Self {
+        SecretStorage {
+            inner,
+            vault,
+            sealed: HashMap::new(),
+            locked: HashSet::new(),
+        }
+    }
+
+    fn seal(&mut self, location: &str, value: &mut String) -> Result<(), StorageError> {
+        if value.is_empty() || self.locked.contains(value.as_str()) {
+            return Ok(());
+        }
+        if let Some((plaintext, ciphertext)) = self.sealed.get(location) {
+            if plaintext == value {
+                *value = ciphertext.clone();
+                return Ok(());
+            }
+        }
+        let vault = self.vault.as_mut().ok_or(StorageError::SecretsLocked)?;
+        let ciphertext = vault.encrypt(value);
+        self.sealed.insert(
+            location.to_string(),
+            (std::mem::replace(value, ciphertext.clone()), ciphertext),
+        );
+        Ok(())
+    }
+
+    /// Decrypts `value` if it can, leaving it encrypted otherwise.
+    fn open(&mut self, location: &str, value: &mut String) {
+        if !is_encrypted(value) {
+            return;
+        }
+        match self.vault.as_mut().map(|vault| vault.decrypt(value)) {
+            Some(Ok(plaintext)) => {
+                let ciphertext = std::mem::replace(value, plaintext.clone());
+                self.sealed
+                    .insert(location.to_string(), (plaintext, ciphertext));
+            }
+            _ => {
+                self.locked.insert(value.clone());
+            }
+        }
+    }
+
+    fn seal_project(&mut self, project: &ProjectData) -> Result<ProjectData, StorageError> {
+        let mut project = project.clone();
+        for_each_secret(&mut project, |location, value| self.seal(location, value))?;
+        Ok(project)
+    }
+
+    fn seal_update(
+        &mut self,
+        project: &ProjectData,
+        update: &ProjectUpdate,
+    ) -> Result<ProjectUpdate, StorageError> {
+        let mut update = update.clone();
+        let mut seal = |location: &str, value: &mut String| self.seal(location, value);
+        match &mut update {
+            ProjectUpdate::AddEnvironment(env) | ProjectUpdate::UpdateEnvironment(_, env) => {
+                for_each_environment_secret(&project.id, env, &mut seal)?;
+            }
+            ProjectUpdate::AddRequest(request) | ProjectUpdate::UpdateRequest(request) => {
+                for_each_request_secret(&project.id, request, &mut seal)?;
+            }
+            ProjectUpdate::UpdateVariables { variables, secrets } => {
+                let location = format!("{}/variables", project.id);
+                for_each_variable_secret(&location, variables, secrets, &mut seal)?;
+            }
+            ProjectUpdate::UpdateSettings(settings) => {
+                for_each_settings_secret(&project.id, settings, &mut seal)?;
+            }
+            _ => {}
+        }
+        Ok(update)
+    }
+}
+
+/// Calls `f` with every secret value in `project` and where it is. The
+/// location stays the same as long as the value stays in place.
+fn for_each_secret(
+    project: &mut ProjectData,
+    mut f: impl FnMut(&str, &mut String) -> Result<(), StorageError>,
+) -> Result<(), StorageError> {
+    let location = format!("{}/variables", project.id);
+    for_each_variable_secret(&location, &mut project.variables, &project.secrets, &mut f)?;
+    for env in &mut project.environments {
+        for_each_environment_secret(&project.id, env, &mut f)?;
+    }
+    for request in &mut project.requests {
+        for_each_request_secret(&project.id, request, &mut f)?;
+    }
+    for_each_settings_secret(&project.id, &mut project.settings, f)
+}
+
+/// Calls `f` with the value of each variable named in `secrets`.
+fn for_each_variable_secret(
+    location: &str,
+    variables: &mut HashMap<String, String>,
+    secrets: &BTreeSet<String>,
+    mut f: impl FnMut(&str, &mut String) -> Result<(), StorageError>,
+) -> Result<(), StorageError> {
+    for name in secrets {
+        if let Some(value) = variables.get_mut(name) {
+            f(&format!("{}/{}", location, name), value)?;
+        }
+    }
+    Ok(())
+}
+
+fn for_each_environment_secret(
+    project_id: &str,
+    env: &mut Environment,
+    mut f: impl FnMut(&str, &mut String) -> Result<(), StorageError>,
+) -> Result<(), StorageError> {
+    let location = format!("{}/environment/{}", project_id, env.name);
+    for_each_variable_secret(&location, &mut env.variables, &env.secrets, &mut f)?;
+    for_each_tls_secret(&location, &mut env.tls, f)
+}
+
+fn for_each_request_secret(
+    project_id: &str,
+    request: &mut RequestData,
+    mut f: impl FnMut(&str, &mut String) -> Result<(), StorageError>,
+) -> Result<(), StorageError> {
+    let location = format!("{}/request/{}", project_id, request.id);
+    let variables = format!("{}/variables", location);
+    for_each_variable_secret(&variables, &mut request.variables, &request.secrets, &mut f)?;
+    for_each_auth_secret(&location, &mut request.auth, request.secret_auth, f)
+}
+
+fn for_each_settings_secret(
+    project_id: &str,
+    settings: &mut ProjectSettings,
+    mut f: impl FnMut(&str, &mut String) -> Result<(), StorageError>,
+) -> Result<(), StorageError> {
+    let location = format!("{}/settings", project_id);
+    for_each_auth_secret(&location, &mut settings.auth, settings.secret_auth, &mut f)?;
+    for_each_tls_secret(&location, &mut settings.tls, f)
+}
+
+/// Calls `f` with the password of a PKCS#12 client certificate, which is
+/// always secret.
+fn for_each_tls_secret(
+    location: &str,
+    tls: &mut TlsSettings,
+    mut f: impl FnMut(&str, &mut String) -> Result<(), StorageError>,
+) -> Result<(), StorageError> {
+    match &mut tls.client_certificate {
+        Some(ClientCertificate::Pkcs12 { password, .. }) => {
+            f(&format!("{}/tls/password", location), password)
+        }
+        Some(ClientCertificate::Pem { .. }) | None => Ok(()),
+    }
+}
+
+/// Calls `f` with the credentials in `auth` if they are `secret`.
+fn for_each_auth_secret(
+    location: &str,
+    auth: &mut Option<AuthData>,
+    secret: bool,
+    mut f: impl FnMut(&str, &mut String) -> Result<(), StorageError>,
+) -> Result<(), StorageError> {
+    if !secret {
+        return Ok(());
+    }
+    let mut f = |field: &str, value: &mut String| f(&format!("{}/auth/{}", location, field), value);
+    match auth {
+        Some(AuthData::Basic { password, .. } | AuthData::Digest { password, .. }) => {
+            f("password", password)
+        }
+        Some(AuthData::Bearer { token }) => f("token", token),
+        Some(AuthData::ApiKey { value, .. }) => f("value", value),
+        Some(AuthData::OAuth2 {
+            client_secret,
+            password,
+            ..
+        }) => {
+            f("client_secret", client_secret)?;
+            f("password", password)
+        }
+        Some(AuthData::AwsSigV4 {
+            secret_key,
+            session_token,
+            ..
+        }) => {
+            f("secret_key", secret_key)?;
+            f("session_token", session_token)
+        }
+        Some(AuthData::Jwt { key, .. }) => f("key", key),
+        Some(AuthData::Hmac { secret, .. }) => f("secret", secret),
+        Some(AuthData::None | AuthData::Inherit) | None => Ok(()),
+    }
+}
+
+/// Whether any secret in `project` is still encrypted, because no key or the
+/// wrong one was given.
+pub fn has_locked_secrets(project: &ProjectData) -> bool {
+    let mut locked = false;
+    let _ = for_each_secret(&mut project.clone(), |_, value| {
+        locked |= is_encrypted(value);
+        Ok(())
+    });
+    locked
+}
+
+/// A copy of `project` with every secret value blanked, for sharing.
+pub fn without_secrets(project: &ProjectData) -> ProjectData {
+    let mut project = project.clone();
+    let _ = for_each_secret(&mut project, |_, value| {
+        value.clear();
+        Ok(())
+    });
+    project
+}
+
+impl Storage for SecretStorage {
+    fn save_project(&mut self, project: &ProjectData) -> Result<(), StorageError> {
+        let sealed = self.seal_project(project)?;
+        self.inner.save_project(&sealed)
+    }
+
+    fn load_project(&mut self, id: &str) -> Result<Option<ProjectData>, StorageError> {
+        let mut project = self.inner.load_project(id)?;
+        if let Some(project) = &mut project {
+            let _ = for_each_secret(project, |location, value| {
+                self.open(location, value);
+                Ok(())
+            });
+        }
+        Ok(project)
+    }
+
+    fn list_projects(&mut self) -> Result<Vec<ProjectData>, StorageError> {
+        self.inner.list_projects()
+    }
+
+    fn delete_project(&mut self, id: &str) -> Result<(), StorageError> {
+        self.inner.delete_project(id)
+    }
+
+    fn apply_update(
+        &mut self,
+        project: &ProjectData,
+        update: &ProjectUpdate,
+    ) -> Result<(), StorageError> {
+        let sealed = self.seal_project(project)?;
+        let update = self.seal_update(project, update)?;
+        self.inner.apply_update(&sealed, &update)
+    }
+
+    fn save_response(
+        &mut self,
+        request_uuid: &str,
+        response: &ResponseData,
+    ) -> Result<Option<ResponseData>, StorageError> {
+        self.inner.save_response(request_uuid, response)
+    }
+
+    fn load_history(&mut self, request_uuid: &str) -> Result<Vec<ResponseData>, StorageError> {
+        self.inner.load_history(request_uuid)
+    }
+
+    fn load_global_variables(&mut self) -> Result<GlobalVariables, StorageError> {
+        let mut globals = self.inner.load_global_variables()?;
+        let _ = for_each_variable_secret(
+            "global",
+            &mut globals.variables,
+            &globals.secrets,
+            |location, value| {
+                self.open(location, value);
+                Ok(())
+            },
+        );
+        Ok(globals)
+    }
+
+    fn save_global_variables(&mut self, globals: &GlobalVariables) -> Result<(), StorageError> {
+        let mut sealed = globals.clone();
+        for_each_variable_secret(
+            "global",
+            &mut sealed.variables,
+            &globals.secrets,
+            |location, value| self.seal(location, value),
+        )?;
+        self.inner.save_global_variables(&sealed)
+    }
+
+    /// A token that cannot be decrypted is treated as missing, so a new one
+    /// is requested.
+    fn load_token(&mut self, key: &str) -> Result<Option<OAuthToken>, StorageError> {
+        let Some(mut token) = self.inner.load_token(key)? else {
+            return Ok(None);
+        };
+        let location = format!("token/{}", key);
+        self.open(&format!("{}/access", location), &mut token.access_token);
+        if let Some(refresh_token) = &mut token.refresh_token {
+            self.open(&format!("{}/refresh", location), refresh_token);
+        }
+        let locked = is_encrypted(&token.access_token)
+            || token.refresh_token.as_deref().is_some_and(is_encrypted);
+        Ok((!locked).then_some(token))
+    }
+
+    /// Tokens are encrypted when there is a key, but unlike other secrets
+    /// they are still cached without one.
+    fn save_token(&mut self, key: &str, token: &OAuthToken) -> Result<(), StorageError> {
+        let mut token = token.clone();
+        if self.vault.is_some() {
+            let location = format!("token/{}", key);
+            self.seal(&format!("{}/access", location), &mut token.access_token)?;
+            if let Some(refresh_token) = &mut token.refresh_token {
+                self.seal(&format!("{}/refresh", location), refresh_token)?;
+            }
+        }
+        self.inner.save_token(key, &token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{FileStorage, RequestData, SqliteStorage};
+    use std::collections::BTreeSet;
+
+    fn secret_project() -> ProjectData {
+        let mut project = ProjectData::new("Secrets".to_string());
+        project.variables = HashMap::from([
+            ("version".to_string(), "v1".to_string()),
+            ("api_key".to_string(), "k3y".to_string()),
+        ]);
+        project.secrets = BTreeSet::from(["api_key".to_string()]);
+        project.environments.push(Environment {
+            name: "prod".to_string(),
+            variables: HashMap::from([
+                ("host".to_string(), "api.example.com".to_string()),
+                ("token".to_string(), "s3cret".to_string()),
+            ]),
+            secrets: BTreeSet::from(["token".to_string()]),
+            tls: TlsSettings {
+                client_certificate: Some(ClientCertificate::Pkcs12 {
+                    file: "client.p12".to_string(),
+                    password: "changeit".to_string(),
+                }),
+                ..Default::default()
+            },
+        });
+        let mut request = RequestData::new("login".to_string());
+        request.auth = Some(AuthData::Basic {
+            username: "me".to_string(),
+            password: "hunter2".to_string(),
+        });
+        request.secret_auth = true;
+        request.variables = HashMap::from([("otp".to_string(), "123456".to_string())]);
+        request.secrets = BTreeSet::from(["otp".to_string()]);
+        project.requests.push(request);
+        project.settings = ProjectSettings {
+            auth: Some(AuthData::Bearer {
+                token: "t0ken".to_string(),
+            }),
+            secret_auth: true,
+            ..Default::default()
+        };
+        project
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let mut vault = Vault::from_passphrase("correct horse");
+        let sealed = vault.encrypt("s3cret");
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("s3cret"));
+        assert_ne!(vault.encrypt("s3cret"), sealed);
+        assert_eq!(vault.decrypt(&sealed).unwrap(), "s3cret");
+
+        // A new vault with the same passphrase reads it; another does not.
+        assert_eq!(
+            Vault::from_passphrase("correct horse")
+                .decrypt(&sealed)
+                .unwrap(),
+            "s3cret"
+        );
+        assert!(Vault::from_passphrase("wrong").decrypt(&sealed).is_err());
+        assert!(vault.decrypt("enc:v1:garbage").is_err());
+    }
+
+    #[test]
+    fn test_secrets_are_encrypted_at_rest() {
+        let project = secret_project();
+        let mut inner = SqliteStorage::in_memory().unwrap();
+        let sealed = SecretStorage::new(Box::new(SqliteStorage::in_memory().unwrap()), None)
+            .seal_project(&project);
+        assert!(matches!(sealed, Err(StorageError::SecretsLocked)));
+
+        let mut storage = SecretStorage::new(
+            Box::new(SqliteStorage::in_memory().unwrap()),
+            Some(Vault::from_passphrase("pass")),
+        );
+        let sealed = storage.seal_project(&project).unwrap();
+        assert_eq!(sealed.variables["version"], "v1");
+        assert!(is_encrypted(&sealed.variables["api_key"]));
+        assert!(is_encrypted(&sealed.requests[0].variables["otp"]));
+        let env = &sealed.environments[0];
+        assert_eq!(env.variables["host"], "api.example.com");
+        assert!(is_encrypted(&env.variables["token"]));
+        match &env.tls.client_certificate {
+            Some(ClientCertificate::Pkcs12 { file, password }) => {
+                assert_eq!(file, "client.p12");
+                assert!(is_encrypted(password));
+            }
+            certificate => panic!("unexpected certificate {:?}", certificate),
+        }
+        match &sealed.requests[0].auth {
+            Some(AuthData::Basic { username, password }) => {
+                assert_eq!(username, "me");
+                assert!(is_encrypted(password));
+            }
+            auth => panic!("unexpected auth {:?}", auth),
+        }
+        match &sealed.settings.auth {
+            Some(AuthData::Bearer { token }) => assert!(is_encrypted(token)),
+            auth => panic!("unexpected auth {:?}", auth),
+        }
+
+        // What the wrapped storage holds is encrypted...
+        inner.save_project(&sealed).unwrap();
+        let mut storage = SecretStorage::new(Box::new(inner), Some(Vault::from_passphrase("pass")));
+        // ...and comes back decrypted.
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.variables, project.variables);
+        assert_eq!(loaded.environments, project.environments);
+        assert_eq!(loaded.requests[0].auth, project.requests[0].auth);
+        assert_eq!(loaded.requests[0].variables, project.requests[0].variables);
+        assert_eq!(loaded.settings, project.settings);
+    }
+
+    #[test]
+    fn test_global_secrets_are_encrypted_at_rest() {
+        let globals = GlobalVariables {
+            variables: HashMap::from([
+                ("user".to_string(), "me".to_string()),
+                ("password".to_string(), "hunter2".to_string()),
+            ]),
+            secrets: BTreeSet::from(["password".to_string()]),
+        };
+        let mut storage = SecretStorage::new(
+            Box::new(SqliteStorage::in_memory().unwrap()),
+            Some(Vault::from_passphrase("pass")),
+        );
+        storage.save_global_variables(&globals).unwrap();
+
+        let stored = storage.inner.load_global_variables().unwrap();
+        assert_eq!(stored.variables["user"], "me");
+        assert!(is_encrypted(&stored.variables["password"]));
+        assert_eq!(storage.load_global_variables().unwrap(), globals);
+    }
+
+    #[test]
+    fn test_typed_values_that_look_encrypted_are_encrypted() {
+        let globals = GlobalVariables {
+            variables: HashMap::from([("password".to_string(), "enc:v1:typed".to_string())]),
+            secrets: BTreeSet::from(["password".to_string()]),
+        };
+        let mut storage = SecretStorage::new(
+            Box::new(SqliteStorage::in_memory().unwrap()),
+            Some(Vault::from_passphrase("pass")),
+        );
+        storage.save_global_variables(&globals).unwrap();
+
+        let stored = storage.inner.load_global_variables().unwrap();
+        assert_ne!(stored.variables["password"], "enc:v1:typed");
+        assert_eq!(storage.load_global_variables().unwrap(), globals);
+    }
+
+    #[test]
+    fn test_unchanged_secrets_keep_their_ciphertext() {
+        let root = std::env::temp_dir().join(format!("rurl-secrets-{}", uuid::Uuid::new_v4()));
+        let mut project = secret_project();
+        project
+            .requests
+            .push(RequestData::new("logout".to_string()));
+        let mut storage = SecretStorage::new(
+            Box::new(FileStorage::open(&root).unwrap()),
+            Some(Vault::from_passphrase("pass")),
+        );
+        storage.save_project(&project).unwrap();
+
+        let dir = root.join("secrets");
+        let read = |path: &str| fs::read(dir.join(path)).unwrap();
+        let env = read("environments/prod.toml");
+        let login_file = format!("requests/login-{}.toml", &project.requests[0].id[..8]);
+        let login = read(&login_file);
+
+        // An unrelated edit leaves the files holding secrets as they were...
+        let mut logout = project.requests[1].clone();
+        logout.name = "sign out".to_string();
+        let update = ProjectUpdate::UpdateRequest(logout);
+        project.apply_update(update.clone());
+        storage.apply_update(&project, &update).unwrap();
+        assert!(dir
+            .join(format!(
+                "requests/sign-out-{}.toml",
+                &project.requests[1].id[..8]
+            ))
+            .is_file());
+        assert_eq!(read("environments/prod.toml"), env);
+        assert_eq!(read(&login_file), login);
+
+        // ...as does reopening and saving again...
+        let mut storage = SecretStorage::new(
+            Box::new(FileStorage::open(&root).unwrap()),
+            Some(Vault::from_passphrase("pass")),
+        );
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        storage.save_project(&loaded).unwrap();
+        assert_eq!(read("environments/prod.toml"), env);
+
+        // ...while a changed secret is encrypted anew.
+        let mut prod = loaded.environments[0].clone();
+        prod.variables
+            .insert("token".to_string(), "n3w".to_string());
+        let update = ProjectUpdate::UpdateEnvironment(0, prod);
+        let mut loaded = loaded;
+        loaded.apply_update(update.clone());
+        storage.apply_update(&loaded, &update).unwrap();
+        assert_ne!(read("environments/prod.toml"), env);
+        assert_eq!(read(&login_file), login);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_locked_secrets_survive_saving() {
+        let project = secret_project();
+        let mut storage = SecretStorage::new(
+            Box::new(SqliteStorage::in_memory().unwrap()),
+            Some(Vault::from_passphrase("pass")),
+        );
+        storage.save_project(&project).unwrap();
+
+        // Without the key the values stay encrypted but can be saved again.
+        let SecretStorage { inner, .. } = storage;
+        let mut locked = SecretStorage::new(inner, Some(Vault::from_passphrase("wrong")));
+        let mut loaded = locked.load_project(&project.id).unwrap().unwrap();
+        assert!(is_encrypted(&loaded.environments[0].variables["token"]));
+        loaded.environments[0]
+            .variables
+            .insert("host".to_string(), "other.example.com".to_string());
+        let update = ProjectUpdate::UpdateEnvironment(0, loaded.environments[0].clone());
+        locked.apply_update(&loaded, &update).unwrap();
+
+        let SecretStorage { inner, .. } = locked;
+        let mut unlocked = SecretStorage::new(inner, Some(Vault::from_passphrase("pass")));
+        let loaded = unlocked.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.environments[0].variables["token"], "s3cret");
+        assert_eq!(
+            loaded.environments[0].variables["host"],
+            "other.example.com"
+        );
+    }
+
+    #[test]
+    fn test_without_secrets() {
+        let stripped = without_secrets(&secret_project());
+        assert_eq!(stripped.variables["api_key"], "");
+        assert_eq!(stripped.variables["version"], "v1");
+        assert_eq!(stripped.requests[0].variables["otp"], "");
+        assert_eq!(stripped.environments[0].variables["token"], "");
+        assert_eq!(
+            stripped.environments[0].variables["host"],
+            "api.example.com"
+        );
+        assert_eq!(
+            stripped.requests[0].auth,
+            Some(AuthData::Basic {
+                username: "me".to_string(),
+                password: String::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_tokens_are_encrypted_when_possible() {
+        let token = OAuthToken {
+            access_token: "a1".to_string(),
+            refresh_token: Some("r1".to_string()),
+            expires_at: None,
+        };
+        let mut storage = SecretStorage::new(
+            Box::new(SqliteStorage::in_memory().unwrap()),
+            Some(Vault::from_passphrase("pass")),
+        );
+        storage.save_token("key", &token).unwrap();
+        assert_eq!(storage.load_token("key").unwrap(), Some(token.clone()));
+
+        let SecretStorage { mut inner, .. } = storage;
+        let stored = inner.load_token("key").unwrap().unwrap();
+        assert!(is_encrypted(&stored.access_token));
+        let mut locked = SecretStorage::new(inner, None);
+        assert_eq!(locked.load_token("key").unwrap(), None);
+
+        // Without a key the token is still cached, as it is.
+        locked.save_token("key", &token).unwrap();
+        assert_eq!(locked.load_token("key").unwrap(), Some(token));
+    }
+}
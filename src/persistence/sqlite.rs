@@ -0,0 +1,1219 @@
+use rusqlite::{params, Connection, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+    migrations, Environment, Folder, GlobalVariables, OAuthToken, ProjectData, ProjectSettings,
+    ProjectUpdate, RequestData, ResponseData, Storage, StorageError, TlsSettings,
+};
+
+/// Keeps every project, and the response history, in a single SQLite database.
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    /// The database used when no path is configured:
+    /// `<data_local_dir>/rurl/rurl.db`.
+    pub fn default_path() -> Result<PathBuf, StorageError> {
+        let mut path = dirs::data_local_dir().ok_or(StorageError::NoDataDir)?;
+        path.push("rurl");
+        path.push("rurl.db");
+        Ok(path)
+    }
+
+    /// Opens (creating if needed) the database at `path` and brings its
+    /// schema up to date.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| StorageError::CreateDir(dir.to_path_buf(), e))?;
+        }
+
+        let conn = Connection::open(path).map_err(|e| StorageError::Open(path.to_path_buf(), e))?;
+        Self::from_connection(conn)
+    }
+
+    /// A private database that lives only as long as this `SqliteStorage`.
+    pub fn in_memory() -> Result<Self, StorageError> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| StorageError::Open(PathBuf::from(":memory:"), e))?;
+        Self::from_connection(conn)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self, StorageError> {
+        migrations::run(&mut conn)?;
+        Ok(Self { conn })
+    }
+
+    fn upsert_request(
+        conn: &Connection,
+        project_id: &str,
+        request: &RequestData,
+    ) -> Result<i64, StorageError> {
+        conn.execute(
+            "INSERT INTO requests (
+                uuid, project_id, name, method, url, body,
+                query_params, path_params, auth_data, secret_auth, variables, secrets, captures,
+                folder_id, position, created_at, updated_at, protocol
+            ) VALUES (
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18
+            )
+            ON CONFLICT(uuid) DO UPDATE SET
+                project_id = excluded.project_id,
+                name = excluded.name,
+                method = excluded.method,
+                url = excluded.url,
+                body = excluded.body,
+                query_params = excluded.query_params,
+                path_params = excluded.path_params,
+                auth_data = excluded.auth_data,
+                secret_auth = excluded.secret_auth,
+                variables = excluded.variables,
+                secrets = excluded.secrets,
+                captures = excluded.captures,
+                folder_id = excluded.folder_id,
+                position = excluded.position,
+                updated_at = excluded.updated_at,
+                protocol = excluded.protocol",
+            params![
+                request.id,
+                project_id,
+                request.name,
+                request.method,
+                request.url,
+                request.body,
+                serde_json::to_string(&request.query_params)?,
+                serde_json::to_string(&request.path_params)?,
+                serde_json::to_string(&request.auth)?,
+                request.secret_auth,
+                serde_json::to_string(&request.variables)?,
+                serde_json::to_string(&request.secrets)?,
+                serde_json::to_string(&request.captures)?,
+                request.folder_id,
+                request.position,
+                request.created_at,
+                request.updated_at,
+                serde_json::to_string(&request.protocol)?,
+            ],
+        )?;
+        let request_id = conn.query_row(
+            "SELECT id FROM requests WHERE uuid = ?1",
+            params![request.id],
+            |row| row.get::<_, i64>(0),
+        )?;
+
+        conn.execute(
+            "DELETE FROM headers WHERE request_id = ?1",
+            params![request_id],
+        )?;
+        if let Some(headers) = &request.headers {
+            for (name, value) in headers {
+                conn.execute(
+                    "INSERT INTO headers (request_id, name, value) VALUES (?1, ?2, ?3)",
+                    params![request_id, name, value],
+                )?;
+            }
+        }
+
+        Ok(request_id)
+    }
+
+    fn insert_folder(conn: &Connection, project_id: &str, folder: &Folder) -> Result<()> {
+        conn.execute(
+            "INSERT INTO folders (id, project_id, parent_id, name) VALUES (?1, ?2, ?3, ?4)",
+            params![folder.id, project_id, folder.parent_id, folder.name],
+        )?;
+        Ok(())
+    }
+
+    fn insert_environment(
+        conn: &Connection,
+        project_id: &str,
+        env: &Environment,
+        now: i64,
+    ) -> Result<i64, StorageError> {
+        conn.execute(
+            "INSERT INTO environments (project_id, name, tls, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![project_id, env.name, Self::tls_json(&env.tls)?, now, now],
+        )?;
+        let env_id = conn.last_insert_rowid();
+        Self::replace_environment_variables(conn, env_id, env)?;
+        Ok(env_id)
+    }
+
+    /// Environments without TLS settings leave the column empty.
+    fn tls_json(tls: &TlsSettings) -> Result<Option<String>, StorageError> {
+        match tls.is_default() {
+            true => Ok(None),
+            false => Ok(Some(serde_json::to_string(tls)?)),
+        }
+    }
+
+    fn replace_environment_variables(
+        conn: &Connection,
+        env_id: i64,
+        env: &Environment,
+    ) -> Result<()> {
+        conn.execute(
+            "DELETE FROM environment_variables WHERE environment_id = ?1",
+            params![env_id],
+        )?;
+        for (name, value) in &env.variables {
+            conn.execute(
+                "INSERT INTO environment_variables (environment_id, name, value, secret)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![env_id, name, value, env.secrets.contains(name)],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn replace_project_variables(
+        conn: &Connection,
+        project_id: &str,
+        variables: &HashMap<String, String>,
+        secrets: &BTreeSet<String>,
+    ) -> Result<()> {
+        conn.execute(
+            "DELETE FROM project_variables WHERE project_id = ?1",
+            params![project_id],
+        )?;
+        for (name, value) in variables {
+            conn.execute(
+                "INSERT INTO project_variables (project_id, name, value, secret)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![project_id, name, value, secrets.contains(name)],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Maps a position in `ProjectData.environments` to its row id. Environments
+    /// are loaded in row id order, so the two line up.
+    fn environment_row_id(
+        conn: &Connection,
+        project_id: &str,
+        index: usize,
+    ) -> Result<Option<i64>> {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM environments WHERE project_id = ?1 ORDER BY id LIMIT 1 OFFSET ?2",
+        )?;
+        match stmt.query_row(params![project_id, index as i64], |row| row.get(0)) {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn delete_request_rows(conn: &Connection, request_id: i64) -> Result<()> {
+        conn.execute(
+            "DELETE FROM request_history WHERE request_id = ?1",
+            params![request_id],
+        )?;
+        conn.execute(
+            "DELETE FROM headers WHERE request_id = ?1",
+            params![request_id],
+        )?;
+        conn.execute("DELETE FROM requests WHERE id = ?1", params![request_id])?;
+        Ok(())
+    }
+
+    fn find_request_id(&self, request_uuid: &str) -> Result<Option<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM requests WHERE uuid = ?1")?;
+        match stmt.query_row(params![request_uuid], |row| row.get(0)) {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Storage for SqliteStorage {
+    /// Writes the whole project in one transaction.
+    fn save_project(&mut self, project: &ProjectData) -> Result<(), StorageError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO projects (
+                id, name, active_environment, settings, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                project.id,
+                project.name,
+                project.active_environment,
+                serde_json::to_string(&project.settings)?,
+                project.created_at,
+                project.updated_at
+            ],
+        )?;
+
+        tx.execute(
+            "DELETE FROM environment_variables WHERE environment_id IN (
+                SELECT id FROM environments WHERE project_id = ?1
+            )",
+            params![project.id],
+        )?;
+        tx.execute(
+            "DELETE FROM environments WHERE project_id = ?1",
+            params![project.id],
+        )?;
+
+        for env in &project.environments {
+            Self::insert_environment(&tx, &project.id, env, project.updated_at)?;
+        }
+        Self::replace_project_variables(&tx, &project.id, &project.variables, &project.secrets)?;
+
+        tx.execute(
+            "DELETE FROM folders WHERE project_id = ?1",
+            params![project.id],
+        )?;
+        for folder in &project.folders {
+            Self::insert_folder(&tx, &project.id, folder)?;
+        }
+
+        // Requests are upserted by uuid so their row ids, which
+        // request_history refers to, survive every save.
+        let stored: Vec<(i64, String)> = tx
+            .prepare("SELECT id, uuid FROM requests WHERE project_id = ?1")?
+            .query_map(params![project.id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        for (request_id, uuid) in stored {
+            if !project.requests.iter().any(|r| r.id == uuid) {
+                Self::delete_request_rows(&tx, request_id)?;
+            }
+        }
+
+        for request in &project.requests {
+            Self::upsert_request(&tx, &project.id, request)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_project(&mut self, id: &str) -> Result<Option<ProjectData>, StorageError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, active_environment, created_at, updated_at, settings
+             FROM projects WHERE id = ?1",
+        )?;
+
+        let project = stmt.query_row(params![id], |row| {
+            Ok(ProjectData {
+                id: id.to_string(),
+                name: row.get(0)?,
+                requests: Vec::new(),
+                folders: Vec::new(),
+                environments: Vec::new(),
+                active_environment: row.get(1)?,
+                variables: HashMap::new(),
+                secrets: BTreeSet::new(),
+                settings: row
+                    .get::<_, Option<String>>(4)?
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default(),
+                created_at: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        });
+
+        let mut project = match project {
+            Ok(p) => p,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, tls FROM environments WHERE project_id = ?1 ORDER BY id")?;
+        let env_rows = stmt.query_map(params![id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                Environment {
+                    name: row.get(1)?,
+                    variables: HashMap::new(),
+                    secrets: BTreeSet::new(),
+                    tls: row
+                        .get::<_, Option<String>>(2)?
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or_default(),
+                },
+            ))
+        })?;
+
+        let mut environments = Vec::new();
+        for env_row in env_rows {
+            let (env_id, mut env) = env_row?;
+
+            let mut stmt = self.conn.prepare(
+                "SELECT name, value, secret FROM environment_variables WHERE environment_id = ?1",
+            )?;
+            let vars: Vec<(String, String, bool)> = stmt
+                .query_map(params![env_id], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for (name, value, secret) in vars {
+                if secret {
+                    env.secrets.insert(name.clone());
+                }
+                env.variables.insert(name, value);
+            }
+            environments.push(env);
+        }
+        project.environments = environments;
+
+        let variables: Vec<(String, String, bool)> = self
+            .conn
+            .prepare("SELECT name, value, secret FROM project_variables WHERE project_id = ?1")?
+            .query_map(params![id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<_>>()?;
+        for (name, value, secret) in variables {
+            if secret {
+                project.secrets.insert(name.clone());
+            }
+            project.variables.insert(name, value);
+        }
+
+        // Folders are always inserted in vector order, so rowid keeps it.
+        project.folders = self
+            .conn
+            .prepare(
+                "SELECT id, name, parent_id FROM folders WHERE project_id = ?1 ORDER BY rowid",
+            )?
+            .query_map(params![id], |row| {
+                Ok(Folder {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    parent_id: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, uuid, name, method, url, body, query_params, path_params, auth_data,
+                    folder_id, position, created_at, updated_at, variables, captures, secret_auth,
+                    protocol, secrets
+             FROM requests WHERE project_id = ?1 ORDER BY position, id",
+        )?;
+        let request_rows = stmt.query_map(params![id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                RequestData {
+                    id: row.get(1)?,
+                    name: row.get(2)?,
+                    method: row.get(3)?,
+                    url: row.get(4)?,
+                    headers: Some(Vec::new()),
+                    body: row.get(5)?,
+                    query_params: serde_json::from_str(&row.get::<_, String>(6)?).ok(),
+                    path_params: serde_json::from_str(&row.get::<_, String>(7)?).ok(),
+                    auth: serde_json::from_str(&row.get::<_, String>(8)?).ok(),
+                    secret_auth: row.get(15)?,
+                    variables: row
+                        .get::<_, Option<String>>(13)?
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or_default(),
+                    secrets: row
+                        .get::<_, Option<String>>(17)?
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or_default(),
+                    captures: row
+                        .get::<_, Option<String>>(14)?
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or_default(),
+                    protocol: row
+                        .get::<_, Option<String>>(16)?
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or_default(),
+                    folder_id: row.get(9)?,
+                    position: row.get(10)?,
+                    created_at: row.get(11)?,
+                    updated_at: row.get(12)?,
+                },
+            ))
+        })?;
+
+        let mut requests = Vec::new();
+        for request_row in request_rows {
+            let (request_id, mut request) = request_row?;
+
+            let mut stmt = self
+                .conn
+                .prepare("SELECT name, value FROM headers WHERE request_id = ?1")?;
+            let headers: Vec<(String, String)> = stmt
+                .query_map(params![request_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            request.headers = Some(headers);
+            requests.push(request);
+        }
+
+        project.requests = requests;
+        Ok(Some(project))
+    }
+
+    fn list_projects(&mut self) -> Result<Vec<ProjectData>, StorageError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name FROM projects ORDER BY id")?;
+
+        let projects = stmt
+            .query_map([], |row| {
+                Ok(ProjectData {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    requests: Vec::new(),
+                    folders: Vec::new(),
+                    environments: Vec::new(),
+                    active_environment: None,
+                    variables: HashMap::new(),
+                    secrets: BTreeSet::new(),
+                    settings: ProjectSettings::default(),
+                    created_at: 0,
+                    updated_at: 0,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(projects)
+    }
+
+    fn delete_project(&mut self, id: &str) -> Result<(), StorageError> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM request_history WHERE request_id IN (
+                SELECT id FROM requests WHERE project_id = ?1
+            )",
+            params![id],
+        )?;
+        tx.execute(
+            "DELETE FROM headers WHERE request_id IN (
+                SELECT id FROM requests WHERE project_id = ?1
+            )",
+            params![id],
+        )?;
+        tx.execute("DELETE FROM requests WHERE project_id = ?1", params![id])?;
+        tx.execute("DELETE FROM folders WHERE project_id = ?1", params![id])?;
+        tx.execute(
+            "DELETE FROM project_variables WHERE project_id = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "DELETE FROM environment_variables WHERE environment_id IN (
+                SELECT id FROM environments WHERE project_id = ?1
+            )",
+            params![id],
+        )?;
+        tx.execute(
+            "DELETE FROM environments WHERE project_id = ?1",
+            params![id],
+        )?;
+        tx.execute("DELETE FROM projects WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Persists a single [`ProjectUpdate`] with targeted statements instead of
+    /// rewriting the project.
+    fn apply_update(
+        &mut self,
+        project: &ProjectData,
+        update: &ProjectUpdate,
+    ) -> Result<(), StorageError> {
+        let project_id = project.id.as_str();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let tx = self.conn.transaction()?;
+
+        match update {
+            // The project's copy carries the position and timestamps that
+            // `ProjectData::apply_update` assigned.
+            ProjectUpdate::AddRequest(request) | ProjectUpdate::UpdateRequest(request) => {
+                if let Some(request) = project.requests.iter().find(|r| r.id == request.id) {
+                    Self::upsert_request(&tx, project_id, request)?;
+                }
+            }
+            ProjectUpdate::MoveRequest { id, folder_id, .. } => {
+                tx.execute(
+                    "UPDATE requests SET folder_id = ?1 WHERE uuid = ?2",
+                    params![folder_id, id],
+                )?;
+                for request in &project.requests {
+                    tx.execute(
+                        "UPDATE requests SET position = ?1 WHERE uuid = ?2",
+                        params![request.position, request.id],
+                    )?;
+                }
+            }
+            ProjectUpdate::DeleteRequest(uuid) => {
+                let request_id: Option<i64> = tx
+                    .prepare("SELECT id FROM requests WHERE uuid = ?1 AND project_id = ?2")?
+                    .query_map(params![uuid, project_id], |row| row.get(0))?
+                    .next()
+                    .transpose()?;
+                if let Some(request_id) = request_id {
+                    Self::delete_request_rows(&tx, request_id)?;
+                }
+            }
+            ProjectUpdate::UpdateName(name) => {
+                tx.execute(
+                    "UPDATE projects SET name = ?1 WHERE id = ?2",
+                    params![name, project_id],
+                )?;
+            }
+            ProjectUpdate::AddEnvironment(env) => {
+                Self::insert_environment(&tx, project_id, env, now)?;
+            }
+            ProjectUpdate::UpdateEnvironment(index, env) => {
+                if let Some(env_id) = Self::environment_row_id(&tx, project_id, *index)? {
+                    tx.execute(
+                        "UPDATE environments SET name = ?1, tls = ?2, updated_at = ?3 WHERE id = ?4",
+                        params![env.name, Self::tls_json(&env.tls)?, now, env_id],
+                    )?;
+                    Self::replace_environment_variables(&tx, env_id, env)?;
+                }
+            }
+            ProjectUpdate::DeleteEnvironment(index) => {
+                if let Some(env_id) = Self::environment_row_id(&tx, project_id, *index)? {
+                    tx.execute(
+                        "DELETE FROM environment_variables WHERE environment_id = ?1",
+                        params![env_id],
+                    )?;
+                    tx.execute("DELETE FROM environments WHERE id = ?1", params![env_id])?;
+                }
+            }
+            // Written with the project's timestamp below.
+            ProjectUpdate::SetActiveEnvironment(_) => {}
+            ProjectUpdate::UpdateVariables { variables, secrets } => {
+                Self::replace_project_variables(&tx, project_id, variables, secrets)?;
+            }
+            ProjectUpdate::UpdateSettings(settings) => {
+                tx.execute(
+                    "UPDATE projects SET settings = ?1 WHERE id = ?2",
+                    params![serde_json::to_string(settings)?, project_id],
+                )?;
+            }
+            ProjectUpdate::AddFolder(folder) => {
+                Self::insert_folder(&tx, project_id, folder)?;
+            }
+            ProjectUpdate::UpdateFolder(folder) => {
+                tx.execute(
+                    "UPDATE folders SET name = ?1, parent_id = ?2 WHERE id = ?3",
+                    params![folder.name, folder.parent_id, folder.id],
+                )?;
+            }
+            ProjectUpdate::DeleteFolder(folder_id) => {
+                tx.execute(
+                    "UPDATE requests SET folder_id = (SELECT parent_id FROM folders WHERE id = ?1)
+                     WHERE folder_id = ?1",
+                    params![folder_id],
+                )?;
+                tx.execute(
+                    "UPDATE folders SET parent_id = (SELECT parent_id FROM folders WHERE id = ?1)
+                     WHERE parent_id = ?1",
+                    params![folder_id],
+                )?;
+                tx.execute("DELETE FROM folders WHERE id = ?1", params![folder_id])?;
+            }
+        }
+
+        // Renaming or deleting an environment can change which one is
+        // active, so the column is written along with every update.
+        tx.execute(
+            "UPDATE projects SET active_environment = ?1, updated_at = ?2 WHERE id = ?3",
+            params![project.active_environment, now, project_id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_response(
+        &mut self,
+        request_uuid: &str,
+        response: &ResponseData,
+    ) -> Result<Option<ResponseData>, StorageError> {
+        let request_id = match self.find_request_id(request_uuid)? {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        self.conn.execute(
+            "INSERT INTO request_history (
+                request_id, status_code, response_body, response_headers,
+                response_time, timestamp, variables, hops, http_version
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                request_id,
+                response.status_code,
+                response.response_body,
+                serde_json::to_string(&response.response_headers)?,
+                response.response_time,
+                response.timestamp,
+                serde_json::to_string(&response.variables)?,
+                serde_json::to_string(&response.hops)?,
+                response.http_version,
+            ],
+        )?;
+
+        Ok(Some(ResponseData {
+            request_id,
+            ..response.clone()
+        }))
+    }
+
+    fn load_history(&mut self, request_uuid: &str) -> Result<Vec<ResponseData>, StorageError> {
+        let request_id = match self.find_request_id(request_uuid)? {
+            Some(id) => id,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT status_code, response_body, response_headers, response_time, timestamp,
+                    variables, hops, http_version
+             FROM request_history WHERE request_id = ?1 ORDER BY timestamp DESC, id DESC",
+        )?;
+        let history = stmt
+            .query_map(params![request_id], |row| {
+                Ok(ResponseData {
+                    request_id,
+                    status_code: row.get(0)?,
+                    response_body: row.get(1)?,
+                    response_headers: serde_json::from_str(&row.get::<_, String>(2)?)
+                        .unwrap_or(None),
+                    response_time: row.get(3)?,
+                    timestamp: row.get(4)?,
+                    http_version: row.get(7)?,
+                    variables: row
+                        .get::<_, Option<String>>(5)?
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or_default(),
+                    hops: row
+                        .get::<_, Option<String>>(6)?
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or_default(),
+                    signing: None,
+                    certificates: Vec::new(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(history)
+    }
+
+    fn load_global_variables(&mut self) -> Result<GlobalVariables, StorageError> {
+        let mut globals = GlobalVariables::default();
+        let variables: Vec<(String, String, bool)> = self
+            .conn
+            .prepare("SELECT name, value, secret FROM global_variables")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_>>()?;
+        for (name, value, secret) in variables {
+            if secret {
+                globals.secrets.insert(name.clone());
+            }
+            globals.variables.insert(name, value);
+        }
+        Ok(globals)
+    }
+
+    fn save_global_variables(&mut self, globals: &GlobalVariables) -> Result<(), StorageError> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM global_variables", [])?;
+        for (name, value) in &globals.variables {
+            tx.execute(
+                "INSERT INTO global_variables (name, value, secret) VALUES (?1, ?2, ?3)",
+                params![name, value, globals.secrets.contains(name)],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_token(&mut self, key: &str) -> Result<Option<OAuthToken>, StorageError> {
+        let token = self
+            .conn
+            .prepare(
+                "SELECT access_token, refresh_token, expires_at FROM oauth_tokens
+                 WHERE cache_key = ?1",
+            )?
+            .query_map(params![key], |row| {
+                Ok(OAuthToken {
+                    access_token: row.get(0)?,
+                    refresh_token: row.get(1)?,
+                    expires_at: row.get(2)?,
+                })
+            })?
+            .next()
+            .transpose()?;
+        Ok(token)
+    }
+
+    fn save_token(&mut self, key: &str, token: &OAuthToken) -> Result<(), StorageError> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO oauth_tokens (cache_key, access_token, refresh_token, expires_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![key, token.access_token, token.refresh_token, token.expires_at],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{
+        AuthData, Capture, CaptureSource, ClientCertificate, Hop, HttpProtocol, TlsVersion,
+    };
+
+    #[test]
+    fn test_project_crud() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+
+        let project = ProjectData {
+            name: "Test Project".to_string(),
+            id: "test-123".to_string(),
+            requests: vec![RequestData {
+                id: "test-request-123".to_string(),
+                name: "".to_string(),
+                method: Some("GET".to_string()),
+                url: Some("https://api.example.com".to_string()),
+                headers: Some(vec![(
+                    "Content-Type".to_string(),
+                    "application/json".to_string(),
+                )]),
+                body: None,
+                query_params: None,
+                path_params: None,
+                auth: None,
+                variables: HashMap::new(),
+                secrets: BTreeSet::new(),
+                captures: vec![Capture {
+                    variable: "token".to_string(),
+                    source: CaptureSource::JsonPath("$.token".to_string()),
+                }],
+                secret_auth: true,
+                protocol: HttpProtocol::H2c,
+                folder_id: None,
+                position: 0,
+                created_at: 0,
+                updated_at: 0,
+            }],
+            folders: Vec::new(),
+            environments: Vec::new(),
+            active_environment: None,
+            variables: HashMap::new(),
+            secrets: BTreeSet::new(),
+            settings: Default::default(),
+            created_at: 0,
+            updated_at: 0,
+        };
+
+        storage.save_project(&project).unwrap();
+
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(project.name, loaded.name);
+        assert_eq!(project.id, loaded.id);
+        assert_eq!(project.requests.len(), loaded.requests.len());
+        assert_eq!(project.requests[0].captures, loaded.requests[0].captures);
+        assert!(loaded.requests[0].secret_auth);
+        assert_eq!(loaded.requests[0].protocol, HttpProtocol::H2c);
+
+        let projects = storage.list_projects().unwrap();
+        assert!(projects.iter().any(|p| p.id == project.id));
+
+        storage.delete_project(&project.id).unwrap();
+        assert!(storage.load_project(&project.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_variable_scopes() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        assert_eq!(
+            storage.load_global_variables().unwrap(),
+            GlobalVariables::default()
+        );
+
+        let globals = GlobalVariables {
+            variables: HashMap::from([
+                ("user".to_string(), "me".to_string()),
+                ("password".to_string(), "hunter2".to_string()),
+            ]),
+            secrets: BTreeSet::from(["password".to_string()]),
+        };
+        storage.save_global_variables(&globals).unwrap();
+        assert_eq!(storage.load_global_variables().unwrap(), globals);
+        storage
+            .save_global_variables(&GlobalVariables::default())
+            .unwrap();
+        assert_eq!(
+            storage.load_global_variables().unwrap(),
+            GlobalVariables::default()
+        );
+
+        let mut project = ProjectData::new("Scoped".to_string());
+        let mut request = RequestData::new("get user".to_string());
+        request.variables = HashMap::from([
+            ("id".to_string(), "42".to_string()),
+            ("token".to_string(), "t0k3n".to_string()),
+        ]);
+        request.secrets = BTreeSet::from(["token".to_string()]);
+        project.requests.push(request);
+        storage.save_project(&project).unwrap();
+
+        let update = ProjectUpdate::UpdateVariables {
+            variables: HashMap::from([
+                ("host".to_string(), "api.example.com".to_string()),
+                ("api_key".to_string(), "k3y".to_string()),
+            ]),
+            secrets: BTreeSet::from(["api_key".to_string()]),
+        };
+        project.apply_update(update.clone());
+        storage.apply_update(&project, &update).unwrap();
+
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.variables, project.variables);
+        assert_eq!(loaded.secrets, project.secrets);
+        assert_eq!(loaded.requests[0].variables, project.requests[0].variables);
+        assert_eq!(loaded.requests[0].secrets, project.requests[0].secrets);
+    }
+
+    #[test]
+    fn test_project_settings() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let mut project = ProjectData::new("Defaults".to_string());
+        project.settings.base_url = "https://api.example.com/v1/".to_string();
+        storage.save_project(&project).unwrap();
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.settings, project.settings);
+
+        let update = ProjectUpdate::UpdateSettings(ProjectSettings {
+            base_url: "https://api.example.com".to_string(),
+            headers: vec![
+                ("Accept".to_string(), "application/json".to_string()),
+                ("X-Client".to_string(), "rurl".to_string()),
+            ],
+            auth: Some(AuthData::Bearer {
+                token: "{{token}}".to_string(),
+            }),
+            secret_auth: false,
+            connect_timeout: Some(2),
+            timeout: Some(30),
+            tls: TlsSettings {
+                ca_files: vec!["certs/ca.pem".to_string()],
+                client_certificate: Some(ClientCertificate::Pkcs12 {
+                    file: "certs/client.p12".to_string(),
+                    password: "changeit".to_string(),
+                }),
+                min_version: Some(TlsVersion::Tls13),
+                insecure_hosts: Vec::new(),
+            },
+        });
+        project.apply_update(update.clone());
+        storage.apply_update(&project, &update).unwrap();
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.settings, project.settings);
+
+        // Relative URLs join the base URL, the request's own headers win and
+        // only requests that inherit get the project's auth.
+        let mut request = RequestData::new("me".to_string());
+        request.url = Some("/users/me".to_string());
+        request.headers = Some(vec![("accept".to_string(), "text/plain".to_string())]);
+        let sent = loaded.settings.apply(&request);
+        assert_eq!(
+            sent.url.as_deref(),
+            Some("https://api.example.com/users/me")
+        );
+        assert_eq!(
+            sent.headers.unwrap(),
+            vec![
+                ("X-Client".to_string(), "rurl".to_string()),
+                ("accept".to_string(), "text/plain".to_string()),
+            ]
+        );
+        assert_eq!(sent.auth, project.settings.auth);
+
+        request.url = Some("https://other.example.com/".to_string());
+        request.auth = Some(AuthData::None);
+        let sent = loaded.settings.apply(&request);
+        assert_eq!(sent.url, request.url);
+        assert_eq!(sent.auth, Some(AuthData::None));
+    }
+
+    #[test]
+    fn test_open_creates_and_reopens_database() {
+        let dir = std::env::temp_dir().join(format!("rurl-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nested").join("rurl.db");
+
+        let project = ProjectData::new("On Disk".to_string());
+        SqliteStorage::open(&path)
+            .unwrap()
+            .save_project(&project)
+            .unwrap();
+
+        let mut reopened = SqliteStorage::open(&path).unwrap();
+        assert!(reopened.load_project(&project.id).unwrap().is_some());
+
+        drop(reopened);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let mut project = ProjectData::new("Incremental Project".to_string());
+        storage.save_project(&project).unwrap();
+
+        let mut apply = |project: &mut ProjectData, update: ProjectUpdate| {
+            project.apply_update(update.clone());
+            storage.apply_update(project, &update).unwrap();
+        };
+
+        let mut request = RequestData::new("get user".to_string());
+        request.headers = Some(vec![("Accept".to_string(), "*/*".to_string())]);
+        apply(&mut project, ProjectUpdate::AddRequest(request.clone()));
+        apply(
+            &mut project,
+            ProjectUpdate::AddRequest(RequestData::new("delete user".to_string())),
+        );
+        request.url = Some("https://example.com/users/1".to_string());
+        apply(&mut project, ProjectUpdate::UpdateRequest(request.clone()));
+        let deleted = project.requests[1].id.clone();
+        apply(&mut project, ProjectUpdate::DeleteRequest(deleted));
+
+        let env = |name: &str| Environment {
+            name: name.to_string(),
+            variables: HashMap::from([("host".to_string(), name.to_string())]),
+            secrets: BTreeSet::from(["host".to_string()]),
+            tls: Default::default(),
+        };
+        apply(&mut project, ProjectUpdate::AddEnvironment(env("dev")));
+        apply(&mut project, ProjectUpdate::AddEnvironment(env("staging")));
+        apply(&mut project, ProjectUpdate::AddEnvironment(env("prod")));
+        apply(
+            &mut project,
+            ProjectUpdate::SetActiveEnvironment(Some("staging".to_string())),
+        );
+        apply(
+            &mut project,
+            ProjectUpdate::UpdateEnvironment(
+                1,
+                Environment {
+                    tls: TlsSettings {
+                        insecure_hosts: vec!["stage.internal".to_string()],
+                        ..Default::default()
+                    },
+                    ..env("stage")
+                },
+            ),
+        );
+        apply(&mut project, ProjectUpdate::DeleteEnvironment(0));
+        apply(
+            &mut project,
+            ProjectUpdate::UpdateName("Renamed".to_string()),
+        );
+
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.name, "Renamed");
+        assert_eq!(loaded.requests.len(), 1);
+        assert_eq!(loaded.requests[0].id, request.id);
+        assert_eq!(loaded.requests[0].url, request.url);
+        assert_eq!(loaded.requests[0].headers, request.headers);
+        assert_eq!(loaded.environments, project.environments);
+        assert_eq!(loaded.active_environment, Some("stage".to_string()));
+
+        storage.delete_project(&project.id).unwrap();
+    }
+
+    #[test]
+    fn test_folders() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let mut project = ProjectData::new("Folder Project".to_string());
+        let users = Folder::new("users".to_string(), None);
+        let admin = Folder::new("admin".to_string(), Some(users.id.clone()));
+        let mut request = RequestData::new("ban user".to_string());
+        request.folder_id = Some(admin.id.clone());
+        project.folders = vec![users.clone(), admin.clone()];
+        project.requests.push(request.clone());
+        storage.save_project(&project).unwrap();
+
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.folders, project.folders);
+        assert_eq!(loaded.requests[0].folder_id, Some(admin.id.clone()));
+
+        let mut apply = |project: &mut ProjectData, update: ProjectUpdate| {
+            project.apply_update(update.clone());
+            storage.apply_update(project, &update).unwrap();
+        };
+        apply(
+            &mut project,
+            ProjectUpdate::AddFolder(Folder::new("audit".to_string(), Some(admin.id.clone()))),
+        );
+        apply(
+            &mut project,
+            ProjectUpdate::UpdateFolder(Folder {
+                name: "accounts".to_string(),
+                ..users.clone()
+            }),
+        );
+        apply(&mut project, ProjectUpdate::DeleteFolder(admin.id.clone()));
+
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        assert_eq!(loaded.folders, project.folders);
+        assert_eq!(loaded.folders[0].name, "accounts");
+        assert_eq!(loaded.folders[1].parent_id, Some(users.id.clone()));
+        assert_eq!(loaded.requests[0].folder_id, Some(users.id.clone()));
+
+        storage.delete_project(&project.id).unwrap();
+    }
+
+    #[test]
+    fn test_request_order() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let mut project = ProjectData::new("Ordered Project".to_string());
+        let folder = Folder::new("users".to_string(), None);
+        project.folders.push(folder.clone());
+        storage.save_project(&project).unwrap();
+
+        let mut apply = |project: &mut ProjectData, update: ProjectUpdate| {
+            project.apply_update(update.clone());
+            storage.apply_update(project, &update).unwrap();
+        };
+        for name in ["a", "b", "c"] {
+            apply(
+                &mut project,
+                ProjectUpdate::AddRequest(RequestData::new(name.to_string())),
+            );
+        }
+        let moved = project.requests[2].clone();
+        apply(
+            &mut project,
+            ProjectUpdate::MoveRequest {
+                id: moved.id.clone(),
+                folder_id: Some(folder.id.clone()),
+                index: 0,
+            },
+        );
+        // A stale copy, as held by the editor, must not undo the move.
+        apply(
+            &mut project,
+            ProjectUpdate::UpdateRequest(RequestData {
+                url: Some("https://example.com".to_string()),
+                ..moved
+            }),
+        );
+
+        let loaded = storage.load_project(&project.id).unwrap().unwrap();
+        let names: Vec<&str> = loaded.requests.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["c", "a", "b"]);
+        assert_eq!(loaded.requests[0].folder_id, Some(folder.id));
+        assert_eq!(
+            loaded.requests[0].url,
+            Some("https://example.com".to_string())
+        );
+
+        storage.delete_project(&project.id).unwrap();
+    }
+
+    #[test]
+    fn test_request_history() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+
+        let mut project = ProjectData::new("History Project".to_string());
+        let request = RequestData::new("list users".to_string());
+        let request_id = request.id.clone();
+        project.requests.push(request);
+        storage.save_project(&project).unwrap();
+
+        for (status_code, timestamp) in [(200, 10), (500, 20)] {
+            let response = ResponseData {
+                request_id: 0,
+                status_code: Some(status_code),
+                response_body: Some("{}".to_string()),
+                response_headers: Some(vec![("a".to_string(), "b".to_string())]),
+                response_time: 12,
+                timestamp,
+                http_version: Some("HTTP/1.1".to_string()),
+                variables: vec![("$uuid".to_string(), format!("id-{}", timestamp))],
+                hops: vec![Hop {
+                    status_code: 401,
+                    response_headers: vec![(
+                        "www-authenticate".to_string(),
+                        r#"Digest realm="r", nonce="n""#.to_string(),
+                    )],
+                    response_time: 3,
+                }],
+                signing: None,
+                certificates: Vec::new(),
+            };
+            let saved = storage
+                .save_response(&request_id, &response)
+                .unwrap()
+                .unwrap();
+            assert_ne!(saved.request_id, 0);
+        }
+
+        // Renaming and re-saving must keep the request's history attached.
+        let mut renamed = project.requests[0].clone();
+        renamed.name = "list all users".to_string();
+        project.apply_update(ProjectUpdate::UpdateRequest(renamed));
+        storage.save_project(&project).unwrap();
+
+        let history = storage.load_history(&request_id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].status_code, Some(500));
+        assert_eq!(
+            history[1].response_headers,
+            Some(vec![("a".to_string(), "b".to_string())])
+        );
+        assert_eq!(
+            history[1].variables,
+            vec![("$uuid".to_string(), "id-10".to_string())]
+        );
+        assert_eq!(history[1].http_version.as_deref(), Some("HTTP/1.1"));
+        assert_eq!(history[1].hops[0].status_code, 401);
+        assert_eq!(history[1].hops[0].response_headers.len(), 1);
+
+        assert!(storage
+            .save_response("missing", &history[0])
+            .unwrap()
+            .is_none());
+
+        project.apply_update(ProjectUpdate::DeleteRequest(request_id.clone()));
+        storage.save_project(&project).unwrap();
+        assert!(storage.load_history(&request_id).unwrap().is_empty());
+
+        storage.delete_project(&project.id).unwrap();
+    }
+
+    #[test]
+    fn test_oauth_tokens() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        assert!(storage.load_token("key").unwrap().is_none());
+
+        let mut token = OAuthToken {
+            access_token: "a1".to_string(),
+            refresh_token: Some("r1".to_string()),
+            expires_at: Some(1_700_000_000),
+        };
+        storage.save_token("key", &token).unwrap();
+        assert_eq!(storage.load_token("key").unwrap(), Some(token.clone()));
+
+        token.access_token = "a2".to_string();
+        token.refresh_token = None;
+        storage.save_token("key", &token).unwrap();
+        assert_eq!(storage.load_token("key").unwrap(), Some(token));
+        assert!(storage.load_token("other").unwrap().is_none());
+    }
+}
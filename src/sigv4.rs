@@ -0,0 +1,345 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::persistence::Signing;
+
+/// Everything but the unreserved characters, as AWS encodes URIs.
+const URI_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// The credentials and scope a request is signed with.
+pub struct Signer<'a> {
+    pub access_key: &'a str,
+    pub secret_key: &'a str,
+    /// Temporary credentials also carry a session token.
+    pub session_token: &'a str,
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+impl Signer<'_> {
+    /// Signs a request as it will be sent. Returns the headers to add,
+    /// `Authorization` last, and what was signed.
+    pub fn sign(
+        &self,
+        method: &str,
+        url: &Url,
+        headers: &[(String, String)],
+        body: &[u8],
+        time: DateTime<Utc>,
+    ) -> (Vec<(String, String)>, Signing) {
+        let amz_date = time.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = &amz_date[..8];
+        let payload_hash = hex(&Sha256::digest(body));
+
+        let mut added = vec![("x-amz-date".to_string(), amz_date.clone())];
+        // S3 refuses requests without it; other services ignore it.
+        if self.service == "s3" {
+            added.push(("x-amz-content-sha256".to_string(), payload_hash.clone()));
+        }
+        if !self.session_token.is_empty() {
+            added.push((
+                "x-amz-security-token".to_string(),
+                self.session_token.to_string(),
+            ));
+        }
+
+        // Lowercased names with their values in order, as AWS folds them.
+        let mut signed: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        if !headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("host"))
+        {
+            signed.insert("host".to_string(), vec![host(url)]);
+        }
+        for (name, value) in headers.iter().chain(&added) {
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            signed.entry(name.to_lowercase()).or_default().push(value);
+        }
+        let canonical_headers: String = signed
+            .iter()
+            .map(|(name, values)| format!("{}:{}\n", name, values.join(",")))
+            .collect();
+        let signed_headers = signed.keys().cloned().collect::<Vec<_>>().join(";");
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            self.canonical_uri(url),
+            canonical_query(url),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = [date, self.region, self.service, "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.secret_key).into_bytes(),
+                |key, part| hmac(&key, part.as_bytes()),
+            );
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+        added.push((
+            "Authorization".to_string(),
+            format!(
+                "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+                ALGORITHM, self.access_key, scope, signed_headers, signature
+            ),
+        ));
+        (
+            added,
+            Signing {
+                canonical_request,
+                string_to_sign,
+            },
+        )
+    }
+
+    /// Each path segment encoded the AWS way: once for S3, twice for every
+    /// other service.
+    fn canonical_uri(&self, url: &Url) -> String {
+        let path = url.path();
+        if path.is_empty() || path == "/" {
+            return "/".to_string();
+        }
+        path.split('/')
+            .map(|segment| {
+                let segment = percent_decode_str(segment).decode_utf8_lossy();
+                let once = utf8_percent_encode(&segment, URI_ENCODE).to_string();
+                if self.service == "s3" {
+                    once
+                } else {
+                    utf8_percent_encode(&once, URI_ENCODE).to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// `canonical_request` with the values of the session token header and of
+/// `secret_headers` replaced by `mask`, for showing on screen.
+pub fn mask_headers(canonical_request: &str, secret_headers: &[String], mask: &str) -> String {
+    // The headers follow the method, path and query, up to a blank line.
+    let mut in_headers = true;
+    canonical_request
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            if index < 3 || !in_headers {
+                return line.to_string();
+            }
+            if line.is_empty() {
+                in_headers = false;
+                return line.to_string();
+            }
+            match line.split_once(':') {
+                Some((name, _))
+                    if name == "x-amz-security-token"
+                        || secret_headers.iter().any(|s| s.eq_ignore_ascii_case(name)) =>
+                {
+                    format!("{}:{}", name, mask)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The `Host` header reqwest will send, which is signed but not yet set.
+fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+fn canonical_query(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            (
+                utf8_percent_encode(&name, URI_ENCODE).to_string(),
+                utf8_percent_encode(&value, URI_ENCODE).to_string(),
+            )
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// The credentials and time used throughout the AWS SigV4 test suite.
+    const SIGNER: Signer = Signer {
+        access_key: "AKIDEXAMPLE",
+        secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        session_token: "",
+        region: "us-east-1",
+        service: "service",
+    };
+
+    fn time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap()
+    }
+
+    fn authorization(headers: &[(String, String)]) -> &str {
+        &headers.last().unwrap().1
+    }
+
+    #[test]
+    fn test_aws_suite_get_vanilla() {
+        let url = Url::parse("https://example.amazonaws.com/").unwrap();
+        let (headers, signing) = SIGNER.sign("GET", &url, &[], b"", time());
+        assert_eq!(
+            signing.canonical_request,
+            "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            signing.string_to_sign,
+            "AWS4-HMAC-SHA256\n20150830T123600Z\n20150830/us-east-1/service/aws4_request\n\
+             bb579772317eb040ac9ed261061d46c1f17a8133879d6129b6e1c25292927e63"
+        );
+        assert_eq!(
+            headers[0],
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string())
+        );
+        assert_eq!(
+            authorization(&headers),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_aws_suite_query_order() {
+        let url = Url::parse("https://example.amazonaws.com/?Param2=value2&Param1=value1").unwrap();
+        let (headers, signing) = SIGNER.sign("GET", &url, &[], b"", time());
+        assert!(signing
+            .canonical_request
+            .starts_with("GET\n/\nParam1=value1&Param2=value2\n"));
+        assert!(authorization(&headers).ends_with(
+            "Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        ));
+    }
+
+    #[test]
+    fn test_canonical_parts() {
+        let url = Url::parse("http://localhost:8080/a b/c%2Fd?x=1%202&a=").unwrap();
+        let headers = [
+            ("Content-Type".to_string(), "application/json".to_string()),
+            ("X-Note".to_string(), "  two   words ".to_string()),
+        ];
+        let signer = Signer {
+            session_token: "token",
+            ..SIGNER
+        };
+        let (added, signing) = signer.sign("POST", &url, &headers, b"{}", time());
+        let lines: Vec<&str> = signing.canonical_request.lines().collect();
+        assert_eq!(lines[1], "/a%2520b/c%252Fd");
+        assert_eq!(lines[2], "a=&x=1%202");
+        assert_eq!(
+            lines[3..8],
+            [
+                "content-type:application/json",
+                "host:localhost:8080",
+                "x-amz-date:20150830T123600Z",
+                "x-amz-security-token:token",
+                "x-note:two words",
+            ]
+        );
+        assert_eq!(
+            lines[9],
+            "content-type;host;x-amz-date;x-amz-security-token;x-note"
+        );
+        assert!(added.contains(&("x-amz-security-token".to_string(), "token".to_string())));
+
+        let s3 = Signer {
+            service: "s3",
+            ..SIGNER
+        };
+        let (added, signing) = s3.sign("PUT", &url, &[], b"", time());
+        assert!(signing.canonical_request.contains("\n/a%20b/c%2Fd\n"));
+        assert!(added.iter().any(|(name, _)| name == "x-amz-content-sha256"));
+    }
+
+    #[test]
+    fn test_mask_headers() {
+        let url = Url::parse("https://example.amazonaws.com/?x-api-key=q").unwrap();
+        let headers = [
+            ("X-Api-Key".to_string(), "k3y".to_string()),
+            ("Accept".to_string(), "*/*".to_string()),
+        ];
+        let signer = Signer {
+            session_token: "t0ken",
+            ..SIGNER
+        };
+        let (_, signing) = signer.sign("GET", &url, &headers, b"", time());
+        let masked = mask_headers(
+            &signing.canonical_request,
+            &["x-api-key".to_string()],
+            "***",
+        );
+        assert!(!masked.contains("t0ken") && !masked.contains("k3y"));
+        let lines: Vec<&str> = masked.lines().collect();
+        assert_eq!(lines[2], "x-api-key=q");
+        assert_eq!(
+            lines[3..8],
+            [
+                "accept:*/*",
+                "host:example.amazonaws.com",
+                "x-amz-date:20150830T123600Z",
+                "x-amz-security-token:***",
+                "x-api-key:***",
+            ]
+        );
+        // Everything after the headers is left alone.
+        assert_eq!(
+            masked.lines().skip(8).collect::<Vec<_>>(),
+            signing
+                .canonical_request
+                .lines()
+                .skip(8)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,530 @@
+use p12_keystore::{KeyStore, Pkcs12ImportPolicy};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::ring;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use x509_cert::der::Decode;
+use x509_cert::Certificate;
+
+use crate::persistence::{CertificateInfo, ClientCertificate, TlsSettings, TlsVersion};
+
+/// The certificates the server presented in the last handshake.
+pub type Chain = Arc<Mutex<Vec<CertificateDer<'static>>>>;
+
+/// The configuration requests are sent with, and where the certificates
+/// presented to it end up. The protocols to offer are left to the caller.
+pub fn client_config(settings: &TlsSettings) -> Result<(ClientConfig, Chain), String> {
+    let provider = Arc::new(ring::default_provider());
+
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    for file in &settings.ca_files {
+        let certs = CertificateDer::pem_file_iter(file)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("CA file {}: {}", file, e))?;
+        if certs.is_empty() {
+            return Err(format!("CA file {}: no certificates found", file));
+        }
+        for cert in certs {
+            roots
+                .add(cert)
+                .map_err(|e| format!("CA file {}: {}", file, e))?;
+        }
+    }
+    let webpki = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let chain = Chain::default();
+    let verifier = Recorder {
+        webpki,
+        insecure_hosts: settings.insecure_hosts.clone(),
+        chain: chain.clone(),
+    };
+
+    let versions: &[&rustls::SupportedProtocolVersion] = match settings.min_version {
+        Some(TlsVersion::Tls13) => &[&rustls::version::TLS13],
+        Some(TlsVersion::Tls12) | None => rustls::DEFAULT_VERSIONS,
+    };
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(versions)
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier));
+    let config = match &settings.client_certificate {
+        Some(certificate) => {
+            let (certs, key) = identity(certificate)?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|e| format!("client certificate: {}", e))?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    Ok((config, chain))
+}
+
+/// The certificate chain and private key in `certificate`'s files.
+fn identity(
+    certificate: &ClientCertificate,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), String> {
+    match certificate {
+        ClientCertificate::Pem {
+            cert_file,
+            key_file,
+        } => {
+            let certs = CertificateDer::pem_file_iter(cert_file)
+                .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("client certificate {}: {}", cert_file, e))?;
+            let key_file = if key_file.is_empty() {
+                cert_file
+            } else {
+                key_file
+            };
+            let key = PrivateKeyDer::from_pem_file(key_file)
+                .map_err(|e| format!("client key {}: {}", key_file, e))?;
+            Ok((certs, key))
+        }
+        ClientCertificate::Pkcs12 { file, password } => {
+            let data = fs::read(file).map_err(|e| format!("client certificate {}: {}", file, e))?;
+            let store = KeyStore::from_pkcs12(&data, password, Pkcs12ImportPolicy::Relaxed)
+                .map_err(|e| format!("client certificate {}: {}", file, e))?;
+            let (_, chain) = store
+                .private_key_chain()
+                .ok_or_else(|| format!("client certificate {}: no private key found", file))?;
+            let certs = chain
+                .certs()
+                .iter()
+                .map(|cert| CertificateDer::from(cert.as_der().to_vec()))
+                .collect();
+            let key = PrivateKeyDer::Pkcs8(chain.key().as_der().to_vec().into());
+            Ok((certs, key))
+        }
+    }
+}
+
+/// Subject, issuer and validity of each certificate in `chain`. Ones that
+/// cannot be parsed are left out.
+pub fn describe(chain: &[CertificateDer]) -> Vec<CertificateInfo> {
+    chain
+        .iter()
+        .filter_map(|der| Certificate::from_der(der).ok())
+        .map(|cert| {
+            let tbs = cert.tbs_certificate;
+            CertificateInfo {
+                subject: tbs.subject.to_string(),
+                issuer: tbs.issuer.to_string(),
+                not_before: tbs.validity.not_before.to_unix_duration().as_secs() as i64,
+                not_after: tbs.validity.not_after.to_unix_duration().as_secs() as i64,
+            }
+        })
+        .collect()
+}
+
+/// Verifies servers as usual, except for the insecure hosts, and keeps what
+/// each one presented.
+#[derive(Debug)]
+struct Recorder {
+    webpki: Arc<WebPkiServerVerifier>,
+    insecure_hosts: Vec<String>,
+    chain: Chain,
+}
+
+impl ServerCertVerifier for Recorder {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        *self.chain.lock().unwrap() = std::iter::once(end_entity)
+            .chain(intermediates)
+            .map(|cert| cert.clone().into_owned())
+            .collect();
+
+        let host = server_name.to_str();
+        if self
+            .insecure_hosts
+            .iter()
+            .any(|insecure| insecure.eq_ignore_ascii_case(&host))
+        {
+            return Ok(ServerCertVerified::assertion());
+        }
+        self.webpki
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.webpki.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.webpki.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.webpki.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{execute, ClientOptions};
+    use crate::persistence::{HttpProtocol, RequestData, ResponseData};
+    use p12_keystore::{KeyStoreEntry, PrivateKey, PrivateKeyChain};
+    use rcgen::{
+        BasicConstraints, CertificateParams, CertifiedIssuer, DnType, ExtendedKeyUsagePurpose,
+        IsCa, KeyPair,
+    };
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::{ServerConfig, ServerConnection, StreamOwned};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::thread;
+
+    /// A CA with a server certificate for localhost and a client certificate,
+    /// written to a temporary directory as PEM.
+    struct Pki {
+        dir: PathBuf,
+        server: ServerConfig,
+        client_cert: String,
+        client_key: Vec<u8>,
+    }
+
+    impl Pki {
+        fn new(versions: &[&'static rustls::SupportedProtocolVersion]) -> Self {
+            let dir = std::env::temp_dir().join(format!("rurl-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+
+            let mut params = CertificateParams::new(Vec::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params
+                .distinguished_name
+                .push(DnType::CommonName, "rurl test CA");
+            let ca = CertifiedIssuer::self_signed(params, KeyPair::generate().unwrap()).unwrap();
+            fs::write(dir.join("ca.pem"), ca.pem()).unwrap();
+
+            let server_key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+            params
+                .distinguished_name
+                .push(DnType::CommonName, "localhost");
+            params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+            let server_cert = params.signed_by(&server_key, &*ca).unwrap();
+
+            let client_key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(Vec::new()).unwrap();
+            params
+                .distinguished_name
+                .push(DnType::CommonName, "rurl client");
+            params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+            let client_cert = params.signed_by(&client_key, &*ca).unwrap();
+            fs::write(dir.join("client.pem"), client_cert.pem()).unwrap();
+            fs::write(dir.join("client.key"), client_key.serialize_pem()).unwrap();
+
+            let provider = Arc::new(ring::default_provider());
+            let mut roots = RootCertStore::empty();
+            roots.add(ca.der().clone()).unwrap();
+            let verifier =
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                    .build()
+                    .unwrap();
+            let server = ServerConfig::builder_with_provider(provider)
+                .with_protocol_versions(versions)
+                .unwrap()
+                .with_client_cert_verifier(verifier)
+                .with_single_cert(
+                    vec![server_cert.der().clone(), ca.der().clone()],
+                    PrivateKeyDer::Pkcs8(server_key.serialize_der().into()),
+                )
+                .unwrap();
+
+            Pki {
+                dir,
+                server,
+                client_cert: client_cert.pem(),
+                client_key: client_key.serialize_der(),
+            }
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.dir.join(name).to_string_lossy().to_string()
+        }
+
+        fn pem_identity(&self) -> ClientCertificate {
+            ClientCertificate::Pem {
+                cert_file: self.path("client.pem"),
+                key_file: self.path("client.key"),
+            }
+        }
+
+        /// Serves `connections` HTTPS connections, answering each with "ok"
+        /// over HTTP/1.1. Failed handshakes count as connections too. The
+        /// server returns the protocol agreed on for each that succeeded.
+        fn serve(&self, connections: usize) -> (u16, thread::JoinHandle<Vec<Option<Vec<u8>>>>) {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let config = Arc::new(self.server.clone());
+            let server = thread::spawn(move || {
+                let mut protocols = Vec::new();
+                for stream in listener.incoming().take(connections) {
+                    let connection = ServerConnection::new(config.clone()).unwrap();
+                    let mut tls = StreamOwned::new(connection, stream.unwrap());
+                    let mut buf = [0u8; 4096];
+                    if tls.read(&mut buf).is_err() {
+                        continue;
+                    }
+                    protocols.push(tls.conn.alpn_protocol().map(<[u8]>::to_vec));
+                    let _ = tls.write_all(
+                        b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                    );
+                    tls.conn.send_close_notify();
+                    let _ = tls.flush();
+                }
+                protocols
+            });
+            (port, server)
+        }
+    }
+
+    impl Drop for Pki {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn get(port: u16, tls: TlsSettings) -> Result<ResponseData, String> {
+        get_over(port, tls, HttpProtocol::Auto)
+    }
+
+    fn get_over(
+        port: u16,
+        tls: TlsSettings,
+        protocol: HttpProtocol,
+    ) -> Result<ResponseData, String> {
+        let mut request = RequestData::new("tls".to_string());
+        request.url = Some(format!("https://localhost:{}/", port));
+        request.protocol = protocol;
+        let options = ClientOptions {
+            tls,
+            ..Default::default()
+        };
+        execute(&request, &options).map_err(|e| format!("{:?}", e))
+    }
+
+    #[test]
+    fn test_merge() {
+        let global = TlsSettings {
+            ca_files: vec!["corp.pem".to_string()],
+            min_version: Some(TlsVersion::Tls12),
+            ..Default::default()
+        };
+        let project = TlsSettings {
+            ca_files: vec!["corp.pem".to_string(), "project.pem".to_string()],
+            insecure_hosts: vec!["localhost".to_string()],
+            ..Default::default()
+        };
+        let env = TlsSettings {
+            client_certificate: Some(ClientCertificate::Pem {
+                cert_file: "client.pem".to_string(),
+                key_file: String::new(),
+            }),
+            min_version: Some(TlsVersion::Tls13),
+            ..Default::default()
+        };
+        let merged = global.merge(&project).merge(&env);
+        assert_eq!(merged.ca_files, vec!["corp.pem", "project.pem"]);
+        assert_eq!(merged.insecure_hosts, vec!["localhost"]);
+        assert_eq!(merged.client_certificate, env.client_certificate);
+        assert_eq!(merged.min_version, Some(TlsVersion::Tls13));
+        assert!(TlsSettings::default().is_default());
+        assert!(!merged.is_default());
+    }
+
+    #[test]
+    fn test_identity_formats() {
+        let pki = Pki::new(rustls::DEFAULT_VERSIONS);
+        let (certs, key) = identity(&pki.pem_identity()).unwrap();
+        assert_eq!(certs.len(), 1);
+        assert_eq!(key.secret_der(), pki.client_key.as_slice());
+
+        // A single file holding both is read for each.
+        let both = pki.path("both.pem");
+        fs::write(
+            &both,
+            format!(
+                "{}{}",
+                pki.client_cert,
+                fs::read_to_string(pki.path("client.key")).unwrap()
+            ),
+        )
+        .unwrap();
+        let (combined, _) = identity(&ClientCertificate::Pem {
+            cert_file: both,
+            key_file: String::new(),
+        })
+        .unwrap();
+        assert_eq!(combined, certs);
+
+        let mut store = KeyStore::new();
+        store.add_entry(
+            "client",
+            KeyStoreEntry::PrivateKeyChain(PrivateKeyChain::new(
+                b"client".as_slice(),
+                PrivateKey::from_der(&pki.client_key).unwrap(),
+                [p12_keystore::Certificate::from_der(&certs[0]).unwrap()],
+            )),
+        );
+        let file = pki.path("client.p12");
+        fs::write(&file, store.writer("changeit").write().unwrap()).unwrap();
+        let (p12_certs, p12_key) = identity(&ClientCertificate::Pkcs12 {
+            file: file.clone(),
+            password: "changeit".to_string(),
+        })
+        .unwrap();
+        assert_eq!(p12_certs, certs);
+        assert_eq!(p12_key.secret_der(), pki.client_key.as_slice());
+
+        assert!(identity(&ClientCertificate::Pkcs12 {
+            file,
+            password: "wrong".to_string(),
+        })
+        .is_err());
+        assert!(identity(&ClientCertificate::Pem {
+            cert_file: pki.path("missing.pem"),
+            key_file: String::new(),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_client_config_errors() {
+        let pki = Pki::new(rustls::DEFAULT_VERSIONS);
+        let empty = pki.path("empty.pem");
+        fs::write(&empty, "").unwrap();
+        let error = client_config(&TlsSettings {
+            ca_files: vec![empty],
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(error.contains("no certificates found"), "{}", error);
+    }
+
+    #[test]
+    fn test_local_server() {
+        let pki = Pki::new(rustls::DEFAULT_VERSIONS);
+        let (port, server) = pki.serve(4);
+
+        // Unknown to the built-in roots, the server is refused...
+        let error = get(
+            port,
+            TlsSettings {
+                client_certificate: Some(pki.pem_identity()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(error.contains("UnknownIssuer"), "{}", error);
+
+        // ...until its CA is trusted, and a client certificate is required.
+        let trusted = TlsSettings {
+            ca_files: vec![pki.path("ca.pem")],
+            ..Default::default()
+        };
+        assert!(get(port, trusted.clone()).is_err());
+
+        let response = get(
+            port,
+            TlsSettings {
+                client_certificate: Some(pki.pem_identity()),
+                ..trusted
+            },
+        )
+        .unwrap();
+        assert_eq!(response.status_code, Some(200));
+        assert_eq!(response.certificates.len(), 2);
+        assert_eq!(response.certificates[0].subject, "CN=localhost");
+        assert_eq!(response.certificates[0].issuer, "CN=rurl test CA");
+        assert_eq!(response.certificates[1].subject, "CN=rurl test CA");
+        assert!(response.certificates[0].not_before < response.certificates[0].not_after);
+
+        // An insecure host skips verification but still records the chain.
+        let response = get(
+            port,
+            TlsSettings {
+                client_certificate: Some(pki.pem_identity()),
+                insecure_hosts: vec!["LOCALHOST".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(response.status_code, Some(200));
+        assert_eq!(response.certificates[0].subject, "CN=localhost");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_minimum_version() {
+        let pki = Pki::new(&[&rustls::version::TLS12]);
+        let (port, server) = pki.serve(2);
+        let settings = TlsSettings {
+            ca_files: vec![pki.path("ca.pem")],
+            client_certificate: Some(pki.pem_identity()),
+            ..Default::default()
+        };
+        let response = get(port, settings.clone()).unwrap();
+        assert_eq!(response.status_code, Some(200));
+
+        let error = get(
+            port,
+            TlsSettings {
+                min_version: Some(TlsVersion::Tls13),
+                ..settings
+            },
+        )
+        .unwrap_err();
+        assert!(error.contains("ProtocolVersion"), "{}", error);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_protocol_negotiation() {
+        let mut pki = Pki::new(rustls::DEFAULT_VERSIONS);
+        pki.server.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        let (port, server) = pki.serve(2);
+        let settings = TlsSettings {
+            ca_files: vec![pki.path("ca.pem")],
+            client_certificate: Some(pki.pem_identity()),
+            ..Default::default()
+        };
+
+        // Forcing HTTP/1.1 leaves HTTP/2 unoffered...
+        let response = get_over(port, settings.clone(), HttpProtocol::Http1).unwrap();
+        assert_eq!(response.http_version.as_deref(), Some("HTTP/1.1"));
+        // ...while by default the server picks it, though this one only
+        // pretends to speak it.
+        assert!(get(port, settings).is_err());
+
+        assert_eq!(
+            server.join().unwrap(),
+            vec![Some(b"http/1.1".to_vec()), Some(b"h2".to_vec())]
+        );
+    }
+}
@@ -0,0 +1,511 @@
+use chrono::{SecondsFormat, Utc};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::auth::{self, Input};
+use crate::persistence::{AuthData, RequestData};
+
+/// Where a variable's value comes from. A name defined in several scopes
+/// takes its value from the first of them in this order, so a request's own
+/// variables beat the active environment's, which beat the project's, which
+/// beat the global ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Request,
+    Environment,
+    Project,
+    Global,
+}
+
+impl Scope {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Scope::Request => "request",
+            Scope::Environment => "environment",
+            Scope::Project => "project",
+            Scope::Global => "global",
+        }
+    }
+}
+
+/// Shown wherever the value of a secret would be.
+pub const MASK: &str = "••••••••";
+
+/// The variables every request in a project can see: the global ones, the
+/// project's and the active environment's. A request's own variables are
+/// layered on top when resolving it.
+#[derive(Debug, Default, Clone)]
+pub struct Scopes {
+    pub global: HashMap<String, String>,
+    pub project: HashMap<String, String>,
+    pub environment: HashMap<String, String>,
+    /// Names whose values are secret in any of these scopes.
+    pub secrets: HashSet<String>,
+}
+
+impl Scopes {
+    /// The scopes that apply to `request`, in precedence order.
+    fn layers<'a>(&'a self, request: &'a RequestData) -> [(Scope, &'a HashMap<String, String>); 4] {
+        [
+            (Scope::Request, &request.variables),
+            (Scope::Environment, &self.environment),
+            (Scope::Project, &self.project),
+            (Scope::Global, &self.global),
+        ]
+    }
+
+    /// Every variable `request` can use, with the value from the scope that
+    /// takes precedence.
+    pub fn resolve(&self, request: &RequestData) -> HashMap<String, String> {
+        let mut resolved = HashMap::new();
+        for (_, variables) in self.layers(request).into_iter().rev() {
+            resolved.extend(variables.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        resolved
+    }
+
+    /// Each scope that defines `name` for `request`, with its value. The
+    /// first entry is the one that is used.
+    pub fn definitions<'a>(
+        &'a self,
+        request: &'a RequestData,
+        name: &str,
+    ) -> Vec<(Scope, &'a str)> {
+        self.layers(request)
+            .into_iter()
+            .filter_map(|(scope, variables)| Some((scope, variables.get(name)?.as_str())))
+            .collect()
+    }
+
+    /// Whether `name` is secret in any scope `request` can see. Whichever
+    /// scope's value is used, it is treated as secret, so overriding a secret
+    /// does not put the override on screen.
+    pub fn is_secret(&self, request: &RequestData, name: &str) -> bool {
+        request.secrets.contains(name) || self.secrets.contains(name)
+    }
+
+    /// Names defined in any scope `request` can see, sorted.
+    pub fn names(&self, request: &RequestData) -> Vec<String> {
+        let mut names: Vec<String> = self.resolve(request).into_keys().collect();
+        names.sort();
+        names
+    }
+}
+
+/// A built-in placeholder whose name starts with `$`. These are not stored
+/// anywhere; a fresh value is generated every time a request is sent.
+#[derive(Debug, PartialEq)]
+pub enum Dynamic {
+    /// `$uuid`: a random v4 UUID.
+    Uuid,
+    /// `$timestamp`: seconds since the Unix epoch.
+    Timestamp,
+    /// `$isoTimestamp`: the current UTC time in RFC 3339.
+    IsoTimestamp,
+    /// `$randomInt min max`: an integer in `min..=max`, 0 to 1000 by default.
+    RandomInt(i64, i64),
+    /// `$randomEmail`: a made-up address at example.com.
+    RandomEmail,
+    /// `$env.NAME`: the process environment variable `NAME`, read when the
+    /// placeholder is parsed.
+    Env(String),
+}
+
+impl Dynamic {
+    /// The built-in named by `name`, or `None` if it is not one or is missing
+    /// something it needs, such as an environment variable that is not set.
+    pub fn parse(name: &str) -> Option<Self> {
+        let mut words = name.strip_prefix('$')?.split_whitespace();
+        let dynamic = match words.next()? {
+            "uuid" => Dynamic::Uuid,
+            "timestamp" => Dynamic::Timestamp,
+            "isoTimestamp" => Dynamic::IsoTimestamp,
+            "randomEmail" => Dynamic::RandomEmail,
+            "randomInt" => {
+                let min = words.next().map_or(Some(0), |w| w.parse().ok())?;
+                let max = words.next().map_or(Some(1000), |w| w.parse().ok())?;
+                if min > max {
+                    return None;
+                }
+                Dynamic::RandomInt(min, max)
+            }
+            word => Dynamic::Env(std::env::var(word.strip_prefix("env.")?).ok()?),
+        };
+        // Only `$randomInt` takes arguments.
+        match words.next() {
+            Some(_) => None,
+            None => Some(dynamic),
+        }
+    }
+
+    pub fn generate(&self) -> String {
+        match self {
+            Dynamic::Uuid => Uuid::new_v4().to_string(),
+            Dynamic::Timestamp => Utc::now().timestamp().to_string(),
+            Dynamic::IsoTimestamp => Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            Dynamic::RandomInt(min, max) => {
+                let span = (*max as i128 - *min as i128) as u128 + 1;
+                (*min as i128 + (random() % span) as i128).to_string()
+            }
+            Dynamic::RandomEmail => {
+                format!("user.{:08x}@example.com", random() as u32)
+            }
+            Dynamic::Env(value) => value.clone(),
+        }
+    }
+}
+
+/// Random bits, taken from a v4 UUID so no other generator is needed.
+fn random() -> u128 {
+    Uuid::new_v4().as_u128()
+}
+
+/// A piece of a template: literal text, or a `{{name}}` placeholder.
+#[derive(Debug, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    /// `raw` is the placeholder as written, braces included; `name` is what
+    /// is between the braces, trimmed.
+    Placeholder {
+        raw: &'a str,
+        name: &'a str,
+    },
+}
+
+/// Splits `template` into text and placeholders. A `{{` without a matching
+/// `}}` is kept as text.
+pub fn parse(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        segments.push(Segment::Placeholder {
+            raw: &rest[start..end],
+            name: rest[start + 2..end - 2].trim(),
+        });
+        rest = &rest[end..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+/// Replaces every placeholder that has a value in `variables`. Unknown ones
+/// are left as written so they are easy to spot in what was sent.
+pub fn substitute(template: &str, variables: &HashMap<String, String>) -> String {
+    parse(template)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text,
+            Segment::Placeholder { raw, name } => variables.get(name).map_or(raw, String::as_str),
+        })
+        .collect()
+}
+
+/// Names of the placeholders in everything `request` sends, in the order
+/// they first appear.
+pub fn placeholders(request: &RequestData) -> Vec<String> {
+    let mut templates: Vec<&str> = vec![
+        request.url.as_deref().unwrap_or_default(),
+        request.body.as_deref().unwrap_or_default(),
+    ];
+    for pairs in [
+        &request.headers,
+        &request.query_params,
+        &request.path_params,
+    ] {
+        for (key, value) in pairs.iter().flatten() {
+            templates.extend([key.as_str(), value.as_str()]);
+        }
+    }
+    let auth_fields = request.auth.as_ref().map(auth::fields).unwrap_or_default();
+    templates.extend(auth_fields.iter().map(|field| field.value.as_str()));
+
+    let mut names: Vec<String> = Vec::new();
+    for segment in templates.into_iter().flat_map(parse) {
+        if let Segment::Placeholder { name, .. } = segment {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Values for the built-in placeholders in `request` that `variables` does
+/// not define, generated once so that every use within a send agrees.
+pub fn generate(
+    request: &RequestData,
+    variables: &HashMap<String, String>,
+) -> Vec<(String, String)> {
+    placeholders(request)
+        .into_iter()
+        .filter(|name| !variables.contains_key(name))
+        .filter_map(|name| {
+            let value = Dynamic::parse(&name)?.generate();
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// A copy of `request` with placeholders substituted in everything that is
+/// sent: url, headers, query and path parameters, body and auth, and the
+/// fresh values built-in placeholders were given, sorted by name. `$env.NAME`
+/// values are left out of those since they are often credentials, and they
+/// are shown and recorded with the response.
+pub fn resolve_request(
+    request: &RequestData,
+    variables: &HashMap<String, String>,
+) -> (RequestData, Vec<(String, String)>) {
+    let generated = generate(request, variables);
+    let mut variables = variables.clone();
+    variables.extend(generated.iter().cloned());
+    let variables = &variables;
+    let mut generated: Vec<_> = generated
+        .into_iter()
+        .filter(|(name, _)| !name.starts_with("$env."))
+        .collect();
+    generated.sort();
+
+    let text = |value: &Option<String>| value.as_ref().map(|v| substitute(v, variables));
+    let pairs = |pairs: &Option<Vec<(String, String)>>| {
+        pairs.as_ref().map(|pairs| {
+            pairs
+                .iter()
+                .map(|(k, v)| (substitute(k, variables), substitute(v, variables)))
+                .collect()
+        })
+    };
+
+    let resolved = RequestData {
+        url: text(&request.url),
+        headers: pairs(&request.headers),
+        query_params: pairs(&request.query_params),
+        path_params: pairs(&request.path_params),
+        body: text(&request.body),
+        auth: request
+            .auth
+            .as_ref()
+            .map(|auth| resolve_auth(auth, variables)),
+        ..request.clone()
+    };
+    (resolved, generated)
+}
+
+/// A copy of `auth` with placeholders substituted.
+pub fn resolve_auth(auth: &AuthData, variables: &HashMap<String, String>) -> AuthData {
+    let mut resolved = auth.clone();
+    for (index, field) in auth::fields(auth).into_iter().enumerate() {
+        if !matches!(field.input, Input::Choice(_)) {
+            auth::set_field(&mut resolved, index, &substitute(&field.value, variables));
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> HashMap<String, String> {
+        HashMap::from([
+            ("host".to_string(), "api.example.com".to_string()),
+            ("token".to_string(), "s3cret".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("https://{{ host }}/{{id}}?x={{"),
+            vec![
+                Segment::Text("https://"),
+                Segment::Placeholder {
+                    raw: "{{ host }}",
+                    name: "host"
+                },
+                Segment::Text("/"),
+                Segment::Placeholder {
+                    raw: "{{id}}",
+                    name: "id"
+                },
+                Segment::Text("?x={{"),
+            ]
+        );
+        assert!(parse("").is_empty());
+    }
+
+    #[test]
+    fn test_substitute_keeps_unknown_placeholders() {
+        let variables = variables();
+        assert_eq!(
+            substitute("https://{{host}}/users/{{id}}", &variables),
+            "https://api.example.com/users/{{id}}"
+        );
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let mut request = RequestData::new("get user".to_string());
+        request.url = Some("{{host}}/users/{{id}}".to_string());
+        request.headers = Some(vec![("X-Trace".to_string(), "{{ id }}".to_string())]);
+        request.auth = Some(AuthData::Basic {
+            username: "{{user}}".to_string(),
+            password: "{{password}}".to_string(),
+        });
+        assert_eq!(
+            placeholders(&request),
+            vec!["host", "id", "user", "password"]
+        );
+    }
+
+    #[test]
+    fn test_scope_precedence() {
+        let scopes = Scopes {
+            global: HashMap::from([
+                ("user".to_string(), "me".to_string()),
+                ("host".to_string(), "global.example.com".to_string()),
+            ]),
+            project: HashMap::from([("host".to_string(), "project.example.com".to_string())]),
+            environment: HashMap::from([
+                ("host".to_string(), "staging.example.com".to_string()),
+                ("id".to_string(), "1".to_string()),
+            ]),
+            secrets: HashSet::new(),
+        };
+        let mut request = RequestData::new("get user".to_string());
+        request.variables = HashMap::from([("id".to_string(), "42".to_string())]);
+
+        let resolved = scopes.resolve(&request);
+        assert_eq!(resolved["user"], "me");
+        assert_eq!(resolved["host"], "staging.example.com");
+        assert_eq!(resolved["id"], "42");
+        assert_eq!(
+            scopes.definitions(&request, "host"),
+            vec![
+                (Scope::Environment, "staging.example.com"),
+                (Scope::Project, "project.example.com"),
+                (Scope::Global, "global.example.com"),
+            ]
+        );
+        assert_eq!(scopes.names(&request), vec!["host", "id", "user"]);
+    }
+
+    #[test]
+    fn test_resolve_request() {
+        let mut request = RequestData::new("get user".to_string());
+        request.url = Some("{{host}}/users/:id".to_string());
+        request.headers = Some(vec![("X-Host".to_string(), "{{host}}".to_string())]);
+        request.query_params = Some(vec![("q".to_string(), "{{token}}".to_string())]);
+        request.path_params = Some(vec![("id".to_string(), "{{token}}".to_string())]);
+        request.body = Some(r#"{"token": "{{token}}"}"#.to_string());
+        request.auth = Some(AuthData::Bearer {
+            token: "{{token}}".to_string(),
+        });
+
+        let (resolved, _) = resolve_request(&request, &variables());
+        assert_eq!(resolved.id, request.id);
+        assert_eq!(resolved.url.as_deref(), Some("api.example.com/users/:id"));
+        assert_eq!(
+            resolved.headers,
+            Some(vec![("X-Host".to_string(), "api.example.com".to_string())])
+        );
+        assert_eq!(
+            resolved.query_params,
+            Some(vec![("q".to_string(), "s3cret".to_string())])
+        );
+        assert_eq!(
+            resolved.path_params,
+            Some(vec![("id".to_string(), "s3cret".to_string())])
+        );
+        assert_eq!(resolved.body.as_deref(), Some(r#"{"token": "s3cret"}"#));
+        assert!(resolved.variables.is_empty());
+        assert_eq!(
+            resolved.auth,
+            Some(AuthData::Bearer {
+                token: "s3cret".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_dynamic() {
+        assert_eq!(Dynamic::parse("$uuid"), Some(Dynamic::Uuid));
+        assert_eq!(
+            Dynamic::parse("$randomInt 5 10"),
+            Some(Dynamic::RandomInt(5, 10))
+        );
+        assert_eq!(
+            Dynamic::parse("$randomInt"),
+            Some(Dynamic::RandomInt(0, 1000))
+        );
+        assert_eq!(Dynamic::parse("$randomInt 10 5"), None);
+        assert_eq!(Dynamic::parse("$randomInt a"), None);
+        assert_eq!(Dynamic::parse("$uuid 1"), None);
+        assert_eq!(Dynamic::parse("$unknown"), None);
+        assert_eq!(Dynamic::parse("uuid"), None);
+
+        std::env::set_var("RURL_TEST_DYNAMIC", "from env");
+        assert_eq!(
+            Dynamic::parse("$env.RURL_TEST_DYNAMIC"),
+            Some(Dynamic::Env("from env".to_string()))
+        );
+        assert_eq!(Dynamic::parse("$env.RURL_TEST_MISSING"), None);
+    }
+
+    #[test]
+    fn test_generate_dynamic() {
+        for _ in 0..100 {
+            let value: i64 = Dynamic::RandomInt(-2, 2).generate().parse().unwrap();
+            assert!((-2..=2).contains(&value));
+        }
+        assert_eq!(
+            Dynamic::RandomInt(i64::MIN, i64::MIN).generate(),
+            i64::MIN.to_string()
+        );
+        assert!(Dynamic::RandomEmail.generate().ends_with("@example.com"));
+        assert!(Uuid::parse_str(&Dynamic::Uuid.generate()).is_ok());
+        assert!(chrono::DateTime::parse_from_rfc3339(&Dynamic::IsoTimestamp.generate()).is_ok());
+    }
+
+    #[test]
+    fn test_resolve_request_generates_fresh_values() {
+        let mut request = RequestData::new("create user".to_string());
+        request.url = Some("{{host}}/users/{{$uuid}}".to_string());
+        request.headers = Some(vec![("X-Id".to_string(), "{{ $uuid }}".to_string())]);
+        request.body = Some("{{$unknown}}".to_string());
+        request.auth = Some(AuthData::Bearer {
+            token: "{{$env.RURL_TEST_RESOLVE_TOKEN}}".to_string(),
+        });
+        std::env::set_var("RURL_TEST_RESOLVE_TOKEN", "from shell");
+
+        let (first, generated) = resolve_request(&request, &variables());
+        let id = &generated[0].1;
+        assert_eq!(generated[0].0, "$uuid");
+        assert_eq!(first.url, Some(format!("api.example.com/users/{}", id)));
+        assert_eq!(first.headers, Some(vec![("X-Id".to_string(), id.clone())]));
+        assert_eq!(first.body.as_deref(), Some("{{$unknown}}"));
+        // Used, but not handed back to be shown or recorded.
+        assert_eq!(
+            first.auth,
+            Some(AuthData::Bearer {
+                token: "from shell".to_string()
+            })
+        );
+        assert_eq!(generated.len(), 1);
+        // The request's own variables are left as they were.
+        assert_eq!(first.variables, request.variables);
+
+        let (_, again) = resolve_request(&request, &variables());
+        assert_ne!(again[0].1, *id);
+    }
+}